@@ -0,0 +1,271 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+//! 读写zip和tar.zst压缩包
+
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::error::{Error, Result};
+
+/// zstd的压缩等级
+const ZSTD_LEVEL: i32 = 3;
+
+/// 压缩包格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+pub enum ArchiveFormat {
+    /// tar.zst, 压缩率更高
+    #[default]
+    #[value(name = "tar.zst")]
+    #[serde(rename = "tar.zst")]
+    TarZst,
+    /// zip, Windows可以直接打开
+    #[serde(rename = "zip")]
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// 文件扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ArchiveFormat::TarZst => "tar.zst",
+            ArchiveFormat::Zip => "zip",
+        }
+    }
+
+    /// 按照文件扩展名判断格式
+    ///
+    /// # 示例
+    /// ```
+    /// use std::path::Path;
+    /// use mcscs::archive::ArchiveFormat;
+    /// assert_eq!(ArchiveFormat::from_path(Path::new("a.zip")), Some(ArchiveFormat::Zip));
+    /// assert_eq!(ArchiveFormat::from_path(Path::new("a.tar.zst")), Some(ArchiveFormat::TarZst));
+    /// assert_eq!(ArchiveFormat::from_path(Path::new("a.txt")), None);
+    /// ```
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+            Some(ArchiveFormat::TarZst)
+        } else {
+            None
+        }
+    }
+
+    /// 按照文件开头的魔数判断格式
+    pub fn detect(path: &Path) -> Result<Self> {
+        let mut magic = [0u8; 4];
+        File::open(path)?.read_exact(&mut magic)?;
+        match magic {
+            [0x50, 0x4b, 0x03, 0x04] | [0x50, 0x4b, 0x05, 0x06] => Ok(ArchiveFormat::Zip),
+            [0x28, 0xb5, 0x2f, 0xfd] => Ok(ArchiveFormat::TarZst),
+            _ => Err(Error::Archive(format!(
+                "{}不是zip或者tar.zst压缩包",
+                path.display()
+            ))),
+        }
+    }
+}
+
+/// 写入压缩包
+pub enum ArchiveWriter {
+    TarZst(tar::Builder<zstd::Encoder<'static, File>>),
+    Zip(ZipWriter<File>),
+}
+
+impl ArchiveWriter {
+    pub fn create(path: &Path, format: ArchiveFormat) -> Result<Self> {
+        let file = File::create(path)?;
+        Ok(match format {
+            ArchiveFormat::TarZst => {
+                let mut builder = tar::Builder::new(zstd::Encoder::new(file, ZSTD_LEVEL)?);
+                builder.follow_symlinks(false);
+                ArchiveWriter::TarZst(builder)
+            }
+            ArchiveFormat::Zip => ArchiveWriter::Zip(ZipWriter::new(file)),
+        })
+    }
+
+    fn zip_options() -> FileOptions {
+        FileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .large_file(true)
+    }
+
+    /// 添加一个文件, `name`是压缩包里的路径
+    pub fn add_file(&mut self, name: &str, path: &Path) -> Result<()> {
+        match self {
+            ArchiveWriter::TarZst(builder) => builder.append_path_with_name(path, name)?,
+            ArchiveWriter::Zip(zip) => {
+                #[allow(unused_mut)]
+                let mut options = Self::zip_options();
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    options = options.unix_permissions(fs::metadata(path)?.permissions().mode());
+                }
+                zip.start_file(name, options)?;
+                io::copy(&mut File::open(path)?, zip)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 添加一个文件夹, 用于保留空文件夹
+    pub fn add_dir(&mut self, name: &str, path: &Path) -> Result<()> {
+        match self {
+            ArchiveWriter::TarZst(builder) => builder.append_dir(name, path)?,
+            ArchiveWriter::Zip(zip) => zip.add_directory(name, Self::zip_options())?,
+        }
+        Ok(())
+    }
+
+    /// 添加内容不在磁盘上的文件
+    pub fn add_data(&mut self, name: &str, data: &[u8]) -> Result<()> {
+        match self {
+            ArchiveWriter::TarZst(builder) => {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(data.len() as u64);
+                header.set_mode(0o644);
+                header.set_mtime(chrono::Local::now().timestamp() as u64);
+                header.set_cksum();
+                builder.append_data(&mut header, name, data)?;
+            }
+            ArchiveWriter::Zip(zip) => {
+                zip.start_file(name, Self::zip_options())?;
+                zip.write_all(data)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 写入压缩包的结尾
+    pub fn finish(self) -> Result<()> {
+        match self {
+            ArchiveWriter::TarZst(builder) => {
+                builder.into_inner()?.finish()?.sync_all()?;
+            }
+            ArchiveWriter::Zip(mut zip) => {
+                zip.finish()?.sync_all()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 把文件夹里的文件按顺序写入压缩包, `exclude`返回`true`的文件和文件夹会被跳过
+///
+/// 压缩包里的路径以`prefix`开头, 使用`/`分隔, 返回写入的文件数量
+pub fn add_dir_all(
+    writer: &mut ArchiveWriter,
+    dir: &Path,
+    prefix: &str,
+    exclude: &dyn Fn(&str) -> bool,
+) -> Result<u64> {
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    let mut count = 0;
+    for entry in entries {
+        let name = format!("{prefix}{}", entry.file_name().to_string_lossy());
+        if exclude(&name) {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            writer.add_dir(&format!("{name}/"), &entry.path())?;
+            count += add_dir_all(writer, &entry.path(), &format!("{name}/"), exclude)?;
+        } else if file_type.is_file() {
+            writer.add_file(&name, &entry.path())?;
+            count += 1;
+        } else {
+            warn!("跳过不是普通文件的{}", entry.path().display());
+        }
+    }
+    Ok(count)
+}
+
+/// 解压压缩包, 格式按照文件开头判断, 跳过路径不安全(绝对路径, 包含`..`)的文件
+pub fn extract(archive: &Path, dest: &Path) -> Result<()> {
+    fs::create_dir_all(dest)?;
+    match ArchiveFormat::detect(archive)? {
+        ArchiveFormat::TarZst => {
+            let mut tar = tar::Archive::new(zstd::Decoder::new(File::open(archive)?)?);
+            tar.set_preserve_permissions(true);
+            tar.unpack(dest)?;
+        }
+        ArchiveFormat::Zip => extract_zip(archive, dest)?,
+    }
+    Ok(())
+}
+
+fn extract_zip(archive: &Path, dest: &Path) -> Result<()> {
+    let mut archive = ZipArchive::new(File::open(archive)?)?;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let Some(path) = entry.enclosed_name().map(|path| dest.join(path)) else {
+            warn!("跳过不安全的路径: {}", entry.name());
+            continue;
+        };
+        if entry.is_dir() {
+            fs::create_dir_all(&path)?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut entry, &mut File::create(&path)?)?;
+        #[cfg(unix)]
+        if let Some(mode) = entry.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode))?;
+        }
+    }
+    Ok(())
+}
+
+/// 完整地读取一遍压缩包, 检查压缩包没有损坏, 返回文件数量
+pub fn verify(archive: &Path) -> Result<u64> {
+    let mut count = 0;
+    match ArchiveFormat::detect(archive)? {
+        ArchiveFormat::TarZst => {
+            let mut tar = tar::Archive::new(zstd::Decoder::new(File::open(archive)?)?);
+            for entry in tar.entries()? {
+                io::copy(&mut entry?, &mut io::sink())?;
+                count += 1;
+            }
+        }
+        ArchiveFormat::Zip => {
+            let mut zip = ZipArchive::new(File::open(archive)?)?;
+            for i in 0..zip.len() {
+                // 读到结尾时会检查CRC32
+                io::copy(&mut zip.by_index(i)?, &mut io::sink())?;
+                count += 1;
+            }
+        }
+    }
+    Ok(count)
+}
+
+/// 压缩包的默认文件名, 例如: `survival-20240314-120000.tar.zst`
+pub fn archive_name(name: &str, format: ArchiveFormat) -> PathBuf {
+    PathBuf::from(format!(
+        "{name}-{}.{}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        format.extension()
+    ))
+}
@@ -8,13 +8,16 @@
 
 use std::path::PathBuf;
 use std::process::Command;
-use std::{env, error::Error, thread::sleep, time::Duration};
+use std::{env, thread::sleep, time::Duration};
 
 use indicatif::{ProgressBar, ProgressStyle};
 use jsonrpc::Client;
 use log::{info, trace, warn};
 use serde_json::{json, Value};
 
+use crate::error::{Error, Result};
+use crate::workspace::Workspace;
+
 /// 给aria2c发送JSON-RPC请求
 ///
 /// # 参数
@@ -27,7 +30,7 @@ use serde_json::{json, Value};
 /// let version = call_aria2c_rpc("aria2.getVersion", json!([])).unwrap();
 /// println!("{version}");
 /// ```
-pub fn call_aria2c_rpc(method: &str, params: Value) -> Result<Value, Box<dyn Error>> {
+pub fn call_aria2c_rpc(method: &str, params: Value) -> Result<Value> {
     let mut params = params.clone();
     params
         .as_array_mut()
@@ -37,6 +40,9 @@ pub fn call_aria2c_rpc(method: &str, params: Value) -> Result<Value, Box<dyn Err
     let args = jsonrpc::arg(params);
     let request = client.build_request(method, Some(&args));
     let response = Client::send_request(&client, request)?;
+    if let Some(err) = response.error {
+        return Err(Error::Aria2(format!("{method}: {}", err.message)));
+    }
     Ok(json!(response.result))
 }
 
@@ -52,6 +58,15 @@ fn format_size(size: u64) -> String {
     format!("{size:.2}{}", units[index])
 }
 
+/// 读取aria2c返回的数字, aria2c的数字都是字符串
+fn status_number(status: &Value, key: &str) -> Result<u64> {
+    status[key]
+        .as_str()
+        .unwrap_or("0")
+        .parse::<u64>()
+        .map_err(|err| Error::Aria2(format!("{key}: {err}")))
+}
+
 /// 使用aria2c下载文件
 ///
 /// # 示例
@@ -61,7 +76,7 @@ fn format_size(size: u64) -> String {
 ///     println!("{}", file_path.display());
 /// }
 /// ```
-pub fn download(url: &str) -> Result<PathBuf, Box<dyn Error>> {
+pub fn download(url: &str) -> Result<PathBuf> {
     // 调用 aria2.addUri 来添加下载任务，并获取 GID
     let gid_json = call_aria2c_rpc("aria2.addUri", json!([[url]]))?;
     let gid = gid_json.as_str().unwrap_or_default();
@@ -87,25 +102,15 @@ pub fn download(url: &str) -> Result<PathBuf, Box<dyn Error>> {
             ]),
         )?;
         // 获取已完成的大小，总大小，下载速度，剩余时间等信息
-        let completed = status["completedLength"]
-            .as_str()
-            .unwrap_or("0")
-            .parse::<u64>()?;
-        let total = status["totalLength"]
-            .as_str()
-            .unwrap_or("0")
-            .parse::<u64>()?;
-        let speed = status["downloadSpeed"]
-            .as_str()
-            .unwrap_or("0")
-            .parse::<u64>()?;
+        let completed = status_number(&status, "completedLength")?;
+        let total = status_number(&status, "totalLength")?;
+        let speed = status_number(&status, "downloadSpeed")?;
 
         pb.set_length(total);
         pb.set_position(completed);
 
         let mut eta = String::new();
-        if speed != 0 {
-            let remaining_time_secs = (total - completed) / speed;
+        if let Some(remaining_time_secs) = (total - completed).checked_div(speed) {
             if remaining_time_secs != 0 {
                 let remaining_hours = remaining_time_secs / 3600;
                 let remaining_minutes = (remaining_time_secs % 3600) / 60;
@@ -126,10 +131,7 @@ pub fn download(url: &str) -> Result<PathBuf, Box<dyn Error>> {
             format_size(speed),
             format_size(completed),
             format_size(total),
-            status["connections"]
-                .as_str()
-                .unwrap_or("0")
-                .parse::<u64>()?
+            status_number(&status, "connections")?
         ));
         let download_status = status["status"].as_str().unwrap_or("error");
         if download_status == "complete" {
@@ -140,17 +142,17 @@ pub fn download(url: &str) -> Result<PathBuf, Box<dyn Error>> {
                 pb.finish_with_message(format!("下载完成: {file_path}"));
                 return Ok(PathBuf::from(file_path));
             }
-            return Err("下载错误".into());
+            return Err(Error::Aria2(format!("下载错误: {url}")));
         }
         if download_status == "error" || download_status == "removed" {
-            return Err("下载错误".into());
+            return Err(Error::Aria2(format!("下载错误({download_status}): {url}")));
         }
         if download_status == "paused" {
             warn!("下载任务被暂停, 正在重新启动...");
             if call_aria2c_rpc("aria2.unpause", json!([gid]))? == gid_json {
                 info!("下载任务已重新启动");
             } else {
-                return Err("下载错误".into());
+                return Err(Error::Aria2(format!("无法重新启动下载任务: {url}")));
             }
         }
         sleep(Duration::from_millis(175));
@@ -159,7 +161,7 @@ pub fn download(url: &str) -> Result<PathBuf, Box<dyn Error>> {
 
 /// 如果没有安装aria2c,自动从GitHub下载最新的aria2c
 #[cfg(target_os = "windows")]
-pub async fn install_aria2c() {
+pub async fn install_aria2c() -> Result<()> {
     use std::{
         fs::{self, File},
         io,
@@ -168,63 +170,61 @@ pub async fn install_aria2c() {
     use zip::ZipArchive;
 
     if get_aria2c_execute().is_err() {
-        let path = env::current_dir()
-            .expect("install_aria2c()")
-            .join("MCSCS")
-            .join("aria2c");
+        let path = Workspace::current()?.aria2c_dir();
         println!("开始下载Aria2c");
         let url = {
             let request = reqwest::Client::new()
                 .get("https://api.github.com/repos/aria2/aria2/releases")
                 .header("User-Agent", "MCSCS/1.0");
-            let response = request.send().await.expect("install_aria2c()");
-            let response = response.json::<Value>().await.expect("install_aria2c()");
+            let response = request.send().await?.error_for_status()?;
+            let response = response.json::<Value>().await?;
             let mut result = String::new();
-            for data in response[0]["assets"].as_array().expect("install_aria2c()") {
-                let name = data["name"].as_str().expect("install_aria2c()");
+            for data in response[0]["assets"].as_array().into_iter().flatten() {
+                let name = data["name"].as_str().unwrap_or_default();
                 if name.contains("win") && name.contains("64bit") {
                     result = data["browser_download_url"]
                         .as_str()
-                        .expect("install_aria2c()")
+                        .unwrap_or_default()
                         .to_string();
                 }
             }
+            if result.is_empty() {
+                return Err(Error::Aria2("GitHub上没有找到可用的aria2c".into()));
+            }
             result
         };
 
-        let response = reqwest::get(url).await.expect("install_aria2c()");
-        if !response.status().is_success() {
-            panic!("install_aria2c()");
-        }
+        let response = reqwest::get(url).await?.error_for_status()?;
 
-        let mut file = File::create(path.join("aria2c.zip")).expect("install_aria2c()");
-        io::copy(
-            &mut response.bytes().await.expect("install_aria2c()").as_ref(),
-            &mut file,
-        )
-        .expect("install_aria2c()");
+        fs::create_dir_all(&path)?;
+        let mut file = File::create(path.join("aria2c.zip"))?;
+        io::copy(&mut response.bytes().await?.as_ref(), &mut file)?;
         println!("Aria2c下载完成");
 
-        let file = File::open(path.join("aria2c.zip")).expect("install_aria2c()");
-        let mut archive = ZipArchive::new(file).expect("install_aria2c()");
+        let file = File::open(path.join("aria2c.zip"))?;
+        let mut archive =
+            ZipArchive::new(file).map_err(|err| Error::Aria2(format!("解压aria2c失败: {err}")))?;
 
         for i in 0..archive.len() {
-            let mut file = archive.by_index(i).expect("install_aria2c()");
+            let mut file = archive
+                .by_index(i)
+                .map_err(|err| Error::Aria2(format!("解压aria2c失败: {err}")))?;
             if file.name().ends_with("aria2c.exe") {
-                let mut outfile = File::create(&path.join("aria2c.exe")).expect("install_aria2c()");
-                io::copy(&mut file, &mut outfile).expect("install_aria2c()");
+                let mut outfile = File::create(path.join("aria2c.exe"))?;
+                io::copy(&mut file, &mut outfile)?;
             }
         }
-        fs::remove_file(path.join("aria2c.zip")).expect("install_aria2c()");
+        fs::remove_file(path.join("aria2c.zip"))?;
         println!("解压完成");
     }
+    Ok(())
 }
 
 /// 如果无法获取aria2c可执行程序则报错让用户自己安装aria2c
 #[cfg(not(target_os = "windows"))]
-pub async fn install_aria2c() {
+pub async fn install_aria2c() -> Result<()> {
     if get_aria2c_execute().is_err() {
-        panic!(
+        return Err(Error::Aria2(
             "aria2c未安装, 请安装后再次运行本程序:
 Ubuntu/Debian:
 sudo apt update
@@ -237,8 +237,10 @@ Fedora
 sudo dnf install aria2
 openSUSE
 sudo zypper install aria2"
-        );
+                .into(),
+        ));
     }
+    Ok(())
 }
 
 /// 获取aria2c可执行文件
@@ -249,17 +251,14 @@ sudo zypper install aria2"
 /// let aria2c = get_aria2c_execute().unwrap();
 /// println!("{}", aria2c.display());
 /// ```
-pub fn get_aria2c_execute() -> Result<PathBuf, Box<dyn Error>> {
+pub fn get_aria2c_execute() -> Result<PathBuf> {
     #[cfg(target_os = "windows")]
     let execute = "aria2c.exe";
     #[cfg(not(target_os = "windows"))]
     let execute = "aria2c";
 
     // 先检测有没有内置的aria2c
-    let aria2c_path = env::current_dir()?
-        .join("MCSCS")
-        .join("aria2c")
-        .join(execute);
+    let aria2c_path = Workspace::current()?.aria2c_dir().join(execute);
     if aria2c_path.exists() {
         let mut process = Command::new(&aria2c_path);
         process.arg("-v");
@@ -281,5 +280,5 @@ pub fn get_aria2c_execute() -> Result<PathBuf, Box<dyn Error>> {
     }
 
     // 没找到
-    Err("未找到aria2c可执行文件".into())
+    Err(Error::Aria2("未找到aria2c可执行文件".into()))
 }
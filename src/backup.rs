@@ -0,0 +1,730 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+//! 世界备份
+//!
+//! 备份保存在`MCSCS/backups/<name>/<ID>.tar.zst`(或者`.zip`), ID是备份的时间(`%Y%m%d-%H%M%S`).
+//! 增量备份只在这里保存清单`<ID>.snapshot.json`, 文件内容分块保存在所有服务器共享的[`Store`]里.
+//! 压缩包里是`server.properties`里`level-name`对应的世界文件夹, 以及Bukkit系核心的`<level-name>_nether`和`<level-name>_the_end`.
+//!
+//! 服务器正在运行时, 备份前通过控制台(或者RCON)执行`save-off`和`save-all flush`, 备份后执行`save-on`.
+//! 备份的设置(格式, 保留规则, 自动备份的间隔)保存在同一个文件夹的`backup.json`, 自动备份由宿主进程执行.
+//!
+//! 恢复备份前会自动备份当前的世界, 再通过重命名替换世界文件夹, 也可以把备份恢复为一个新的服务器
+
+use std::{
+    cmp::Reverse,
+    collections::HashSet,
+    fmt,
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use chrono::{Local, NaiveDateTime};
+use clap::ValueEnum;
+use log::{error, info, trace, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::archive::{add_dir_all, extract, verify, ArchiveFormat, ArchiveWriter};
+use crate::control::{is_running, stop_server, DEFAULT_STOP_TIMEOUT};
+use crate::error::{Error, Result};
+use crate::pages::clone::clone_server;
+use crate::properties::{Properties, PROPERTIES_FILE};
+use crate::rcon::{self, RconClient};
+use crate::server::{is_local_file, ServerConfig};
+use crate::session::Session;
+use crate::store::{Snapshot, Store};
+use crate::workspace::Workspace;
+
+/// 备份设置的文件名, 在备份文件夹里
+pub const SETTINGS_FILE: &str = "backup.json";
+
+/// 备份ID的格式, 也是备份的时间
+pub const ID_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// 等待服务器保存世界的时间
+const SAVE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 服务器保存完世界后输出的内容
+const SAVED_MESSAGE: &str = "Saved the game";
+
+/// 恢复时使用的临时文件夹的前缀, 在服务器文件夹里
+pub const STAGING_PREFIX: &str = ".restore-";
+
+/// 自动备份检查设置的间隔
+const SCHEDULER_TICK: Duration = Duration::from_secs(1);
+
+/// 备份格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+pub enum BackupFormat {
+    /// 完整的tar.zst压缩包
+    #[default]
+    #[value(name = "tar.zst")]
+    #[serde(rename = "tar.zst")]
+    TarZst,
+    /// 完整的zip压缩包
+    #[serde(rename = "zip")]
+    Zip,
+    /// 增量备份, 没有变化的文件和区块不占用空间
+    #[serde(rename = "incremental")]
+    Incremental,
+}
+
+impl fmt::Display for BackupFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackupFormat::Incremental => write!(f, "incremental"),
+            _ => write!(f, "{}", self.extension()),
+        }
+    }
+}
+
+impl BackupFormat {
+    /// 备份文件的扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            BackupFormat::TarZst => ArchiveFormat::TarZst.extension(),
+            BackupFormat::Zip => ArchiveFormat::Zip.extension(),
+            BackupFormat::Incremental => "snapshot.json",
+        }
+    }
+
+    /// 对应的压缩包格式, 增量备份返回`None`
+    pub fn archive(&self) -> Option<ArchiveFormat> {
+        match self {
+            BackupFormat::TarZst => Some(ArchiveFormat::TarZst),
+            BackupFormat::Zip => Some(ArchiveFormat::Zip),
+            BackupFormat::Incremental => None,
+        }
+    }
+
+    /// 按照文件名判断格式
+    ///
+    /// # 示例
+    /// ```
+    /// use std::path::Path;
+    /// use mcscs::backup::BackupFormat;
+    /// let format = BackupFormat::from_path(Path::new("20240314-120000.snapshot.json"));
+    /// assert_eq!(format, Some(BackupFormat::Incremental));
+    /// assert_eq!(BackupFormat::from_path(Path::new("backup.json")), None);
+    /// ```
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy();
+        if name.ends_with(".snapshot.json") {
+            return Some(BackupFormat::Incremental);
+        }
+        match ArchiveFormat::from_path(path)? {
+            ArchiveFormat::TarZst => Some(BackupFormat::TarZst),
+            ArchiveFormat::Zip => Some(BackupFormat::Zip),
+        }
+    }
+}
+
+/// 保留规则: 每小时, 每天, 每周各保留最新的一个备份, 最多保留的数量
+///
+/// 最新的备份总是会保留
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Retention {
+    pub hourly: u32,
+    pub daily: u32,
+    pub weekly: u32,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            hourly: 24,
+            daily: 7,
+            weekly: 4,
+        }
+    }
+}
+
+/// 服务器的备份设置, 保存在`MCSCS/backups/<name>/backup.json`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupSettings {
+    pub format: BackupFormat,
+    pub retention: Retention,
+    /// 自动备份的间隔(秒), `None`表示不自动备份
+    pub interval: Option<u64>,
+}
+
+impl BackupSettings {
+    /// 读取服务器的备份设置, 没有设置过时返回默认值
+    pub fn load(name: &str) -> Result<Self> {
+        let path = Workspace::current()?.backup_dir(name).join(SETTINGS_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// 保存服务器的备份设置
+    pub fn save(&self, name: &str) -> Result<()> {
+        let dir = Workspace::current()?.backup_dir(name);
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(SETTINGS_FILE), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// 一个备份
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Backup {
+    /// 备份的ID, 例如: `20240314-120000`
+    pub id: String,
+    #[serde(skip)]
+    pub created: NaiveDateTime,
+    pub format: BackupFormat,
+    pub path: PathBuf,
+    /// 占用的空间(字节), 增量备份是清单和这次新保存的块的大小
+    pub size: u64,
+}
+
+impl Backup {
+    /// 从备份文件夹里的文件读取备份信息, 不是备份的文件返回`None`
+    ///
+    /// 无法读取的增量备份清单也会返回, 只是不计算新保存的块, 由[`Backup::verify`]报告
+    fn from_path(path: &Path) -> Option<Self> {
+        let format = BackupFormat::from_path(path)?;
+        let file_name = path.file_name()?.to_str()?;
+        let id = file_name.strip_suffix(&format!(".{}", format.extension()))?;
+        let created = NaiveDateTime::parse_from_str(id, ID_FORMAT).ok()?;
+        let mut size = fs::metadata(path).ok()?.len();
+        if format == BackupFormat::Incremental {
+            size += Snapshot::load(path).map_or(0, |snapshot| snapshot.added);
+        }
+        Some(Self {
+            id: id.to_string(),
+            created,
+            format,
+            path: path.to_path_buf(),
+            size,
+        })
+    }
+
+    /// 把备份里的世界文件夹解压到`dest`
+    pub fn extract(&self, dest: &Path) -> Result<()> {
+        match self.format {
+            BackupFormat::Incremental => {
+                Snapshot::load(&self.path)?.checkout(&Store::current()?, dest)
+            }
+            _ => extract(&self.path, dest),
+        }
+    }
+
+    /// 检查备份是否完整, 返回发现的问题
+    ///
+    /// 压缩包会完整地读取一遍, 增量备份会检查清单和用到的每一个块
+    pub fn verify(&self) -> Result<Vec<String>> {
+        match self.format {
+            BackupFormat::Incremental => Ok(match Snapshot::load(&self.path) {
+                Ok(snapshot) => snapshot.verify(&Store::current()?),
+                Err(err) => vec![format!("无法读取清单{}: {err}", self.path.display())],
+            }),
+            _ => Ok(verify(&self.path)
+                .err()
+                .map(|err| err.to_string())
+                .into_iter()
+                .collect()),
+        }
+    }
+}
+
+/// 列出服务器的所有备份, 最新的在前面
+pub fn list_backups(name: &str) -> Result<Vec<Backup>> {
+    let dir = Workspace::current()?.backup_dir(name);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if let Some(backup) = path.is_file().then(|| Backup::from_path(&path)).flatten() {
+            backups.push(backup);
+        }
+    }
+    backups.sort_by_key(|backup| Reverse(backup.created));
+    Ok(backups)
+}
+
+/// 返回服务器的世界文件夹, 相对于服务器文件夹
+///
+/// 世界文件夹是`server.properties`里的`level-name`(默认为`world`), 以及存在时的`<level-name>_nether`和`<level-name>_the_end`
+pub fn world_dirs(server_dir: &Path) -> Result<Vec<String>> {
+    let properties = Properties::load(&server_dir.join(PROPERTIES_FILE))?;
+    let level = match properties.get("level-name") {
+        Some(level) if !level.is_empty() => level,
+        _ => "world",
+    };
+    let worlds = [
+        level.to_string(),
+        format!("{level}_nether"),
+        format!("{level}_the_end"),
+    ]
+    .into_iter()
+    .filter(|world| server_dir.join(world).is_dir())
+    .collect::<Vec<_>>();
+    if worlds.is_empty() {
+        return Err(Error::Backup(format!(
+            "{}里没有世界文件夹{level}, 请先启动一次服务器",
+            server_dir.display()
+        )));
+    }
+    Ok(worlds)
+}
+
+/// 按照保留规则返回需要删除的备份
+///
+/// 从新到旧, 每个小时(天, 周)里最新的备份会被保留, 直到达到保留的数量
+///
+/// # 示例
+/// ```
+/// use std::path::PathBuf;
+/// use chrono::NaiveDateTime;
+/// use mcscs::backup::{expired_backups, Backup, BackupFormat, Retention, ID_FORMAT};
+/// let backups = ["20240314-120000", "20240314-113000", "20240314-110000"]
+///     .map(|id| Backup {
+///         id: id.to_string(),
+///         created: NaiveDateTime::parse_from_str(id, ID_FORMAT).unwrap(),
+///         format: BackupFormat::TarZst,
+///         path: PathBuf::from(format!("{id}.tar.zst")),
+///         size: 0,
+///     });
+/// let retention = Retention { hourly: 2, daily: 0, weekly: 0 };
+/// let expired = expired_backups(&backups, &retention);
+/// assert_eq!(expired.len(), 1);
+/// assert_eq!(expired[0].id, "20240314-110000");
+/// ```
+pub fn expired_backups<'a>(backups: &'a [Backup], retention: &Retention) -> Vec<&'a Backup> {
+    let mut sorted = backups.iter().collect::<Vec<_>>();
+    sorted.sort_by_key(|backup| Reverse(backup.created));
+    let mut keep = vec![false; sorted.len()];
+    if let Some(newest) = keep.first_mut() {
+        *newest = true;
+    }
+    for (count, bucket) in [
+        (retention.hourly, "%Y%m%d%H"),
+        (retention.daily, "%Y%m%d"),
+        (retention.weekly, "%G%V"),
+    ] {
+        let mut left = count;
+        let mut last = None;
+        for (i, backup) in sorted.iter().enumerate() {
+            if left == 0 {
+                break;
+            }
+            let key = backup.created.format(bucket).to_string();
+            if last.as_ref() != Some(&key) {
+                keep[i] = true;
+                left -= 1;
+                last = Some(key);
+            }
+        }
+    }
+    sorted
+        .into_iter()
+        .zip(keep)
+        .filter(|(_, keep)| !keep)
+        .map(|(backup, _)| backup)
+        .collect()
+}
+
+/// 按照保留规则删除旧的备份, 返回删除的备份
+///
+/// 删除了增量备份时会调用[`prune_store`]删除没有使用的块
+pub fn prune_backups(name: &str, retention: &Retention) -> Result<Vec<Backup>> {
+    let backups = list_backups(name)?;
+    let mut removed = Vec::new();
+    for backup in expired_backups(&backups, retention) {
+        info!("删除旧的备份{}", backup.path.display());
+        fs::remove_file(&backup.path)?;
+        removed.push(backup.clone());
+    }
+    if removed
+        .iter()
+        .any(|backup| backup.format == BackupFormat::Incremental)
+    {
+        prune_store()?;
+    }
+    Ok(removed)
+}
+
+/// 删除存储里没有被任何服务器的增量备份使用的块, 返回删除的块数量和字节数
+pub fn prune_store() -> Result<(usize, u64)> {
+    let backups_dir = Workspace::current()?.backups_dir();
+    if !backups_dir.exists() {
+        return Ok((0, 0));
+    }
+    let mut referenced = HashSet::new();
+    for entry in fs::read_dir(backups_dir)? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        for entry in fs::read_dir(path)? {
+            let path = entry?.path();
+            if BackupFormat::from_path(&path) == Some(BackupFormat::Incremental) {
+                referenced.extend(Snapshot::load(&path)?.chunks().cloned());
+            }
+        }
+    }
+    Store::current()?.prune(&referenced)
+}
+
+/// 正在运行的服务器的控制台, 用于在备份时暂停自动保存
+enum Console {
+    Session(Session),
+    Rcon(RconClient),
+}
+
+impl Console {
+    /// 服务器没有运行时返回`None`
+    fn connect(name: &str) -> Result<Option<Self>> {
+        if !is_running(name)? {
+            return Ok(None);
+        }
+        if let Some(session) = Session::load(name)? {
+            return Ok(Some(Console::Session(session)));
+        }
+        match rcon::connect_server(name) {
+            Ok(client) => Ok(Some(Console::Rcon(client))),
+            Err(err) => Err(Error::Backup(format!(
+                "服务器{name}正在运行, 但是找不到它的会话, 也无法通过RCON连接({err})"
+            ))),
+        }
+    }
+
+    fn command(&mut self, command: &str) -> Result<()> {
+        trace!("backup -> {command}");
+        match self {
+            Console::Session(session) => session.send_command(command),
+            Console::Rcon(client) => client.command(command).map(drop),
+        }
+    }
+
+    /// 保存世界并等待保存完成
+    fn save_all(&mut self) -> Result<()> {
+        match self {
+            Console::Session(session) => {
+                let offset = fs::metadata(&session.log).map_or(0, |meta| meta.len());
+                session.send_command("save-all flush")?;
+                wait_for_log(&session.log, offset, SAVED_MESSAGE, SAVE_TIMEOUT)
+            }
+            // RCON在保存完成后才返回
+            Console::Rcon(client) => client.command("save-all flush").map(drop),
+        }
+    }
+}
+
+/// 等待会话日志`offset`之后出现`message`
+fn wait_for_log(log: &Path, offset: u64, message: &str, timeout: Duration) -> Result<()> {
+    let start = Instant::now();
+    let mut output = String::new();
+    loop {
+        let mut file = File::open(log)?;
+        file.seek(SeekFrom::Start(offset))?;
+        output.clear();
+        file.read_to_string(&mut output)?;
+        if output.contains(message) {
+            return Ok(());
+        }
+        if start.elapsed() >= timeout {
+            return Err(Error::Backup(format!(
+                "服务器在{}秒内没有保存完世界",
+                timeout.as_secs()
+            )));
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+}
+
+/// 把世界文件夹写入备份, 先写入临时文件, 完成后再重命名, 不会留下不完整的备份
+fn write_backup(
+    path: &Path,
+    format: BackupFormat,
+    server_dir: &Path,
+    worlds: &[String],
+    previous: Option<&Backup>,
+) -> Result<()> {
+    let part = path.with_extension("part");
+    let result = (|| {
+        match format.archive() {
+            Some(archive) => {
+                let mut writer = ArchiveWriter::create(&part, archive)?;
+                for world in worlds {
+                    let dir = server_dir.join(world);
+                    writer.add_dir(&format!("{world}/"), &dir)?;
+                    add_dir_all(&mut writer, &dir, &format!("{world}/"), &is_local_file)?;
+                }
+                writer.finish()?;
+            }
+            None => {
+                let previous = previous
+                    .map(|backup| Snapshot::load(&backup.path))
+                    .transpose()?;
+                let snapshot = Snapshot::create(
+                    &Store::current()?,
+                    server_dir,
+                    worlds,
+                    previous.as_ref(),
+                    &is_local_file,
+                )?;
+                snapshot.save(&part)?;
+            }
+        }
+        fs::rename(&part, path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&part);
+    }
+    result
+}
+
+/// 备份服务器的世界, 服务器正在运行时先暂停自动保存并保存世界
+///
+/// # 示例
+/// ```no_run
+/// use mcscs::backup::{create_backup, BackupFormat};
+/// let backup = create_backup("survival", BackupFormat::Incremental).unwrap();
+/// println!("{}: {}", backup.id, backup.path.display());
+/// ```
+pub fn create_backup(name: &str, format: BackupFormat) -> Result<Backup> {
+    let server_dir = Workspace::current()?.server_dir(name);
+    if !server_dir.exists() {
+        return Err(Error::ServerNotFound(name.to_string()));
+    }
+    let worlds = world_dirs(&server_dir)?;
+    let dir = Workspace::current()?.backup_dir(name);
+    fs::create_dir_all(&dir)?;
+    // ID精确到秒, 同一秒里已经有备份时等到下一秒
+    let (id, path) = loop {
+        let id = Local::now().format(ID_FORMAT).to_string();
+        let path = dir.join(format!("{id}.{}", format.extension()));
+        if !path.exists() {
+            break (id, path);
+        }
+        thread::sleep(Duration::from_millis(200));
+    };
+
+    let mut console = Console::connect(name)?;
+    if let Some(console) = console.as_mut() {
+        console.command("save-off")?;
+    }
+    let result = match console.as_mut() {
+        Some(console) => console.save_all(),
+        None => Ok(()),
+    }
+    .and_then(|()| {
+        // 增量备份时没有变化的文件直接使用上一次增量备份的块
+        let previous = list_backups(name)?
+            .into_iter()
+            .find(|backup| backup.format == BackupFormat::Incremental);
+        write_backup(&path, format, &server_dir, &worlds, previous.as_ref())
+    });
+    if let Some(console) = console.as_mut() {
+        if let Err(err) = console.command("save-on") {
+            error!("无法恢复服务器{name}的自动保存: {err}");
+            result?;
+            return Err(Error::Backup(format!(
+                "备份已完成, 但是无法恢复自动保存, 请在控制台执行save-on({err})"
+            )));
+        }
+    }
+    result?;
+    let backup =
+        Backup::from_path(&path).ok_or_else(|| Error::Backup(format!("无法读取备份{id}")))?;
+    info!(
+        "备份{name}的{} -> {}",
+        worlds.join(", "),
+        backup.path.display()
+    );
+    Ok(backup)
+}
+
+/// 按照ID查找服务器的备份
+pub fn find_backup(name: &str, id: &str) -> Result<Backup> {
+    list_backups(name)?
+        .into_iter()
+        .find(|backup| backup.id == id)
+        .ok_or_else(|| Error::Backup(format!("服务器{name}没有备份{id}")))
+}
+
+/// 服务器文件夹里恢复时使用的临时文件夹
+fn staging_dir(server_dir: &Path, kind: &str, id: &str) -> PathBuf {
+    server_dir.join(format!("{STAGING_PREFIX}{kind}-{id}"))
+}
+
+/// 压缩包里的世界文件夹, 也就是解压后顶层的文件夹
+fn extracted_worlds(dir: &Path) -> Result<Vec<String>> {
+    let mut worlds = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            worlds.push(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+    worlds.sort();
+    Ok(worlds)
+}
+
+/// 用`staging`里的世界文件夹替换服务器的世界文件夹, 原来的世界文件夹先移动到`old`
+///
+/// 只使用同一个分区里的重命名, 任何一步失败时都会把原来的世界文件夹移回去
+fn swap_worlds(server_dir: &Path, staging: &Path, old: &Path) -> Result<()> {
+    let worlds = extracted_worlds(staging)?;
+    let mut replaced = world_dirs(server_dir).unwrap_or_default();
+    replaced.extend(worlds.iter().cloned());
+    replaced.sort();
+    replaced.dedup();
+    replaced.retain(|world| server_dir.join(world).exists());
+
+    fs::create_dir_all(old)?;
+    let mut moved_old = Vec::new();
+    let mut moved_new = Vec::new();
+    let result = (|| {
+        for world in &replaced {
+            fs::rename(server_dir.join(world), old.join(world))?;
+            moved_old.push(world);
+        }
+        for world in &worlds {
+            fs::rename(staging.join(world), server_dir.join(world))?;
+            moved_new.push(world);
+        }
+        Ok(())
+    })();
+    if let Err(err) = result {
+        error!("替换世界文件夹失败, 还原原来的世界: {err}");
+        for world in moved_new {
+            let _ = fs::rename(server_dir.join(world), staging.join(world));
+        }
+        for world in moved_old {
+            if let Err(err) = fs::rename(old.join(world), server_dir.join(world)) {
+                error!("无法还原{world}, 原来的世界在{}: {err}", old.display());
+            }
+        }
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// 用备份替换服务器的世界, 返回恢复前自动创建的备份(服务器没有世界时为`None`)
+///
+/// 服务器正在运行时, `stop`为`true`会先关闭服务器, 否则返回错误.
+/// 备份先解压到服务器文件夹里的临时文件夹, 然后通过重命名替换世界文件夹
+///
+/// # 示例
+/// ```no_run
+/// use mcscs::backup::restore_backup;
+/// if let Some(snapshot) = restore_backup("survival", "20240314-120000", false).unwrap() {
+///     println!("恢复前的世界已备份为{}", snapshot.id);
+/// }
+/// ```
+pub fn restore_backup(name: &str, id: &str, stop: bool) -> Result<Option<Backup>> {
+    let server_dir = Workspace::current()?.server_dir(name);
+    if !server_dir.exists() {
+        return Err(Error::ServerNotFound(name.to_string()));
+    }
+    let backup = find_backup(name, id)?;
+    if is_running(name)? {
+        if !stop {
+            return Err(Error::Backup(format!(
+                "服务器{name}正在运行, 请先关闭服务器再恢复"
+            )));
+        }
+        info!(
+            "恢复备份前关闭服务器{name}: {}",
+            stop_server(name, DEFAULT_STOP_TIMEOUT)?
+        );
+    }
+    let snapshot = match world_dirs(&server_dir) {
+        Ok(_) => Some(create_backup(name, backup.format)?),
+        Err(_) => None,
+    };
+
+    let staging = staging_dir(&server_dir, "new", id);
+    let old = staging_dir(&server_dir, "old", id);
+    for dir in [&staging, &old] {
+        if dir.exists() {
+            fs::remove_dir_all(dir)?;
+        }
+    }
+    let result = backup
+        .extract(&staging)
+        .and_then(|()| swap_worlds(&server_dir, &staging, &old));
+    let _ = fs::remove_dir_all(&staging);
+    result?;
+    fs::remove_dir_all(&old)?;
+    info!("已把服务器{name}恢复到备份{id}");
+    Ok(snapshot)
+}
+
+/// 把备份恢复为一个新的服务器, 返回新服务器的配置
+///
+/// 使用[`clone_server`]复制原来服务器除了世界以外的文件(核心, 插件, 配置等), 再解压备份里的世界
+pub fn restore_to_new_server(name: &str, id: &str, new_name: &str) -> Result<ServerConfig> {
+    let backup = find_backup(name, id)?;
+    let server = clone_server(name, new_name, false)?;
+    let new_dir = Workspace::current()?.server_dir(new_name);
+    if let Err(err) = backup.extract(&new_dir) {
+        let _ = fs::remove_dir_all(&new_dir);
+        return Err(err);
+    }
+    info!("已把服务器{name}的备份{id}恢复为新的服务器{new_name}");
+    Ok(server)
+}
+
+/// 按照服务器的备份设置备份并删除旧的备份, 返回新的备份和删除的备份
+pub fn backup_server(name: &str) -> Result<(Backup, Vec<Backup>)> {
+    let settings = BackupSettings::load(name)?;
+    let backup = create_backup(name, settings.format)?;
+    let removed = prune_backups(name, &settings.retention)?;
+    Ok((backup, removed))
+}
+
+/// 启动自动备份的线程, 按照`backup.json`里的`interval`定时执行[`backup_server`], `stopped`为`true`后退出
+///
+/// 每次检查时都会重新读取设置, 修改后不需要重启服务器
+pub fn spawn_scheduler(name: &str, stopped: Arc<AtomicBool>) -> JoinHandle<()> {
+    let name = name.to_string();
+    thread::spawn(move || {
+        let mut last = Instant::now();
+        while !stopped.load(Ordering::SeqCst) {
+            thread::sleep(SCHEDULER_TICK);
+            let interval = match BackupSettings::load(&name) {
+                Ok(settings) => settings.interval,
+                Err(err) => {
+                    warn!("无法读取服务器{name}的备份设置: {err}");
+                    continue;
+                }
+            };
+            match interval.filter(|interval| *interval > 0) {
+                Some(interval) if last.elapsed() >= Duration::from_secs(interval) => {}
+                _ => continue,
+            }
+            last = Instant::now();
+            match backup_server(&name) {
+                Ok((backup, removed)) => info!(
+                    "自动备份{name}: {}, 删除了{}个旧的备份",
+                    backup.id,
+                    removed.len()
+                ),
+                Err(err) => error!("自动备份{name}失败: {err}"),
+            }
+        }
+    })
+}
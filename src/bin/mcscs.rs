@@ -6,39 +6,99 @@
  * Vestibulum commodo. Ut rhoncus gravida arcu.
  */
 
-use std::error::Error;
+use std::process::ExitCode;
 
-use mcscs::pages::{config, create, delete, import, init, start};
+use clap::Parser;
+use log::error;
+
+use mcscs::cli::{self, Cli};
+use mcscs::pages::{
+    backup, clone, config, create, delete, export, import, init, start, stop, update,
+};
 use mcscs::select::select_option;
 use mcscs::utils::{clear_console, pause};
+use mcscs::workspace::Workspace;
+use mcscs::{Error, Result};
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    init::main().await?;
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = async {
+        Workspace::set_current(Workspace::resolve(cli.home.as_deref())?);
+        match cli.command {
+            Some(command) => cli::run(command, cli.output).await,
+            None => {
+                init::main().await?;
+                menu().await
+            }
+        }
+    }
+    .await;
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            error!("{err}");
+            eprintln!("错误: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// 交互式菜单
+async fn menu() -> Result<()> {
     let options = vec![
         "启动服务器",
+        "关闭服务器",
         "创建服务器",
         "配置服务器",
         "删除服务器",
-        // "导入服务器",
+        "导入服务器",
+        "导出服务器",
+        "备份服务器",
+        "复制服务器",
+        "更新服务器核心",
         "退出",
     ];
     loop {
-        let selection = select_option("请选择一个选项(请按上下键切换, Enter确认)", &options)?;
+        let selection = match select_option("请选择一个选项(请按上下键切换, Enter确认)", &options)
+        {
+            Ok(selection) => selection,
+            Err(Error::Cancelled) => return Ok(()),
+            Err(err) => return Err(err),
+        };
         if selection == options.len() - 1 {
             return Ok(());
         }
         clear_console();
-        if selection == 0 {
-            start::main()?;
+        let result = if selection == 0 {
+            start::main()
         } else if selection == 1 {
-            create::main().await?;
+            stop::main()
         } else if selection == 2 {
-            config::main()?;
+            create::main().await
         } else if selection == 3 {
-            delete::main()?;
+            config::main()
         } else if selection == 4 {
-            import::main();
+            delete::main()
+        } else if selection == 5 {
+            import::main()
+        } else if selection == 6 {
+            export::main()
+        } else if selection == 7 {
+            backup::main()
+        } else if selection == 8 {
+            clone::main()
+        } else {
+            update::main().await
+        };
+        // 出错时显示错误信息并返回菜单
+        match result {
+            Ok(()) => {}
+            Err(Error::Cancelled) => println!("{}", Error::Cancelled),
+            Err(err) => {
+                error!("{err}");
+                println!("错误: {err}");
+            }
         }
         pause();
         clear_console();
@@ -6,23 +6,26 @@
  * Vestibulum commodo. Ut rhoncus gravida arcu.
  */
 
-use std::{env, fs, process::Command};
+use std::{fs, process::Command};
 
 use chrono::Local;
 
+use mcscs::workspace::Workspace;
+
 fn main() {
-    let current_dir = env::current_dir().unwrap().join("MCSCS");
-    let log_path = current_dir
-        .join("logs")
+    let workspace = Workspace::resolve(None).expect("无法决定MCSCS工作目录");
+    Workspace::set_current(workspace.clone());
+    let log_path = workspace
+        .logs_dir()
         .join(Local::now().format("%Y%m%d%H%M").to_string());
     fs::create_dir_all(&log_path).expect("创建logs文件夹失败");
-    fs::create_dir_all(current_dir.join("downloads")).expect("创建MCSCS/downloads文件夹失败");
+    fs::create_dir_all(workspace.downloads_dir()).expect("创建MCSCS/downloads文件夹失败");
     #[cfg(target_os = "windows")]
-    let execute = current_dir.join("aria2c").join("aria2c.exe");
+    let execute = workspace.aria2c_dir().join("aria2c.exe");
     #[cfg(not(any(target_os = "windows")))]
     let execute = "aria2c";
     let mut aria2c = Command::new(execute);
-    aria2c.arg(format!("--dir={}", current_dir.join("downloads").display()));
+    aria2c.arg(format!("--dir={}", workspace.downloads_dir().display()));
     aria2c.arg(format!("--log={}", log_path.join("aria2c.log").display()));
     aria2c.arg("--enable-rpc=true");
     aria2c.arg("--rpc-listen-port=6800");
@@ -30,7 +33,7 @@ fn main() {
     aria2c.arg("--rpc-secret=MCSCS");
     aria2c.arg(format!(
         "--conf-path={}",
-        current_dir.join("aria2c").join("aria2c.conf").display()
+        workspace.aria2c_dir().join("aria2c.conf").display()
     ));
     aria2c.arg("--console-log-level=info");
     // aria2c.arg("--quiet=true");
@@ -0,0 +1,1169 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+use clap::{Args, Parser, Subcommand};
+use log::warn;
+
+use crate::archive::{archive_name, ArchiveFormat};
+use crate::backup::BackupFormat;
+use crate::control::{is_running, kill_server, restart_server, stop_server, DEFAULT_STOP_TIMEOUT};
+use crate::error::{Error, Result};
+use crate::java::{get_java_version, load_java_lists, Java};
+use crate::migrate::SCHEMA_VERSION;
+use crate::mojang::Mojang;
+use crate::output::{self, format_bytes, format_size, OutdatedEntry, OutputFormat, ServerEntry};
+use crate::pages::{clone, config, create, delete, export, import, init, start, update};
+use crate::properties::{validate_property, Properties, PROPERTIES_FILE};
+use crate::provider::{CoreProvider, ProviderKind};
+use crate::server::{load_servers_lists, save_servers_lists, CoreInfo, ServerConfig};
+use crate::session::{self, Session};
+use crate::status::{server_status, DEFAULT_TIMEOUT};
+use crate::supervisor::RestartPolicy;
+use crate::workspace::Workspace;
+use crate::{backup, query, rcon};
+
+/// Minecraft Server Config Script for Rust, 不带子命令运行时进入交互式菜单
+#[derive(Debug, Parser)]
+#[command(name = "mcscs", version)]
+pub struct Cli {
+    /// MCSCS的工作目录, 默认为当前目录下的MCSCS文件夹
+    #[arg(long, global = true)]
+    pub home: Option<PathBuf>,
+
+    /// 输出格式, 用于list, status, query, inspect, java, cores, backup, outdated
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub output: OutputFormat,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// 在后台启动服务器并连接控制台
+    Start {
+        /// 服务器名称
+        name: String,
+        /// 同意Minecraft EULA(https://aka.ms/MinecraftEULA)
+        #[arg(long)]
+        accept_eula: bool,
+        /// 启动后不连接控制台
+        #[arg(long, short)]
+        detach: bool,
+        #[command(flatten)]
+        supervise: SuperviseArgs,
+    },
+    /// 连接正在运行的服务器的控制台, 输入:detach断开连接
+    Attach {
+        /// 服务器名称
+        name: String,
+    },
+    /// 向正在运行的服务器的控制台发送一条命令
+    Send {
+        /// 服务器名称
+        name: String,
+        /// 命令, 例如: say hello
+        #[arg(required = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+    /// 通过RCON执行命令, 没有指定命令时进入交互式的RCON控制台
+    Rcon {
+        /// 服务器名称
+        name: String,
+        /// 在server.properties里启用RCON并生成密码, 重启服务器后生效
+        #[arg(long)]
+        enable: bool,
+        /// 命令, 例如: whitelist add Steve
+        #[arg(allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+    /// 关闭服务器, 先在控制台输入stop, 超时后依次发送SIGTERM和SIGKILL
+    Stop {
+        /// 服务器名称
+        name: String,
+        /// 等待服务器正常关闭的时间(秒)
+        #[arg(long, default_value_t = DEFAULT_STOP_TIMEOUT.as_secs())]
+        timeout: u64,
+    },
+    /// 重启服务器, 关闭的过程和stop一样
+    Restart {
+        /// 服务器名称
+        name: String,
+        /// 等待服务器正常关闭的时间(秒)
+        #[arg(long, default_value_t = DEFAULT_STOP_TIMEOUT.as_secs())]
+        timeout: u64,
+    },
+    /// 立即强制结束服务器(SIGKILL), 可能会损坏存档
+    Kill {
+        /// 服务器名称
+        name: String,
+    },
+    /// 在当前进程运行服务器的宿主, 由start在后台调用
+    #[command(hide = true)]
+    Host {
+        /// 服务器名称
+        name: String,
+        #[command(flatten)]
+        supervise: SuperviseArgs,
+    },
+    /// 创建服务器
+    Create(Box<CreateArgs>),
+    /// 把服务器导出为压缩包, 可以在其他电脑上使用import导入
+    Export {
+        /// 服务器名称
+        name: String,
+        /// 压缩包的路径, 默认为当前目录下的<name>-<时间>.<格式>
+        path: Option<PathBuf>,
+        /// 压缩包格式, 默认按照路径的扩展名判断
+        #[arg(long, value_enum)]
+        format: Option<ArchiveFormat>,
+        /// 不导出logs和控制台日志
+        #[arg(long)]
+        exclude_logs: bool,
+        /// 不导出crash-reports
+        #[arg(long)]
+        exclude_crash_reports: bool,
+        /// 不导出cache, libraries等启动时会重新下载的文件
+        #[arg(long)]
+        exclude_caches: bool,
+        /// 其他不导出的文件或文件夹, 相对于服务器文件夹, 可以指定多次
+        #[arg(long)]
+        exclude: Vec<String>,
+    },
+    /// 导入已有的服务器文件夹, 或者zip, tar.zst压缩包(包括export导出的压缩包)
+    Import {
+        /// 服务器文件夹或者压缩包的路径
+        source: PathBuf,
+        /// 服务器名称
+        #[arg(long)]
+        name: String,
+        /// 服务器核心的文件名, 默认使用server.jar或者唯一的jar文件
+        #[arg(long)]
+        jar: Option<String>,
+        /// Java可执行程序的路径, 默认使用满足版本要求的Java
+        #[arg(long)]
+        java: Option<PathBuf>,
+        /// 移动文件夹而不是复制
+        #[arg(long = "move")]
+        move_files: bool,
+        /// JVM初始堆内存, 默认使用导出时的设置, 不是导出的服务器时为1G
+        #[arg(long)]
+        xms: Option<String>,
+        /// JVM最大堆内存, 默认使用导出时的设置, 不是导出的服务器时为4G
+        #[arg(long)]
+        xmx: Option<String>,
+    },
+    /// 把服务器复制为一个新的服务器, 新服务器使用其他服务器没有使用的端口
+    Clone {
+        /// 要复制的服务器名称
+        source: String,
+        /// 新服务器的名称
+        name: String,
+        /// 不复制世界, 新服务器第一次启动时生成新的世界
+        #[arg(long)]
+        no_worlds: bool,
+    },
+    /// 更换服务器核心的构建版本, 没有指定版本时更新到同一个Minecraft版本最新的构建版本
+    Update {
+        /// 服务器名称
+        name: String,
+        /// 只列出比当前更新的构建版本
+        #[arg(long, conflicts_with_all = ["build", "mc_version", "rollback"])]
+        list: bool,
+        /// 使用的构建版本, 例如: build594
+        #[arg(long)]
+        build: Option<String>,
+        /// 更换Minecraft版本, 世界升级后无法回滚
+        #[arg(long)]
+        mc_version: Option<String>,
+        /// 回滚到更新前的核心
+        #[arg(long, conflicts_with_all = ["build", "mc_version"])]
+        rollback: bool,
+        /// 更换Minecraft版本时不询问
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// 备份服务器的世界, 没有指定操作时立即备份并按照保留规则删除旧的备份
+    Backup {
+        /// 服务器名称
+        name: String,
+        #[command(subcommand)]
+        action: Option<BackupAction>,
+    },
+    /// 从备份恢复服务器的世界, 恢复前会自动备份当前的世界
+    Restore {
+        /// 服务器名称
+        name: String,
+        /// 备份的ID, 使用`mcscs backup <name> list`查看
+        id: String,
+        /// 服务器正在运行时先关闭服务器
+        #[arg(long)]
+        stop: bool,
+        /// 恢复为一个新的服务器, 原来的服务器不受影响
+        #[arg(long, value_name = "NAME", conflicts_with = "stop")]
+        to: Option<String>,
+    },
+    /// 查看或修改服务器配置
+    Config {
+        /// 服务器名称
+        name: String,
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// 删除服务器
+    Delete {
+        /// 服务器名称
+        name: String,
+        /// 不询问直接删除
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// 重命名服务器, 服务器正在运行时不能重命名
+    Rename {
+        /// 服务器名称
+        name: String,
+        /// 新的名称
+        new_name: String,
+    },
+    /// 列出所有服务器
+    List,
+    /// 查询服务器的在线状态, 玩家数量, MOTD, 版本和延迟
+    Status {
+        /// 服务器名称
+        name: String,
+        /// 超时时间(秒)
+        #[arg(long, default_value_t = DEFAULT_TIMEOUT.as_secs())]
+        timeout: u64,
+    },
+    /// 使用查询协议获取完整的玩家列表, 地图和插件, 需要enable-query=true
+    Query {
+        /// 服务器名称
+        name: String,
+        /// 只查询基本状态
+        #[arg(long)]
+        basic: bool,
+        /// 超时时间(秒)
+        #[arg(long, default_value_t = DEFAULT_TIMEOUT.as_secs())]
+        timeout: u64,
+    },
+    /// 显示一个服务器的详细信息
+    Inspect {
+        /// 服务器名称
+        name: String,
+    },
+    /// 列出检测到的Java
+    Java,
+    /// 列出可以下载的核心
+    Cores {
+        /// 核心的来源
+        #[arg(long, value_enum, default_value_t)]
+        provider: ProviderKind,
+        /// 包括快照版本, 只对mojang有效
+        #[arg(long)]
+        snapshots: bool,
+    },
+    /// 检查所有服务器的核心是否有新的构建版本和更新的Minecraft版本
+    Outdated {
+        /// 有服务器需要更新构建版本或者无法检查时返回非0的退出码, 用于CI
+        #[arg(long)]
+        exit_code: bool,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct SuperviseArgs {
+    /// 崩溃后自动重启, 正常关闭(stop)时不会重启
+    #[arg(long)]
+    pub supervise: bool,
+    /// 在--restart-window时间内最多自动重启的次数
+    #[arg(long, default_value_t = RestartPolicy::default().max_restarts, requires = "supervise")]
+    pub max_restarts: u32,
+    /// 统计重启次数的时间窗口(秒)
+    #[arg(long, default_value_t = RestartPolicy::default().window.as_secs(), requires = "supervise")]
+    pub restart_window: u64,
+}
+
+impl SuperviseArgs {
+    /// 没有指定--supervise时返回`None`
+    fn policy(&self) -> Option<RestartPolicy> {
+        self.supervise.then(|| RestartPolicy {
+            max_restarts: self.max_restarts,
+            window: Duration::from_secs(self.restart_window),
+            ..Default::default()
+        })
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct CreateArgs {
+    /// 服务器名称
+    #[arg(long)]
+    pub name: String,
+    /// 核心名称, 例如: Mohist
+    #[arg(long)]
+    pub core: String,
+    /// Minecraft版本, 例如: 1.20.1
+    #[arg(long)]
+    pub mc_version: String,
+    /// 构建版本, 例如: build593
+    #[arg(long)]
+    pub build: String,
+    /// 下载核心的来源
+    #[arg(long, value_enum, default_value_t)]
+    pub provider: ProviderKind,
+    /// Java可执行程序的路径, 默认使用检测到的第一个Java
+    #[arg(long)]
+    pub java: Option<PathBuf>,
+    /// JVM初始堆内存, 例如: 1G
+    #[arg(long)]
+    pub xms: String,
+    /// JVM最大堆内存, 例如: 4G
+    #[arg(long)]
+    pub xmx: String,
+    /// 输入和输出的编码
+    #[arg(long, default_value = "utf-8")]
+    pub encoding: String,
+    /// JVM参数, 可以指定多次
+    #[arg(long = "jvm-arg", allow_hyphen_values = true, default_values_t = ["-Dlog4j2.formatMsgNoLookups=true".to_string()])]
+    pub jvm_args: Vec<String>,
+    /// 服务器参数, 可以指定多次
+    #[arg(long = "server-arg", allow_hyphen_values = true, default_values_t = ["--nogui".to_string()])]
+    pub server_args: Vec<String>,
+    /// 同意Minecraft EULA(https://aka.ms/MinecraftEULA)
+    #[arg(long)]
+    pub accept_eula: bool,
+    /// 启用RCON并生成密码
+    #[arg(long)]
+    pub enable_rcon: bool,
+    /// 服务器端口, 默认使用从25565开始第一个没有被其他服务器使用的端口
+    #[arg(long)]
+    pub port: Option<u16>,
+    /// 世界种子
+    #[arg(long, allow_hyphen_values = true)]
+    pub level_seed: Option<String>,
+    /// 世界类型, 例如: default, flat, largeBiomes, amplified
+    #[arg(long)]
+    pub level_type: Option<String>,
+    /// 默认游戏模式: survival, creative, adventure, spectator
+    #[arg(long)]
+    pub gamemode: Option<String>,
+    /// 难度: peaceful, easy, normal, hard
+    #[arg(long)]
+    pub difficulty: Option<String>,
+    /// 正版验证, true或者false
+    #[arg(long)]
+    pub online_mode: Option<bool>,
+    /// 最大玩家数量
+    #[arg(long)]
+    pub max_players: Option<u32>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigAction {
+    /// 显示服务器的所有配置
+    Show,
+    /// 显示一个配置项
+    Get {
+        /// 配置项, 可用: Xms, Xmx, encoding, jvm_args, server_args, java
+        key: String,
+    },
+    /// 修改一个配置项
+    Set {
+        /// 配置项, 可用: Xms, Xmx, encoding, jvm_args, server_args, java
+        key: String,
+        /// 新的值, 参数用空格分隔
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+    },
+    /// 查看或修改server.properties
+    Property {
+        #[command(subcommand)]
+        action: PropertyAction,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BackupAction {
+    /// 列出所有备份
+    List,
+    /// 按照保留规则删除旧的备份, 并删除增量备份没有使用的块
+    Prune,
+    /// 检查备份是否完整, 没有指定ID时检查所有备份
+    Verify {
+        /// 备份的ID
+        id: Option<String>,
+    },
+    /// 查看或修改备份设置, 没有指定参数时显示当前设置
+    Settings {
+        /// 备份格式, incremental为增量备份
+        #[arg(long, value_enum)]
+        format: Option<BackupFormat>,
+        /// 每小时保留一个备份, 最多保留的数量
+        #[arg(long)]
+        keep_hourly: Option<u32>,
+        /// 每天保留一个备份, 最多保留的数量
+        #[arg(long)]
+        keep_daily: Option<u32>,
+        /// 每周保留一个备份, 最多保留的数量
+        #[arg(long)]
+        keep_weekly: Option<u32>,
+        /// 服务器运行时自动备份的间隔(秒), 0表示不自动备份
+        #[arg(long)]
+        interval: Option<u64>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PropertyAction {
+    /// 显示server.properties里的所有配置项
+    List,
+    /// 显示一个配置项
+    Get {
+        /// 配置项, 例如: server-port
+        key: String,
+    },
+    /// 修改一个配置项, 常用的配置项会检查值是否有效
+    Set {
+        /// 配置项, 例如: server-port
+        key: String,
+        /// 新的值
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+    },
+}
+
+/// `list`查询每个服务器状态的超时时间
+const LIST_STATUS_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// 读取指定的服务器配置
+fn load_server(name: &str) -> Result<ServerConfig> {
+    load_servers_lists(Some(name))?
+        .remove(name)
+        .ok_or_else(|| Error::ServerNotFound(name.to_string()))
+}
+
+/// 读取指定服务器正在运行的会话
+fn running_session(name: &str) -> Result<Session> {
+    load_server(name)?;
+    Session::load(name)?.ok_or_else(|| Error::Other(format!("服务器{name}没有在运行")))
+}
+
+impl CreateArgs {
+    /// 转换为服务器配置
+    fn to_config(&self) -> Result<ServerConfig> {
+        let java = match &self.java {
+            Some(path) => Java {
+                path: path.clone(),
+                version: get_java_version(path)?,
+            },
+            None => load_java_lists()?
+                .into_iter()
+                .next()
+                .ok_or_else(|| Error::Java("没有检测到Java, 请使用--java指定".into()))?,
+        };
+        Ok(ServerConfig {
+            schema_version: SCHEMA_VERSION,
+            name: self.name.clone(),
+            java,
+            jvm_args: self.jvm_args.clone(),
+            encoding: self.encoding.to_lowercase(),
+            xms: create::parse_memory("Xms", &self.xms)?,
+            xmx: create::parse_memory("Xmx", &self.xmx)?,
+            info: CoreInfo {
+                core: self.core.clone(),
+                mc_version: self.mc_version.clone(),
+                build_version: self.build.clone(),
+                provider: self.provider,
+            },
+            server_args: self.server_args.clone(),
+        })
+    }
+}
+
+/// 执行子命令, 只有需要下载文件的子命令才会启动aria2c
+pub async fn run(command: Command, format: OutputFormat) -> Result<()> {
+    match &command {
+        Command::Create(_) => init::main().await?,
+        _ => {
+            init::init_workspace().await?;
+        }
+    }
+    match command {
+        Command::Start {
+            name,
+            accept_eula,
+            detach,
+            supervise,
+        } => {
+            let server = load_server(&name)?;
+            if accept_eula {
+                start::accept_eula(&Workspace::current()?.server_dir(&name))?;
+            }
+            let session = session::spawn_host(&server, supervise.policy().as_ref())?;
+            if detach {
+                println!("服务器{name}已在后台启动, 使用`mcscs attach {name}`连接控制台");
+                Ok(())
+            } else {
+                session.attach()
+            }
+        }
+        Command::Attach { name } => running_session(&name)?.attach(),
+        Command::Send { name, command } => running_session(&name)?.send_command(&command.join(" ")),
+        Command::Rcon {
+            name,
+            enable,
+            command,
+        } => {
+            load_server(&name)?;
+            if enable {
+                let (port, _) = rcon::enable_rcon(&name)?;
+                println!("已启用RCON, 端口: {port}, 重启服务器后生效");
+                if command.is_empty() {
+                    return Ok(());
+                }
+            }
+            let mut client = rcon::connect_server(&name)?;
+            if command.is_empty() {
+                client.shell()
+            } else {
+                println!("{}", client.command(&command.join(" "))?.trim_end());
+                Ok(())
+            }
+        }
+        Command::Stop { name, timeout } => {
+            load_server(&name)?;
+            println!("{}", stop_server(&name, Duration::from_secs(timeout))?);
+            Ok(())
+        }
+        Command::Restart { name, timeout } => {
+            let server = load_server(&name)?;
+            println!("{}", restart_server(&server, Duration::from_secs(timeout))?);
+            println!("服务器{name}已重新启动");
+            Ok(())
+        }
+        Command::Kill { name } => {
+            load_server(&name)?;
+            kill_server(&name)?;
+            println!("服务器{name}已强制结束");
+            Ok(())
+        }
+        Command::Host { name, supervise } => {
+            session::host(&load_server(&name)?, supervise.policy().as_ref())
+        }
+        Command::Create(args) => {
+            let server = args.to_config()?;
+            let settings = create::WorldSettings {
+                port: Some(match args.port {
+                    Some(port) => port,
+                    None => create::free_server_port(&server.name)?,
+                }),
+                level_seed: args.level_seed.clone(),
+                level_type: args.level_type.clone(),
+                gamemode: args.gamemode.clone(),
+                difficulty: args.difficulty.clone(),
+                online_mode: args.online_mode,
+                max_players: args.max_players,
+            };
+            // 下载核心前先检查, 避免写入失败时留下没有设置的服务器
+            for (key, value) in settings.properties() {
+                validate_property(key, &value)?;
+            }
+            create::create_server(&server, args.provider.provider().as_ref()).await?;
+            create::write_world_settings(&server.name, &settings)?;
+            if args.accept_eula {
+                start::accept_eula(&Workspace::current()?.server_dir(&server.name))?;
+            }
+            if args.enable_rcon {
+                let (port, _) = rcon::enable_rcon(&server.name)?;
+                println!("已启用RCON, 端口: {port}");
+            }
+            println!("服务器{}创建成功", server.name);
+            Ok(())
+        }
+        Command::Export {
+            name,
+            path,
+            format,
+            exclude_logs,
+            exclude_crash_reports,
+            exclude_caches,
+            exclude,
+        } => {
+            let format = format
+                .or_else(|| path.as_deref().and_then(ArchiveFormat::from_path))
+                .unwrap_or_default();
+            let path = path.unwrap_or_else(|| archive_name(&name, format));
+            let options = export::ExportOptions {
+                format,
+                exclude_logs,
+                exclude_crash_reports,
+                exclude_caches,
+                exclude,
+            };
+            let count = export::export_server(&name, &path, &options)?;
+            println!("已导出{count}个文件到{}", path.display());
+            Ok(())
+        }
+        Command::Import {
+            source,
+            name,
+            jar,
+            java,
+            move_files,
+            xms,
+            xmx,
+        } => {
+            let java = match java {
+                Some(path) => Some(Java {
+                    version: get_java_version(&path)?,
+                    path,
+                }),
+                None => None,
+            };
+            let options = import::ImportOptions {
+                jar,
+                java,
+                move_files,
+                xms: xms
+                    .map(|xms| create::parse_memory("Xms", &xms))
+                    .transpose()?,
+                xmx: xmx
+                    .map(|xmx| create::parse_memory("Xmx", &xmx))
+                    .transpose()?,
+            };
+            let server = import::import_server(&source, &name, &options)?;
+            let info = &server.info;
+            println!(
+                "服务器{name}导入成功: {} {} {}, Java {}",
+                info.core, info.mc_version, info.build_version, server.java.version
+            );
+            Ok(())
+        }
+        Command::Clone {
+            source,
+            name,
+            no_worlds,
+        } => {
+            clone::clone_server(&source, &name, !no_worlds)?;
+            let properties = Properties::load(
+                &Workspace::current()?
+                    .server_dir(&name)
+                    .join(PROPERTIES_FILE),
+            )?;
+            println!(
+                "已把服务器{source}复制为{name}, 端口: {}",
+                properties.get("server-port").unwrap_or("25565")
+            );
+            Ok(())
+        }
+        Command::Update {
+            name,
+            list,
+            build,
+            mc_version,
+            rollback,
+            yes,
+        } => {
+            let info = load_server(&name)?.info;
+            // 更换Minecraft版本后世界会升级, 必须确认
+            let confirm_version = |version: &str| {
+                if version == info.mc_version || yes {
+                    return Ok(());
+                }
+                Err(Error::Other(format!(
+                    "Minecraft版本会从{}更换为{version}, 服务器启动后世界会按照新的版本升级, 之后即使回滚核心也无法恢复, 请先备份世界并使用--yes确认",
+                    info.mc_version
+                )))
+            };
+            if rollback {
+                let backup = update::core_backup(&name)?
+                    .ok_or_else(|| Error::Other(format!("服务器{name}没有更新前的核心")))?;
+                confirm_version(&backup.mc_version)?;
+                let server = update::rollback_core(&name)?;
+                println!("服务器{name}已回滚到{}", server.info.build_version);
+                return Ok(());
+            }
+            let mc_version = mc_version.unwrap_or_else(|| info.mc_version.clone());
+            let kind = update::update_provider(&info).await?;
+            let builds = kind.provider().builds(&info.core, &mc_version).await?;
+            if list {
+                let rows = update::newer_builds(&info.build_version, &builds)
+                    .iter()
+                    .map(|build| vec![build.version.clone(), build.update_time.clone()])
+                    .collect::<Vec<_>>();
+                if rows.is_empty() {
+                    println!("{name}已经在使用最新的构建版本{}", info.build_version);
+                } else {
+                    print!("{}", output::format_table(&["构建版本", "更新时间"], &rows));
+                }
+                return Ok(());
+            }
+            let build = match build {
+                Some(build) => build,
+                None => builds
+                    .first()
+                    .map(|build| build.version.clone())
+                    .ok_or_else(|| {
+                        Error::Other(format!(
+                            "{kind}上没有{} {mc_version}可用的构建版本",
+                            info.core
+                        ))
+                    })?,
+            };
+            if mc_version == info.mc_version && build == info.build_version {
+                println!("{name}已经在使用最新的构建版本{build}");
+                return Ok(());
+            }
+            confirm_version(&mc_version)?;
+            init::main().await?;
+            let server = update::update_core(&name, &mc_version, &build).await?;
+            println!(
+                "服务器{name}的核心已更新为{} {} {}, 使用`mcscs update {name} --rollback`回滚",
+                server.info.core, server.info.mc_version, server.info.build_version
+            );
+            Ok(())
+        }
+        Command::Backup { name, action } => {
+            load_server(&name)?;
+            match action {
+                None => {
+                    let (backup, removed) = backup::backup_server(&name)?;
+                    println!(
+                        "已备份到{}({})",
+                        backup.path.display(),
+                        format_size(backup.size)
+                    );
+                    if !removed.is_empty() {
+                        println!("删除了{}个旧的备份", removed.len());
+                    }
+                    Ok(())
+                }
+                Some(BackupAction::List) => output::print(
+                    format,
+                    &backup::list_backups(&name)?,
+                    &["ID", "格式", "大小", "路径"],
+                    |backups| {
+                        backups
+                            .iter()
+                            .map(|backup| {
+                                vec![
+                                    backup.id.clone(),
+                                    backup.format.to_string(),
+                                    format_size(backup.size),
+                                    backup.path.display().to_string(),
+                                ]
+                            })
+                            .collect()
+                    },
+                ),
+                Some(BackupAction::Prune) => {
+                    let retention = backup::BackupSettings::load(&name)?.retention;
+                    let removed = backup::prune_backups(&name, &retention)?;
+                    println!("删除了{}个旧的备份", removed.len());
+                    let (chunks, bytes) = backup::prune_store()?;
+                    if chunks > 0 {
+                        println!("删除了{chunks}个没有使用的块, 释放了{}", format_size(bytes));
+                    }
+                    Ok(())
+                }
+                Some(BackupAction::Verify { id }) => {
+                    let backups = match id {
+                        Some(id) => vec![backup::find_backup(&name, &id)?],
+                        None => backup::list_backups(&name)?,
+                    };
+                    let mut broken = 0;
+                    for backup in &backups {
+                        let problems = backup.verify()?;
+                        if problems.is_empty() {
+                            println!("{}: 正常", backup.id);
+                        } else {
+                            broken += 1;
+                            println!("{}: 已损坏", backup.id);
+                            for problem in problems {
+                                println!("  {problem}");
+                            }
+                        }
+                    }
+                    if broken > 0 {
+                        return Err(Error::Backup(format!(
+                            "{broken}/{}个备份已损坏",
+                            backups.len()
+                        )));
+                    }
+                    Ok(())
+                }
+                Some(BackupAction::Settings {
+                    format: archive_format,
+                    keep_hourly,
+                    keep_daily,
+                    keep_weekly,
+                    interval,
+                }) => {
+                    let mut settings = backup::BackupSettings::load(&name)?;
+                    let changed = archive_format.is_some()
+                        || keep_hourly.is_some()
+                        || keep_daily.is_some()
+                        || keep_weekly.is_some()
+                        || interval.is_some();
+                    if let Some(archive_format) = archive_format {
+                        settings.format = archive_format;
+                    }
+                    let retention = &mut settings.retention;
+                    retention.hourly = keep_hourly.unwrap_or(retention.hourly);
+                    retention.daily = keep_daily.unwrap_or(retention.daily);
+                    retention.weekly = keep_weekly.unwrap_or(retention.weekly);
+                    if let Some(interval) = interval {
+                        settings.interval = (interval > 0).then_some(interval);
+                    }
+                    if changed {
+                        settings.save(&name)?;
+                    }
+                    output::print(format, &settings, &["设置", "值"], |settings| {
+                        let retention = &settings.retention;
+                        vec![
+                            vec!["格式".to_string(), settings.format.to_string()],
+                            vec!["每小时保留".to_string(), retention.hourly.to_string()],
+                            vec!["每天保留".to_string(), retention.daily.to_string()],
+                            vec!["每周保留".to_string(), retention.weekly.to_string()],
+                            vec![
+                                "自动备份".to_string(),
+                                match settings.interval {
+                                    Some(interval) => format!("每{interval}秒"),
+                                    None => "关闭".to_string(),
+                                },
+                            ],
+                        ]
+                    })
+                }
+            }
+        }
+        Command::Restore { name, id, stop, to } => {
+            load_server(&name)?;
+            match to {
+                Some(new_name) => {
+                    backup::restore_to_new_server(&name, &id, &new_name)?;
+                    println!("已把备份{id}恢复为新的服务器{new_name}");
+                }
+                None => {
+                    if let Some(snapshot) = backup::restore_backup(&name, &id, stop)? {
+                        println!("恢复前的世界已备份为{}", snapshot.id);
+                    }
+                    println!("已把服务器{name}恢复到备份{id}");
+                }
+            }
+            Ok(())
+        }
+        Command::Config { name, action } => {
+            let mut server = load_server(&name)?;
+            match action {
+                ConfigAction::Show => {
+                    for key in config::CONFIG_KEYS {
+                        println!("{key} = {}", config::get_config_value(&server, key)?);
+                    }
+                }
+                ConfigAction::Get { key } => {
+                    println!("{}", config::get_config_value(&server, &key)?);
+                }
+                ConfigAction::Set { key, value } => {
+                    config::set_config_value(&mut server, &key, &value)?;
+                    save_servers_lists(&name, &server)?;
+                }
+                ConfigAction::Property {
+                    action: PropertyAction::List,
+                } => {
+                    for (key, value) in config::load_properties(&name)?.iter() {
+                        println!("{key} = {value}");
+                    }
+                }
+                ConfigAction::Property {
+                    action: PropertyAction::Get { key },
+                } => match config::load_properties(&name)?.get(&key) {
+                    Some(value) => println!("{value}"),
+                    None => return Err(Error::Other(format!("server.properties里没有{key}"))),
+                },
+                ConfigAction::Property {
+                    action: PropertyAction::Set { key, value },
+                } => {
+                    config::set_property(&name, &key, &value)?;
+                    if is_running(&name)? {
+                        println!("服务器{name}正在运行, 重启后生效");
+                    }
+                }
+            }
+            Ok(())
+        }
+        Command::Delete { name, yes } => {
+            if !yes {
+                return Err(Error::Other(format!(
+                    "删除服务器{name}会删除它的所有文件, 请使用--yes确认"
+                )));
+            }
+            delete::delete_server(&name)
+        }
+        Command::Rename { name, new_name } => {
+            config::rename_server(&name, &new_name)?;
+            println!("已把服务器{name}重命名为{new_name}");
+            Ok(())
+        }
+        Command::List => {
+            let mut servers = Vec::new();
+            for (name, config) in load_servers_lists(None)? {
+                let running = match is_running(&name) {
+                    Ok(running) => Some(running),
+                    Err(err) => {
+                        warn!("无法检查服务器{name}是否正在运行: {err}");
+                        None
+                    }
+                };
+                // 只查询正在运行的服务器, 避免等待超时
+                let status = (running == Some(true))
+                    .then(|| server_status(&name, LIST_STATUS_TIMEOUT).ok())
+                    .flatten();
+                servers.push(ServerEntry {
+                    config,
+                    running,
+                    status,
+                });
+            }
+            output::print(
+                format,
+                &servers,
+                &[
+                    "名称",
+                    "核心",
+                    "MC版本",
+                    "构建版本",
+                    "Xms",
+                    "Xmx",
+                    "状态",
+                    "玩家",
+                    "MOTD",
+                ],
+                |servers| {
+                    servers
+                        .iter()
+                        .map(|entry| {
+                            let server = &entry.config;
+                            let (players, motd) = match &entry.status {
+                                Some(status) => (
+                                    format!("{}/{}", status.online, status.max),
+                                    status.motd.replace('\n', " "),
+                                ),
+                                None => ("-".to_string(), "-".to_string()),
+                            };
+                            vec![
+                                server.name.clone(),
+                                server.info.core.clone(),
+                                server.info.mc_version.clone(),
+                                server.info.build_version.clone(),
+                                format_bytes(server.xms),
+                                format_bytes(server.xmx),
+                                entry.state().to_string(),
+                                players,
+                                motd,
+                            ]
+                        })
+                        .collect()
+                },
+            )
+        }
+        Command::Status { name, timeout } => {
+            load_server(&name)?;
+            let status = server_status(&name, Duration::from_secs(timeout))?;
+            output::print(format, &status, &["项目", "值"], |status| {
+                vec![
+                    vec!["版本".to_string(), status.version.clone()],
+                    vec!["协议".to_string(), status.protocol.to_string()],
+                    vec![
+                        "玩家".to_string(),
+                        format!("{}/{}", status.online, status.max),
+                    ],
+                    vec!["在线玩家".to_string(), status.players.join(", ")],
+                    vec!["MOTD".to_string(), status.motd.replace('\n', " ")],
+                    vec!["延迟".to_string(), format!("{}ms", status.latency)],
+                ]
+            })
+        }
+        Command::Query {
+            name,
+            basic,
+            timeout,
+        } => {
+            load_server(&name)?;
+            let client = query::connect_server(&name, Duration::from_secs(timeout))?;
+            if basic {
+                let stat = client.basic_stat()?;
+                return output::print(format, &stat, &["项目", "值"], |stat| {
+                    vec![
+                        vec!["MOTD".to_string(), stat.motd.clone()],
+                        vec!["游戏类型".to_string(), stat.game_type.clone()],
+                        vec!["地图".to_string(), stat.map.clone()],
+                        vec!["玩家".to_string(), format!("{}/{}", stat.online, stat.max)],
+                        vec![
+                            "地址".to_string(),
+                            format!("{}:{}", stat.host_ip, stat.host_port),
+                        ],
+                    ]
+                });
+            }
+            let stat = client.full_stat()?;
+            output::print(format, &stat, &["项目", "值"], |stat| {
+                vec![
+                    vec!["MOTD".to_string(), stat.motd.clone()],
+                    vec!["版本".to_string(), stat.version.clone()],
+                    vec!["核心".to_string(), stat.server_mod.clone()],
+                    vec!["插件".to_string(), stat.plugins.join(", ")],
+                    vec!["地图".to_string(), stat.map.clone()],
+                    vec!["玩家".to_string(), format!("{}/{}", stat.online, stat.max)],
+                    vec!["在线玩家".to_string(), stat.players.join(", ")],
+                    vec![
+                        "地址".to_string(),
+                        format!("{}:{}", stat.host_ip, stat.host_port),
+                    ],
+                ]
+            })
+        }
+        Command::Inspect { name } => {
+            let server = load_server(&name)?;
+            output::print(format, &server, &["配置项", "值"], |server| {
+                vec![
+                    vec!["name".to_string(), server.name.clone()],
+                    vec!["core".to_string(), server.info.core.clone()],
+                    vec!["mc_version".to_string(), server.info.mc_version.clone()],
+                    vec![
+                        "build_version".to_string(),
+                        server.info.build_version.clone(),
+                    ],
+                    vec!["java".to_string(), server.java.path.display().to_string()],
+                    vec!["java_version".to_string(), server.java.version.clone()],
+                    vec!["Xms".to_string(), format_bytes(server.xms)],
+                    vec!["Xmx".to_string(), format_bytes(server.xmx)],
+                    vec!["encoding".to_string(), server.encoding.clone()],
+                    vec!["jvm_args".to_string(), server.jvm_args.join(" ")],
+                    vec!["server_args".to_string(), server.server_args.join(" ")],
+                ]
+            })
+        }
+        Command::Java => {
+            output::print(format, &load_java_lists()?, &["版本", "路径"], |javas| {
+                javas
+                    .iter()
+                    .map(|java| vec![java.version.clone(), java.path.display().to_string()])
+                    .collect()
+            })
+        }
+        Command::Cores {
+            provider,
+            snapshots,
+        } => {
+            let cores = match provider {
+                ProviderKind::Mojang => Mojang { snapshots }.cores().await?,
+                provider => provider.provider().cores().await?,
+            };
+            output::print(
+                format,
+                &cores,
+                &["名称", "类型", "推荐", "MC版本"],
+                |cores| {
+                    cores
+                        .iter()
+                        .map(|core| {
+                            vec![
+                                core.name.clone(),
+                                core.tag.clone(),
+                                if core.recommend { "是" } else { "否" }.to_string(),
+                                core.mc_versions.join(", "),
+                            ]
+                        })
+                        .collect()
+                },
+            )
+        }
+        Command::Outdated { exit_code } => {
+            // 每个服务器使用创建时的来源, 不可用时换成其他有这个核心的来源
+            let mut cores = HashMap::new();
+            let mut cache = HashMap::new();
+            let mut entries = Vec::new();
+            for server in load_servers_lists(None)?.values() {
+                let info = &server.info;
+                let core_key = (info.provider, info.core.clone());
+                if !cores.contains_key(&core_key) {
+                    let found = match info.provider.find_core(&info.core).await {
+                        Ok(found) => Some(found),
+                        Err(err) => {
+                            warn!("无法获取核心{}: {err}", info.core);
+                            None
+                        }
+                    };
+                    cores.insert(core_key.clone(), found);
+                }
+                let Some((kind, mc_versions)) = &cores[&core_key] else {
+                    entries.push(OutdatedEntry::check(server, info.provider, &[], &[]));
+                    continue;
+                };
+                // 其他来源的构建版本无法和当前的构建版本比较, 不需要获取
+                let key = (*kind, info.core.clone(), info.mc_version.clone());
+                if *kind == info.provider && !cache.contains_key(&key) {
+                    let builds = match kind.provider().builds(&info.core, &info.mc_version).await {
+                        Ok(builds) => builds,
+                        Err(err) => {
+                            warn!("无法获取{} {}的构建版本: {err}", info.core, info.mc_version);
+                            Vec::new()
+                        }
+                    };
+                    cache.insert(key.clone(), builds);
+                }
+                let builds = cache.get(&key).map(Vec::as_slice).unwrap_or_default();
+                entries.push(OutdatedEntry::check(server, *kind, mc_versions, builds));
+            }
+            output::print(
+                format,
+                &entries,
+                &[
+                    "名称",
+                    "核心",
+                    "MC版本",
+                    "构建版本",
+                    "最新构建版本",
+                    "更新的MC版本",
+                ],
+                |entries| {
+                    entries
+                        .iter()
+                        .map(|entry| {
+                            let latest = match (&entry.latest_build, entry.outdated) {
+                                (Some(latest), true) => latest.clone(),
+                                (Some(_), false) => "已是最新".to_string(),
+                                (None, _) => "未知".to_string(),
+                            };
+                            let newer = if entry.newer_mc_versions.is_empty() {
+                                "-".to_string()
+                            } else {
+                                entry.newer_mc_versions.join(", ")
+                            };
+                            vec![
+                                entry.name.clone(),
+                                entry.core.clone(),
+                                entry.mc_version.clone(),
+                                entry.build_version.clone(),
+                                latest,
+                                newer,
+                            ]
+                        })
+                        .collect()
+                },
+            )?;
+            if exit_code {
+                output::check_outdated(&entries)?;
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,329 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::{
+    fmt, fs,
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::{info, trace, warn};
+
+use crate::error::{Error, Result};
+use crate::pages::import::SERVER_JAR;
+use crate::rcon;
+use crate::server::ServerConfig;
+use crate::session::{read_pid, spawn_host, Intent, Session, PID_FILE};
+use crate::workspace::Workspace;
+
+/// 默认等待服务器正常关闭的时间
+pub const DEFAULT_STOP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 发送SIGTERM或者SIGKILL后等待进程退出的时间
+const SIGNAL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 服务器是怎么关闭的
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopOutcome {
+    /// 服务器执行了`stop`命令后正常退出
+    Graceful,
+    /// 超时后发送SIGTERM(Windows上是`taskkill`)后退出
+    Terminated,
+    /// 最后发送SIGKILL(Windows上是`taskkill /F`)后退出
+    Killed,
+}
+
+impl fmt::Display for StopOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopOutcome::Graceful => write!(f, "服务器已正常关闭"),
+            StopOutcome::Terminated => write!(f, "服务器没有及时关闭, 已发送SIGTERM"),
+            StopOutcome::Killed => write!(f, "服务器没有响应SIGTERM, 已强制结束"),
+        }
+    }
+}
+
+/// 进程是否还在运行
+pub fn is_alive(pid: u32) -> bool {
+    #[cfg(target_os = "windows")]
+    let output = Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {pid}"), "/NH"])
+        .output();
+    #[cfg(not(target_os = "windows"))]
+    let output = Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(Stdio::null())
+        .output();
+    match output {
+        #[cfg(target_os = "windows")]
+        Ok(output) => String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()),
+        #[cfg(not(target_os = "windows"))]
+        Ok(output) => output.status.success(),
+        Err(_) => false,
+    }
+}
+
+/// 进程`pid`是不是在`server_dir`里运行的服务器, 防止PID被其他进程重新使用后误认为服务器还在运行
+///
+/// Linux上检查进程的命令行包含`server.jar`并且工作目录是服务器文件夹, 其他Unix系统只检查命令行,
+/// Windows上只检查进程是否还在运行
+pub fn is_server_process(server_dir: &Path, pid: u32) -> bool {
+    if !is_alive(pid) {
+        return false;
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let proc_dir = Path::new("/proc").join(pid.to_string());
+        let has_jar = fs::read(proc_dir.join("cmdline")).is_ok_and(|cmdline| {
+            cmdline
+                .split(|byte| *byte == 0)
+                .any(|arg| arg == SERVER_JAR.as_bytes())
+        });
+        // 其他用户的进程读取不到工作目录, 也不是服务器
+        let in_server_dir = match (
+            fs::read_link(proc_dir.join("cwd")),
+            fs::canonicalize(server_dir),
+        ) {
+            (Ok(cwd), Ok(server_dir)) => cwd == server_dir,
+            _ => false,
+        };
+        has_jar && in_server_dir
+    }
+    #[cfg(all(unix, not(target_os = "linux")))]
+    {
+        let _ = server_dir;
+        Command::new("ps")
+            .args(["-o", "args=", "-p", &pid.to_string()])
+            .output()
+            .is_ok_and(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .split_whitespace()
+                    .any(|arg| arg == SERVER_JAR)
+            })
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = server_dir;
+        true
+    }
+}
+
+/// 结束进程, `force`为`false`时发送SIGTERM, 为`true`时发送SIGKILL
+pub fn terminate(pid: u32, force: bool) -> Result<()> {
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut command = Command::new("taskkill");
+        command.args(["/PID", &pid.to_string(), "/T"]);
+        if force {
+            command.arg("/F");
+        }
+        command
+    };
+    #[cfg(not(target_os = "windows"))]
+    let mut command = {
+        let mut command = Command::new("kill");
+        command.args([if force { "-KILL" } else { "-TERM" }, &pid.to_string()]);
+        command
+    };
+    trace!("shell <- {command:?}");
+    let status = command
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+    if !status.success() && is_alive(pid) {
+        return Err(Error::Other(format!("无法结束进程{pid}({status})")));
+    }
+    Ok(())
+}
+
+/// 等待进程退出, 超时返回`false`
+pub fn wait_exit(pid: u32, timeout: Duration) -> bool {
+    let start = Instant::now();
+    while is_alive(pid) {
+        if start.elapsed() >= timeout {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+    true
+}
+
+/// 正在运行的服务器, 会话和服务器进程至少有一个
+struct Running {
+    /// 宿主进程的会话, 宿主进程异常退出时为`None`
+    session: Option<Session>,
+    /// 服务器进程的PID, 宿主进程在等待自动重启时为`None`
+    pid: Option<u32>,
+}
+
+/// 返回正在运行的服务器, 没有运行时返回`None`
+///
+/// 宿主进程异常退出时没有会话, 但是仍然可以通过`server.pid`找到服务器.
+/// PID对应的进程不是这个服务器时(例如服务器崩溃或者重启电脑后PID被其他进程使用)删除`server.pid`
+fn find(name: &str) -> Result<Option<Running>> {
+    let session = Session::load(name)?;
+    let server_dir = Workspace::current()?.server_dir(name);
+    let pid_file = read_pid(&server_dir);
+    let pid = match &session {
+        Some(session) if session.server_pid != 0 => Some(session.server_pid),
+        _ => pid_file,
+    };
+    let pid = match pid {
+        Some(pid) if is_server_process(&server_dir, pid) => Some(pid),
+        Some(pid) => {
+            if pid_file == Some(pid) {
+                warn!("进程{pid}不是服务器{name}, 删除过期的{PID_FILE}");
+                fs::remove_file(server_dir.join(PID_FILE))?;
+            }
+            None
+        }
+        None => None,
+    };
+    if session.is_none() && pid.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(Running { session, pid }))
+}
+
+/// 和[`find`]一样, 但是没有运行时返回错误
+fn running(name: &str) -> Result<Running> {
+    find(name)?.ok_or_else(|| Error::Other(format!("服务器{name}没有在运行")))
+}
+
+/// 返回要关闭的服务器进程
+///
+/// 宿主进程在等待自动重启时没有服务器进程, 这时告诉它不要再启动服务器并等待它退出, 返回`None`;
+/// 如果刚好在这之前重新启动了服务器, 返回新的服务器进程
+fn stop_target(name: &str, running: &Running) -> Result<Option<u32>> {
+    let session = match (running.pid, &running.session) {
+        (Some(pid), _) => return Ok(Some(pid)),
+        (None, Some(session)) => session,
+        (None, None) => return Ok(None),
+    };
+    info!("服务器{name}正在等待自动重启, 取消重启");
+    session.set_intent(Intent::Stop)?;
+    let start = Instant::now();
+    while start.elapsed() < SIGNAL_TIMEOUT {
+        match find(name)? {
+            None => return Ok(None),
+            Some(Running { pid: Some(pid), .. }) => return Ok(Some(pid)),
+            Some(_) => thread::sleep(Duration::from_millis(200)),
+        }
+    }
+    Err(Error::Other(format!("服务器{name}的宿主进程没有退出")))
+}
+
+/// 等待服务器退出, 超时后依次发送SIGTERM和SIGKILL
+fn escalate(pid: u32, timeout: Duration) -> Result<StopOutcome> {
+    if wait_exit(pid, timeout) {
+        return Ok(StopOutcome::Graceful);
+    }
+    warn!(
+        "服务器{pid}在{}秒内没有关闭, 发送SIGTERM",
+        timeout.as_secs()
+    );
+    terminate(pid, false)?;
+    if wait_exit(pid, SIGNAL_TIMEOUT) {
+        return Ok(StopOutcome::Terminated);
+    }
+    warn!("服务器{pid}没有响应SIGTERM, 发送SIGKILL");
+    terminate(pid, true)?;
+    if wait_exit(pid, SIGNAL_TIMEOUT) {
+        return Ok(StopOutcome::Killed);
+    }
+    Err(Error::Other(format!("无法结束服务器进程{pid}")))
+}
+
+/// 让服务器开始关闭, 有会话时在控制台输入`stop`, 否则通过RCON执行`stop`, 都不行时发送SIGTERM
+fn request_stop(name: &str, session: Option<&Session>, pid: u32, intent: Intent) -> Result<()> {
+    if let Some(session) = session {
+        session.set_intent(intent)?;
+        return session.send_command("stop");
+    }
+    match rcon::connect_server(name).and_then(|mut rcon| rcon.command("stop")) {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            warn!("找不到服务器{name}的会话, 无法通过RCON关闭({err}), 发送SIGTERM");
+            terminate(pid, false)
+        }
+    }
+}
+
+/// 关闭服务器, 先在控制台(或者通过RCON)输入`stop`, `timeout`内没有关闭时依次发送SIGTERM和SIGKILL
+///
+/// # 示例
+/// ```no_run
+/// use mcscs::control::{stop_server, DEFAULT_STOP_TIMEOUT};
+/// println!("{}", stop_server("server_name", DEFAULT_STOP_TIMEOUT).unwrap());
+/// ```
+pub fn stop_server(name: &str, timeout: Duration) -> Result<StopOutcome> {
+    let running = running(name)?;
+    let Some(pid) = stop_target(name, &running)? else {
+        return Ok(StopOutcome::Graceful);
+    };
+    info!("关闭服务器{name}({pid})");
+    request_stop(name, running.session.as_ref(), pid, Intent::Stop)?;
+    escalate(pid, timeout)
+}
+
+/// 重启服务器, 关闭的过程和[`stop_server`]一样
+///
+/// 有会话时由宿主进程重新启动服务器, 保留自动重启的设置和连接的控制台; 否则在后台启动一个新的宿主进程
+pub fn restart_server(server: &ServerConfig, timeout: Duration) -> Result<StopOutcome> {
+    let Running { session, pid } = running(&server.name)?;
+    let Some(pid) = pid else {
+        return Err(Error::Other(format!(
+            "服务器{}正在等待自动重启",
+            server.name
+        )));
+    };
+    info!("重启服务器{}({pid})", server.name);
+    request_stop(&server.name, session.as_ref(), pid, Intent::Restart)?;
+    let outcome = escalate(pid, timeout)?;
+    if session.is_none() {
+        spawn_host(server, None)?;
+        return Ok(outcome);
+    }
+    let start = Instant::now();
+    while start.elapsed() < timeout {
+        match Session::load(&server.name)? {
+            Some(session) if session.server_pid != pid && session.server_pid != 0 => {
+                return Ok(outcome)
+            }
+            Some(_) => thread::sleep(Duration::from_millis(200)),
+            None => break,
+        }
+    }
+    Err(Error::Other(format!("服务器{}没有重新启动", server.name)))
+}
+
+/// 立即强制结束服务器(SIGKILL), 可能会损坏存档, 只在服务器没有响应时使用
+pub fn kill_server(name: &str) -> Result<()> {
+    let running = running(name)?;
+    let Some(pid) = stop_target(name, &running)? else {
+        return Ok(());
+    };
+    warn!("强制结束服务器{name}({pid})");
+    if let Some(session) = &running.session {
+        // 不让守护进程把它当作崩溃重启
+        session.set_intent(Intent::Stop)?;
+    }
+    terminate(pid, true)?;
+    if wait_exit(pid, SIGNAL_TIMEOUT) {
+        Ok(())
+    } else {
+        Err(Error::Other(format!("无法结束服务器进程{pid}")))
+    }
+}
+
+/// 服务器是否正在运行, 宿主进程在等待自动重启时也算作正在运行
+pub fn is_running(name: &str) -> Result<bool> {
+    Ok(find(name)?.is_some())
+}
@@ -0,0 +1,150 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::{fmt, io};
+
+use crate::server::ConfigError;
+
+/// MCSCS的错误类型
+#[derive(Debug)]
+pub enum Error {
+    /// 读写文件或者启动进程失败
+    Io(io::Error),
+    /// 解析或生成JSON失败
+    Json(serde_json::Error),
+    /// 网络请求失败
+    Http(reqwest::Error),
+    /// aria2c的JSON-RPC调用失败或者下载任务出错
+    Aria2(String),
+    /// RCON连接或者认证失败
+    Rcon(String),
+    /// 查询服务器状态失败
+    Status(String),
+    /// 下载的文件SHA1与预期不一致
+    Checksum { expected: String, actual: String },
+    /// 无法获取Java环境的信息
+    Java(String),
+    /// 读取或者写入压缩包失败
+    Archive(String),
+    /// 备份或者恢复失败
+    Backup(String),
+    /// 服务器配置无效
+    Config(ConfigError),
+    /// 找不到指定的服务器
+    ServerNotFound(String),
+    /// 用户没有同意Minecraft EULA
+    Eula,
+    /// 用户取消了操作
+    Cancelled,
+    /// 其他错误
+    Other(String),
+}
+
+/// MCSCS的结果类型
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "IO错误: {err}"),
+            Error::Json(err) => write!(f, "JSON错误: {err}"),
+            Error::Http(err) => write!(f, "网络错误: {err}"),
+            Error::Aria2(err) => write!(f, "aria2c错误: {err}"),
+            Error::Rcon(err) => write!(f, "RCON错误: {err}"),
+            Error::Status(err) => write!(f, "查询服务器状态失败: {err}"),
+            Error::Checksum { expected, actual } => {
+                write!(f, "SHA1校验失败: 预期{expected}, 实际{actual}")
+            }
+            Error::Java(err) => write!(f, "Java错误: {err}"),
+            Error::Archive(err) => write!(f, "压缩包错误: {err}"),
+            Error::Backup(err) => write!(f, "备份错误: {err}"),
+            Error::Config(err) => write!(f, "{err}"),
+            Error::ServerNotFound(name) => write!(f, "服务器{name}不存在"),
+            Error::Eula => write!(f, "必须同意Minecraft EULA(https://aka.ms/MinecraftEULA)"),
+            Error::Cancelled => write!(f, "操作已取消"),
+            Error::Other(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Json(err) => Some(err),
+            Error::Http(err) => Some(err),
+            Error::Config(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Error::Http(err)
+    }
+}
+
+impl From<jsonrpc::Error> for Error {
+    fn from(err: jsonrpc::Error) -> Self {
+        Error::Aria2(err.to_string())
+    }
+}
+
+impl From<jsonrpc::simple_http::Error> for Error {
+    fn from(err: jsonrpc::simple_http::Error) -> Self {
+        Error::Aria2(err.to_string())
+    }
+}
+
+impl From<dialoguer::Error> for Error {
+    fn from(err: dialoguer::Error) -> Self {
+        match err {
+            dialoguer::Error::IO(err) => Error::Io(err),
+        }
+    }
+}
+
+impl From<zip::result::ZipError> for Error {
+    fn from(err: zip::result::ZipError) -> Self {
+        match err {
+            zip::result::ZipError::Io(err) => Error::Io(err),
+            err => Error::Archive(err.to_string()),
+        }
+    }
+}
+
+impl From<ConfigError> for Error {
+    fn from(err: ConfigError) -> Self {
+        Error::Config(err)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(err: &str) -> Self {
+        Error::Other(err.to_string())
+    }
+}
+
+impl From<String> for Error {
+    fn from(err: String) -> Self {
+        Error::Other(err)
+    }
+}
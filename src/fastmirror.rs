@@ -7,18 +7,19 @@
  */
 
 use std::{
-    error::Error,
     fs,
     io::Read,
     path::{Path, PathBuf},
 };
 
-use log::error;
+use async_trait::async_trait;
 use reqwest::{Client, Url};
 use serde_json::{json, Map, Value};
 use sha1::{Digest, Sha1};
 
-use crate::aria2c::download;
+use crate::error::{Error, Result};
+use crate::output::CoreEntry;
+use crate::provider::{Build, CoreProvider};
 
 /// 获取FastMirror的返回值
 ///
@@ -28,7 +29,7 @@ use crate::aria2c::download;
 ///
 /// #[tokio::main]
 /// async fn main() {
-///     let fastmirror = get_fastmirror_value().await;
+///     let fastmirror = get_fastmirror_value().await.unwrap();
 ///     println!("{fastmirror}");
 /// }
 /// ```
@@ -54,13 +55,11 @@ use crate::aria2c::download;
 ///     }
 /// }
 /// ```
-pub async fn get_fastmirror_value() -> Value {
-    let url = Url::parse("https://download.fastmirror.net/api/v3").expect("get_fastmirror_value()");
-    let response = reqwest::get(url).await.expect("get_fastmirror_value()");
-    let data = response
-        .json::<Value>()
-        .await
-        .expect("get_fastmirror_value()");
+pub async fn get_fastmirror_value() -> Result<Value> {
+    let response = reqwest::get("https://download.fastmirror.net/api/v3")
+        .await?
+        .error_for_status()?;
+    let data = response.json::<Value>().await?;
 
     let mut name_map = Map::new();
     if let Some(builds) = data["data"].as_array() {
@@ -70,7 +69,7 @@ pub async fn get_fastmirror_value() -> Value {
             }
         }
     }
-    json!(name_map)
+    Ok(json!(name_map))
 }
 
 /// 获取FastMirror返回的指定版本的构建版本
@@ -81,7 +80,7 @@ pub async fn get_fastmirror_value() -> Value {
 ///
 /// #[tokio::main]
 /// async fn main() {
-///     let fastmirror = get_fastmirror_builds_value("Mohist", "1.20.1").await;
+///     let fastmirror = get_fastmirror_builds_value("Mohist", "1.20.1").await.unwrap();
 ///     println!("{fastmirror}")
 /// }
 /// ```
@@ -106,23 +105,16 @@ pub async fn get_fastmirror_value() -> Value {
 ///     }
 /// }
 /// ```
-pub async fn get_fastmirror_builds_value(core: &str, version: &str) -> Value {
+pub async fn get_fastmirror_builds_value(core: &str, version: &str) -> Result<Value> {
     let mut url = Url::parse(&format!(
         "https://download.fastmirror.net/api/v3/{core}/{version}"
     ))
-    .expect("get_fastmirror_builds_value()");
+    .map_err(|err| Error::Other(format!("{core}/{version}: {err}")))?;
     url.query_pairs_mut()
         .append_pair("offset", "0")
         .append_pair("limit", "25");
-    let response = Client::new()
-        .get(url)
-        .send()
-        .await
-        .expect("get_fastmirror_builds_value()");
-    let data = response
-        .json::<Value>()
-        .await
-        .expect("get_fastmirror_builds_value()");
+    let response = Client::new().get(url).send().await?.error_for_status()?;
+    let data = response.json::<Value>().await?;
 
     let mut name_map = Map::new();
     if let Some(builds) = data["data"]["builds"].as_array() {
@@ -132,26 +124,71 @@ pub async fn get_fastmirror_builds_value(core: &str, version: &str) -> Value {
             }
         }
     }
-    json!(name_map)
+    Ok(json!(name_map))
+}
+
+/// 获取数据的SHA1值
+///
+/// # 示例
+/// ```
+/// use mcscs::fastmirror::get_sha1;
+/// assert_eq!(get_sha1(b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+/// ```
+pub fn get_sha1(data: &[u8]) -> String {
+    hex::encode(Sha1::digest(data))
 }
 
 /// 获取文件的SHA1值
-pub fn get_file_sha1(file_path: &Path) -> String {
+pub fn get_file_sha1(file_path: &Path) -> Result<String> {
     let mut buffer = [0u8; 1024];
-    let mut file = fs::File::open(file_path).expect("get_file_sha1()");
+    let mut file = fs::File::open(file_path)?;
     let mut hasher = Sha1::new();
 
     loop {
-        let bytes_read = file.read(&mut buffer).unwrap();
+        let bytes_read = file.read(&mut buffer)?;
         if bytes_read == 0 {
             break;
         }
         hasher.update(&buffer[..bytes_read]);
     }
-    hex::encode(hasher.finalize())
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// FastMirror(download.fastmirror.net)提供的核心
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FastMirror;
+
+#[async_trait]
+impl CoreProvider for FastMirror {
+    fn name(&self) -> &'static str {
+        "FastMirror"
+    }
+
+    async fn cores(&self) -> Result<Vec<CoreEntry>> {
+        CoreEntry::from_fastmirror(&get_fastmirror_value().await?)
+    }
+
+    async fn builds(&self, core: &str, mc_version: &str) -> Result<Vec<Build>> {
+        Ok(Build::from_fastmirror(
+            &get_fastmirror_builds_value(core, mc_version).await?,
+        ))
+    }
+
+    async fn download_url(&self, core: &str, mc_version: &str, build: &str) -> Result<String> {
+        Ok(format!(
+            "https://download.fastmirror.net/download/{core}/{mc_version}/{build}"
+        ))
+    }
+
+    async fn sha1(&self, core: &str, mc_version: &str, build: &str) -> Result<String> {
+        get_fastmirror_builds_value(core, mc_version).await?[build]["sha1"]
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| Error::Other(format!("FastMirror没有{core} {mc_version} {build}的SHA1")))
+    }
 }
 
-/// 下载服务器核心
+/// 从FastMirror下载服务器核心并检查SHA1, 与`FastMirror.download(...)`相同
 ///
 /// # 示例
 /// ```
@@ -167,20 +204,6 @@ pub async fn download_server_core(
     core: &str,
     mc_version: &str,
     build_version: &str,
-) -> Result<PathBuf, Box<dyn Error>> {
-    let file_path = download(&format!(
-        "https://download.fastmirror.net/download/{core}/{mc_version}/{build_version}"
-    ))
-    .expect("download_server_core()");
-    let fastmirror_sha1 = get_fastmirror_builds_value(core, mc_version).await[build_version]
-        ["sha1"]
-        .as_str()
-        .unwrap()
-        .to_string();
-    let file_sha1 = get_file_sha1(&PathBuf::from(&file_path));
-    if file_sha1 != fastmirror_sha1 {
-        error!("Fastmirror: {fastmirror_sha1} != File: {file_sha1}");
-        return Err("SHA1".into());
-    }
-    Ok(file_path)
+) -> Result<PathBuf> {
+    FastMirror.download(core, mc_version, build_version).await
 }
@@ -7,8 +7,6 @@
  */
 
 use std::{
-    env,
-    error::Error,
     fs,
     io::Read,
     path::{Path, PathBuf},
@@ -19,7 +17,70 @@ use std::{
 use log::trace;
 use rayon::prelude::*;
 use regex::Regex;
-use serde_json::{json, Value};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::workspace::Workspace;
+
+/// Java环境
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Java {
+    /// Java可执行程序的路径
+    pub path: PathBuf,
+    /// Java版本, 例如: `17.0.9`, `1.8.0_402`
+    pub version: String,
+}
+
+impl Java {
+    /// 返回Java的主版本号, `1.8.0_402`返回8, `17.0.9`返回17
+    ///
+    /// # 示例
+    /// ```
+    /// use mcscs::java::Java;
+    /// let java = Java { path: "java".into(), version: "1.8.0_402".into() };
+    /// assert_eq!(java.major_version(), Some(8));
+    /// ```
+    pub fn major_version(&self) -> Option<u32> {
+        let mut parts = self.version.split(['.', '_', '-', '+']);
+        match parts.next()?.parse().ok()? {
+            1 => parts.next()?.parse().ok(),
+            major => Some(major),
+        }
+    }
+}
+
+/// 返回Minecraft版本需要的Java主版本号, 只在服务器核心里没有记录时使用
+///
+/// # 示例
+/// ```
+/// use mcscs::java::required_java;
+/// assert_eq!(required_java("1.12.2"), 8);
+/// assert_eq!(required_java("1.20.1"), 17);
+/// assert_eq!(required_java("1.21"), 21);
+/// ```
+pub fn required_java(mc_version: &str) -> u32 {
+    let mut parts = mc_version
+        .split('.')
+        .map(|part| part.parse::<u32>().unwrap_or(0));
+    let minor = parts.nth(1).unwrap_or(0);
+    let patch = parts.next().unwrap_or(0);
+    match (minor, patch) {
+        (21.., _) | (20, 5..) => 21,
+        (18..=20, _) => 17,
+        (17, _) => 16,
+        _ => 8,
+    }
+}
+
+/// 从Java列表里找一个满足要求的Java, 优先使用主版本号相同的, 否则使用满足要求的最低版本
+pub fn find_java(javas: &[Java], required: u32) -> Option<Java> {
+    javas
+        .iter()
+        .filter_map(|java| Some((java.major_version()?, java)))
+        .filter(|(major, _)| *major >= required)
+        .min_by_key(|(major, _)| *major)
+        .map(|(_, java)| java.clone())
+}
 
 /// 在一个指定的目录下多线程的寻找指定的
 ///
@@ -85,24 +146,25 @@ pub fn search_file(path: &Path, execute_paths: &Arc<Mutex<Vec<PathBuf>>>, execut
 /// use mcscs::java::get_java_version;
 /// let version = get_java_version(&PathBuf::from("java/path"));
 /// ```
-pub fn get_java_version(java_path: &Path) -> Result<String, Box<dyn Error>> {
+pub fn get_java_version(java_path: &Path) -> Result<String> {
     let output = Command::new(java_path)
         .args(["-version", "2>&1"])
         .output()
-        .expect("get_java_version()");
+        .map_err(|err| Error::Java(format!("{}: {err}", java_path.display())))?;
 
     let output_str = String::from_utf8_lossy(&output.stderr);
     let re = Regex::new(r"(\d+)(?:\.(\d+))?(?:\.(\d+))?(?:[._](\d+))?(?:-(.+))?")
-        .expect("get_java_version()");
+        .map_err(|err| Error::Java(err.to_string()))?;
     // 在输出中查找第一个匹配项
-    if let Some(captured) = re.captures(&output_str) {
-        if let Some(first_match) = captured.get(0) {
-            Ok(first_match.as_str().to_string())
-        } else {
-            Err("regex".into())
-        }
-    } else {
-        Err("regex".into())
+    match re
+        .captures(&output_str)
+        .and_then(|captured| captured.get(0))
+    {
+        Some(first_match) => Ok(first_match.as_str().to_string()),
+        None => Err(Error::Java(format!(
+            "{}: 无法识别Java版本",
+            java_path.display()
+        ))),
     }
 }
 
@@ -113,7 +175,7 @@ pub fn get_java_version(java_path: &Path) -> Result<String, Box<dyn Error>> {
 /// ```
 /// use mcscs::java::detect_java;
 /// if let Ok(java) = detect_java() {
-///     println!("{java}");
+///     println!("{java:?}");
 /// }
 /// ```
 ///
@@ -132,7 +194,7 @@ pub fn get_java_version(java_path: &Path) -> Result<String, Box<dyn Error>> {
 ///     "version": "1.8.0_402"
 /// }
 /// ```
-pub fn detect_java() -> Value {
+pub fn detect_java() -> Result<Vec<Java>> {
     let java_paths = Arc::new(Mutex::new(Vec::new()));
 
     #[cfg(target_os = "windows")]
@@ -144,57 +206,40 @@ pub fn detect_java() -> Value {
             search_file(&std::path::PathBuf::from(drive), &java_paths, "java.exe");
         });
     #[cfg(not(target_os = "windows"))]
-    fs::read_dir("/usr/lib")
-        .expect("detect_java()")
-        .for_each(|entry| {
-            if let Ok(entry) = entry {
-                search_file(&entry.path(), &java_paths, "java");
-            }
-        });
+    for entry in fs::read_dir("/usr/lib")?.flatten() {
+        search_file(&entry.path(), &java_paths, "java");
+    }
 
-    let mut java_with_version = Vec::<Value>::new();
+    let mut java_with_version = Vec::new();
     for java in java_paths.lock().unwrap().clone() {
-        let version = get_java_version(&java);
-        if let Ok(version) = version {
-            java_with_version.push(json!({"path": java, "version": version}));
+        if let Ok(version) = get_java_version(&java) {
+            java_with_version.push(Java {
+                path: java,
+                version,
+            });
         }
     }
 
-    let java = json!(java_with_version);
-    trace!("find -> {java}");
-    java
+    trace!("find -> {java_with_version:?}");
+    Ok(java_with_version)
 }
 
 /// 保存Java环境列表到[`MCSCS\configs\java.json`]
-pub fn save_java_lists(java: &Value) {
-    let file = fs::File::create(
-        env::current_dir()
-            .expect("save_java_lists()")
-            .join("MCSCS")
-            .join("configs")
-            .clone()
-            .join("java.json"),
-    )
-    .expect("save_java_lists()");
-    trace!("MCSCS/configs/java.json <- {java}");
-    serde_json::to_writer_pretty(file, &json!(java)).expect("save_java_lists()");
+pub fn save_java_lists(java: &[Java]) -> Result<()> {
+    let file = fs::File::create(Workspace::current()?.configs_dir().join("java.json"))?;
+    trace!("MCSCS/configs/java.json <- {java:?}");
+    serde_json::to_writer_pretty(file, java)?;
+    Ok(())
 }
 
 /// 从[`MCSCS\configs\java.json`]读取Java环境列表
-pub fn load_java_lists() -> Value {
-    let mut file = fs::File::open(
-        env::current_dir()
-            .unwrap()
-            .join("MCSCS")
-            .join("configs")
-            .join("java.json"),
-    )
-    .expect("load_java_lists()");
+pub fn load_java_lists() -> Result<Vec<Java>> {
+    let mut file = fs::File::open(Workspace::current()?.configs_dir().join("java.json"))?;
 
     // 读取文件内容到字符串中
     let mut java = String::new();
-    file.read_to_string(&mut java).expect("load_java_lists()");
-    let java = serde_json::from_str::<Value>(&java).expect("load_java_lists()");
-    trace!("MCSCS/configs/java.json -> {java}");
-    java
+    file.read_to_string(&mut java)?;
+    let java = serde_json::from_str::<Vec<Java>>(&java)?;
+    trace!("MCSCS/configs/java.json -> {java:?}");
+    Ok(java)
 }
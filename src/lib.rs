@@ -5,10 +5,29 @@
  * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
  * Vestibulum commodo. Ut rhoncus gravida arcu.
  */
+pub mod archive;
 pub mod aria2c;
+pub mod backup;
+pub mod cli;
+pub mod control;
+pub mod error;
 pub mod fastmirror;
 pub mod java;
+pub mod migrate;
+pub mod mojang;
+pub mod output;
 pub mod pages;
+pub mod properties;
+pub mod provider;
+pub mod query;
+pub mod rcon;
 pub mod select;
 pub mod server;
+pub mod session;
+pub mod status;
+pub mod store;
+pub mod supervisor;
 pub mod utils;
+pub mod workspace;
+
+pub use error::{Error, Result};
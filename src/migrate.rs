@@ -0,0 +1,159 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::{fs, path::Path};
+
+use log::{info, trace, warn};
+use serde_json::{json, Value};
+
+use crate::error::Result;
+use crate::java::get_java_version;
+use crate::pages::create::to_bytes;
+use crate::server::{ConfigError, ServerConfig};
+
+/// 当前`config.json`的版本, 修改配置格式时需要加1并在[`MIGRATIONS`]末尾添加对应的升级函数
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// 把版本`n`的配置升级到版本`n + 1`, 无法升级时返回出错的配置项
+type Migration = fn(&mut Value) -> std::result::Result<(), ConfigError>;
+
+/// 升级函数列表, 第`n`个函数把版本`n`的配置升级到版本`n + 1`
+const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [migrate_v0_to_v1];
+
+/// 返回配置的版本, 没有`schema_version`的配置为版本0
+pub fn schema_version(config: &Value) -> std::result::Result<u64, ConfigError> {
+    match config.get("schema_version") {
+        None => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .ok_or_else(|| ConfigError::new("schema_version", "版本号必须是非负整数")),
+    }
+}
+
+/// 把配置升级到[`SCHEMA_VERSION`], 返回升级前的版本
+///
+/// # 示例
+/// ```
+/// use serde_json::json;
+/// use mcscs::migrate::{upgrade, SCHEMA_VERSION};
+/// let mut config = json!({"name": "test", "Xms": "1G", "Xmx": "2G"});
+/// assert_eq!(upgrade(&mut config).unwrap(), 0);
+/// assert_eq!(config["schema_version"], SCHEMA_VERSION);
+/// assert_eq!(config["Xms"], 1073741824);
+/// ```
+pub fn upgrade(config: &mut Value) -> std::result::Result<u64, ConfigError> {
+    if !config.is_object() {
+        return Err(ConfigError::new("", "配置文件必须是JSON对象"));
+    }
+    let version = schema_version(config)?;
+    if version > SCHEMA_VERSION {
+        return Err(ConfigError::new(
+            "schema_version",
+            format!("配置文件版本{version}高于当前程序支持的版本{SCHEMA_VERSION}, 请升级mcscs"),
+        ));
+    }
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        migration(config)?;
+        config["schema_version"] = json!(from + 1);
+        trace!("config.json v{from} -> v{}", from + 1);
+    }
+    Ok(version)
+}
+
+/// 版本0(没有`schema_version`)到版本1
+///
+/// 早期版本的内存大小可能是`"1G"`这样的字符串, 参数可能是用空格分隔的字符串,
+/// `java`可能只保存了可执行程序的路径
+fn migrate_v0_to_v1(config: &mut Value) -> std::result::Result<(), ConfigError> {
+    for key in ["Xms", "Xmx"] {
+        if let Some(size) = config[key].as_str() {
+            let bytes = to_bytes(size.trim());
+            if bytes == 0 {
+                return Err(ConfigError::new(key, format!("无效的内存大小: {size}")));
+            }
+            config[key] = json!(bytes);
+        }
+    }
+    for key in ["jvm_args", "server_args"] {
+        if let Some(args) = config[key].as_str() {
+            config[key] = json!(args.split_whitespace().collect::<Vec<&str>>());
+        }
+    }
+    if let Some(path) = config["java"].as_str().map(str::to_string) {
+        let version = get_java_version(Path::new(&path)).unwrap_or_else(|_| "unknown".into());
+        config["java"] = json!({"path": path, "version": version});
+    }
+    Ok(())
+}
+
+/// 读取`config.json`, 如果版本较旧则先备份为`config.json.v<版本>.bak`再写回升级后的配置
+pub fn load_and_upgrade(path: &Path) -> Result<ServerConfig> {
+    let data = fs::read_to_string(path)?;
+    let mut value = serde_json::from_str::<Value>(&data)
+        .map_err(|err| ConfigError::new("", err.to_string()))?;
+    let version = upgrade(&mut value)?;
+    let config = ServerConfig::from_value(value)?;
+    if version < SCHEMA_VERSION {
+        let backup = path.with_file_name(format!("config.json.v{version}.bak"));
+        if !backup.exists() {
+            fs::copy(path, &backup)?;
+        }
+        let file = fs::File::create(path)?;
+        serde_json::to_writer_pretty(file, &config)?;
+        info!(
+            "{} v{version} -> v{SCHEMA_VERSION}, 备份: {}",
+            path.display(),
+            backup.display()
+        );
+    }
+    Ok(config)
+}
+
+/// 把旧版本`MCSCS/configs/servers.json`里的服务器导入到`MCSCS/servers/<name>/config.json`,
+/// 全部导入后把`servers.json`重命名为`servers.json.bak`
+///
+/// 返回无法导入的服务器和原因, 这时会保留`servers.json`, 修复后下次运行时会重新导入
+pub fn import_legacy_servers(
+    configs_dir: &Path,
+    servers_dir: &Path,
+) -> Result<Vec<(String, ConfigError)>> {
+    let legacy = configs_dir.join("servers.json");
+    let mut failed = Vec::new();
+    if !legacy.exists() {
+        return Ok(failed);
+    }
+    let data = fs::read_to_string(&legacy)?;
+    let servers = serde_json::from_str::<Value>(&data)?;
+    for (name, config) in servers.as_object().into_iter().flatten() {
+        let path = servers_dir.join(name).join("config.json");
+        if path.exists() {
+            trace!("find -> {}", path.display());
+            continue;
+        }
+        let mut config = config.clone();
+        if config.get("name").is_none() {
+            config["name"] = json!(name);
+        }
+        let config = match upgrade(&mut config).and_then(|_| ServerConfig::from_value(config)) {
+            Ok(config) => config,
+            Err(err) => {
+                warn!("无法导入{}中的服务器{name}: {err}", legacy.display());
+                failed.push((name.clone(), err));
+                continue;
+            }
+        };
+        fs::create_dir_all(servers_dir.join(name))?;
+        let file = fs::File::create(&path)?;
+        serde_json::to_writer_pretty(file, &config)?;
+        info!("{} -> {}", legacy.display(), path.display());
+    }
+    if failed.is_empty() {
+        fs::rename(&legacy, legacy.with_file_name("servers.json.bak"))?;
+    }
+    Ok(failed)
+}
@@ -0,0 +1,280 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::{collections::HashMap, fmt};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::fastmirror::get_sha1;
+use crate::output::CoreEntry;
+use crate::provider::{Build, CoreProvider};
+
+/// Mojang的版本清单
+pub const VERSION_MANIFEST_URL: &str =
+    "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
+
+/// Mojang提供的核心名称, 与FastMirror上的原版核心相同
+pub const VANILLA: &str = "Vanilla";
+
+/// Minecraft版本的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VersionType {
+    /// 正式版本, 例如: `1.20.1`
+    Release,
+    /// 快照版本, 例如: `23w13a`
+    Snapshot,
+    /// 很早的Beta版本, 没有服务器核心
+    OldBeta,
+    /// 很早的Alpha版本, 没有服务器核心
+    OldAlpha,
+}
+
+impl fmt::Display for VersionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            VersionType::Release => "release",
+            VersionType::Snapshot => "snapshot",
+            VersionType::OldBeta => "old_beta",
+            VersionType::OldAlpha => "old_alpha",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// 最新的正式版本和快照版本
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Latest {
+    pub release: String,
+    pub snapshot: String,
+}
+
+/// 版本清单里的一个版本
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestVersion {
+    /// Minecraft版本, 例如: `1.20.1`
+    pub id: String,
+    #[serde(rename = "type")]
+    pub version_type: VersionType,
+    /// 版本信息的地址, 见[`VersionInfo`]
+    pub url: String,
+    pub release_time: String,
+    /// 版本信息的SHA1
+    pub sha1: String,
+}
+
+/// Mojang的版本清单(`version_manifest_v2.json`), 版本从新到旧排序
+///
+/// # 示例
+/// ```
+/// use mcscs::mojang::VersionManifest;
+/// let manifest: VersionManifest = serde_json::from_str(r#"{
+///     "latest": {"release": "1.20.1", "snapshot": "23w31a"},
+///     "versions": [
+///         {"id": "23w31a", "type": "snapshot", "url": "https://piston-meta.mojang.com/v1/packages/0/23w31a.json", "releaseTime": "2023-08-01T11:03:10+00:00", "sha1": "0"},
+///         {"id": "1.20.1", "type": "release", "url": "https://piston-meta.mojang.com/v1/packages/1/1.20.1.json", "releaseTime": "2023-06-12T13:25:51+00:00", "sha1": "1"}
+///     ]
+/// }"#).unwrap();
+/// assert_eq!(manifest.versions(false), ["1.20.1"]);
+/// assert_eq!(manifest.versions(true), ["23w31a", "1.20.1"]);
+/// assert!(manifest.version("1.0").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionManifest {
+    pub latest: Latest,
+    pub versions: Vec<ManifestVersion>,
+}
+
+impl VersionManifest {
+    /// 有服务器核心的Minecraft版本, `snapshots`为`true`时包括快照版本
+    pub fn versions(&self, snapshots: bool) -> Vec<String> {
+        self.versions
+            .iter()
+            .filter(|version| match version.version_type {
+                VersionType::Release => true,
+                VersionType::Snapshot => snapshots,
+                VersionType::OldBeta | VersionType::OldAlpha => false,
+            })
+            .map(|version| version.id.clone())
+            .collect()
+    }
+
+    /// 查找Minecraft版本`id`
+    pub fn version(&self, id: &str) -> Result<&ManifestVersion> {
+        self.versions
+            .iter()
+            .find(|version| version.id == id)
+            .ok_or_else(|| Error::Other(format!("Mojang没有Minecraft版本{id}")))
+    }
+}
+
+/// 版本信息里的一个下载文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Download {
+    pub sha1: String,
+    pub size: u64,
+    pub url: String,
+}
+
+/// 运行这个版本需要的Java
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JavaVersion {
+    /// 例如: `java-runtime-gamma`
+    pub component: String,
+    /// Java主版本号, 例如: `17`
+    pub major_version: u32,
+}
+
+/// 一个Minecraft版本的信息, 只读取需要的字段
+///
+/// # 示例
+/// ```
+/// use mcscs::mojang::VersionInfo;
+/// let info: VersionInfo = serde_json::from_str(r#"{
+///     "id": "1.20.1",
+///     "type": "release",
+///     "javaVersion": {"component": "java-runtime-gamma", "majorVersion": 17},
+///     "downloads": {"server": {"sha1": "84194a2f286ef7c14ed7ce0090dba59902951553", "size": 49150256, "url": "https://piston-data.mojang.com/v1/objects/84194a2f286ef7c14ed7ce0090dba59902951553/server.jar"}}
+/// }"#).unwrap();
+/// assert_eq!(info.java_version.unwrap().major_version, 17);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionInfo {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub version_type: VersionType,
+    /// 很早的版本没有记录
+    #[serde(default)]
+    pub java_version: Option<JavaVersion>,
+    pub downloads: HashMap<String, Download>,
+}
+
+impl VersionInfo {
+    /// 服务器核心, 很早的版本没有服务器核心时返回错误
+    pub fn server(&self) -> Result<&Download> {
+        self.downloads
+            .get("server")
+            .ok_or_else(|| Error::Other(format!("Minecraft {}没有服务器核心", self.id)))
+    }
+}
+
+/// 获取Mojang的版本清单
+///
+/// # 示例
+/// ```no_run
+/// use mcscs::mojang::get_version_manifest;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let manifest = get_version_manifest().await.unwrap();
+///     println!("{}", manifest.latest.release);
+/// }
+/// ```
+pub async fn get_version_manifest() -> Result<VersionManifest> {
+    let response = reqwest::get(VERSION_MANIFEST_URL)
+        .await?
+        .error_for_status()?;
+    Ok(response.json().await?)
+}
+
+/// 获取版本清单里一个版本的信息, 并使用清单里的SHA1校验
+pub async fn get_version_info(version: &ManifestVersion) -> Result<VersionInfo> {
+    let response = reqwest::get(&version.url).await?.error_for_status()?;
+    let data = response.bytes().await?;
+    let actual = get_sha1(&data);
+    if actual != version.sha1 {
+        return Err(Error::Checksum {
+            expected: version.sha1.clone(),
+            actual,
+        });
+    }
+    Ok(serde_json::from_slice(&data)?)
+}
+
+/// Mojang官方提供的原版核心([`VANILLA`])
+///
+/// 原版核心每个Minecraft版本只有一个构建版本, 构建版本是版本的类型, 例如: `release`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Mojang {
+    /// 列出Minecraft版本时是否包括快照版本, 下载时总是可以使用快照版本
+    pub snapshots: bool,
+}
+
+impl Mojang {
+    /// 读取`core`在`mc_version`下的版本信息, 检查核心名称和构建版本
+    async fn version_info(
+        &self,
+        core: &str,
+        mc_version: &str,
+        build: Option<&str>,
+    ) -> Result<VersionInfo> {
+        if core != VANILLA {
+            return Err(Error::Other(format!("Mojang上没有核心{core}")));
+        }
+        let manifest = get_version_manifest().await?;
+        let version = manifest.version(mc_version)?;
+        if let Some(build) = build {
+            if build != version.version_type.to_string() {
+                return Err(Error::Other(format!(
+                    "Mojang没有{core} {mc_version} {build}, 可用的构建版本: {}",
+                    version.version_type
+                )));
+            }
+        }
+        get_version_info(version).await
+    }
+}
+
+#[async_trait]
+impl CoreProvider for Mojang {
+    fn name(&self) -> &'static str {
+        "Mojang"
+    }
+
+    async fn cores(&self) -> Result<Vec<CoreEntry>> {
+        Ok(vec![CoreEntry {
+            name: VANILLA.to_string(),
+            tag: "vanilla".to_string(),
+            homepage: "https://www.minecraft.net".to_string(),
+            recommend: true,
+            mc_versions: get_version_manifest().await?.versions(self.snapshots),
+        }])
+    }
+
+    async fn builds(&self, core: &str, mc_version: &str) -> Result<Vec<Build>> {
+        if core != VANILLA {
+            return Err(Error::Other(format!("Mojang上没有核心{core}")));
+        }
+        let manifest = get_version_manifest().await?;
+        let version = manifest.version(mc_version)?;
+        Ok(vec![Build {
+            version: version.version_type.to_string(),
+            update_time: version.release_time.clone(),
+        }])
+    }
+
+    async fn download_url(&self, core: &str, mc_version: &str, build: &str) -> Result<String> {
+        let info = self.version_info(core, mc_version, Some(build)).await?;
+        Ok(info.server()?.url.clone())
+    }
+
+    async fn sha1(&self, core: &str, mc_version: &str, build: &str) -> Result<String> {
+        let info = self.version_info(core, mc_version, Some(build)).await?;
+        Ok(info.server()?.sha1.clone())
+    }
+
+    async fn java_version(&self, core: &str, mc_version: &str) -> Result<Option<u32>> {
+        let info = self.version_info(core, mc_version, None).await?;
+        Ok(info.java_version.map(|java| java.major_version))
+    }
+}
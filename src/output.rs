@@ -0,0 +1,266 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use clap::ValueEnum;
+use console::measure_text_width;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+use crate::pages::update::{newer_builds, newer_mc_versions};
+use crate::provider::{Build, ProviderKind};
+use crate::server::ServerConfig;
+use crate::status::ServerStatus;
+
+/// 命令行的输出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// 给人看的表格
+    #[default]
+    Table,
+    /// 给脚本读取的JSON, 格式见README
+    Json,
+}
+
+/// `mcscs list --output json`输出这个结构的数组, 在服务器配置的基础上添加运行状态
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerEntry {
+    #[serde(flatten)]
+    pub config: ServerConfig,
+    /// 服务器进程是否在运行, 无法读取`server.pid`或者`session.json`时为`None`
+    pub running: Option<bool>,
+    /// 服务器在运行并且可以查询到状态时不为`None`
+    pub status: Option<ServerStatus>,
+}
+
+impl ServerEntry {
+    /// 表格里的状态一栏
+    pub fn state(&self) -> &'static str {
+        match (self.running, &self.status) {
+            (_, Some(_)) => "在线",
+            (Some(true), None) => "启动中",
+            (Some(false), None) => "离线",
+            (None, None) => "未知",
+        }
+    }
+}
+
+/// FastMirror上的一个核心, `mcscs cores --output json`输出这个结构的数组
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoreEntry {
+    pub name: String,
+    pub tag: String,
+    pub homepage: String,
+    pub recommend: bool,
+    pub mc_versions: Vec<String>,
+}
+
+impl CoreEntry {
+    /// 把[`get_fastmirror_value`](crate::fastmirror::get_fastmirror_value)的返回值转换为核心列表
+    ///
+    /// # 示例
+    /// ```
+    /// use serde_json::json;
+    /// use mcscs::output::CoreEntry;
+    /// let fastmirror = json!({"Paper": {"name": "Paper", "tag": "plugins", "homepage": "https://papermc.io", "recommend": true, "mc_versions": ["1.20.1"]}});
+    /// let cores = CoreEntry::from_fastmirror(&fastmirror).unwrap();
+    /// assert_eq!(cores[0].mc_versions, ["1.20.1"]);
+    /// ```
+    pub fn from_fastmirror(fastmirror: &Value) -> Result<Vec<Self>> {
+        let mut cores = Vec::new();
+        for value in fastmirror
+            .as_object()
+            .into_iter()
+            .flat_map(|obj| obj.values())
+        {
+            cores.push(serde_json::from_value(value.clone())?);
+        }
+        Ok(cores)
+    }
+}
+
+/// 一个服务器的核心是否需要更新, `mcscs outdated --output json`输出这个结构的数组
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutdatedEntry {
+    pub name: String,
+    pub core: String,
+    pub mc_version: String,
+    pub build_version: String,
+    /// 同一个核心和Minecraft版本最新的构建版本, 来源上没有或者只能使用其他来源时为`None`
+    pub latest_build: Option<String>,
+    /// 有比当前更新的构建版本
+    pub outdated: bool,
+    /// 核心支持的更新的Minecraft版本, 从新到旧排序
+    pub newer_mc_versions: Vec<String>,
+}
+
+impl OutdatedEntry {
+    /// 比较服务器的核心和来源`provider`上的版本, `mc_versions`是核心支持的Minecraft版本,
+    /// `builds`是[`CoreProvider::builds`](crate::provider::CoreProvider::builds)的返回值
+    ///
+    /// 不同来源的构建版本格式不同, `provider`不是服务器记录的来源时不比较构建版本
+    ///
+    /// # 示例
+    /// ```
+    /// use serde_json::json;
+    /// use mcscs::output::OutdatedEntry;
+    /// use mcscs::provider::{Build, ProviderKind};
+    /// use mcscs::server::ServerConfig;
+    /// let server = ServerConfig::from_json(r#"{"schema_version": 1, "name": "test", "java": {"path": "java", "version": "17"}, "jvm_args": [], "encoding": "utf-8", "Xms": 1024, "Xmx": 1024, "info": {"core": "Paper", "mc_version": "1.20.1", "build_version": "build1"}, "server_args": []}"#).unwrap();
+    /// let builds = Build::from_fastmirror(&json!({"build2": {"update_time": "2024-03-02T00:00:00"}, "build1": {"update_time": "2024-03-01T00:00:00"}}));
+    /// let entry = OutdatedEntry::check(&server, ProviderKind::FastMirror, &["1.20.4".to_string()], &builds);
+    /// assert_eq!(entry.latest_build.as_deref(), Some("build2"));
+    /// assert!(entry.outdated);
+    /// assert_eq!(entry.newer_mc_versions, ["1.20.4"]);
+    /// ```
+    pub fn check(
+        server: &ServerConfig,
+        provider: ProviderKind,
+        mc_versions: &[String],
+        builds: &[Build],
+    ) -> Self {
+        let info = &server.info;
+        let builds = if provider == info.provider {
+            builds
+        } else {
+            &[]
+        };
+        let newer = newer_builds(&info.build_version, builds);
+        let latest_build = builds.first().map(|build| build.version.clone());
+        Self {
+            name: server.name.clone(),
+            core: info.core.clone(),
+            mc_version: info.mc_version.clone(),
+            build_version: info.build_version.clone(),
+            outdated: latest_build.is_some() && !newer.is_empty(),
+            latest_build,
+            newer_mc_versions: newer_mc_versions(&info.mc_version, mc_versions),
+        }
+    }
+}
+
+/// `mcscs outdated --exit-code`使用: 有服务器有新的构建版本, 或者有服务器无法检查时返回错误
+///
+/// 无法检查是指没有得到可以比较的最新构建版本, 例如来源暂时不可用
+pub fn check_outdated(entries: &[OutdatedEntry]) -> Result<()> {
+    let outdated = entries.iter().filter(|entry| entry.outdated).count();
+    let unchecked = entries
+        .iter()
+        .filter(|entry| entry.latest_build.is_none())
+        .count();
+    let mut problems = Vec::new();
+    if outdated > 0 {
+        problems.push(format!(
+            "{outdated}个服务器有新的构建版本, 使用`mcscs update <name>`更新"
+        ));
+    }
+    if unchecked > 0 {
+        problems.push(format!(
+            "{unchecked}个服务器无法检查是否有新的构建版本, 核心的来源可能暂时不可用"
+        ));
+    }
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Other(problems.join("; ")))
+    }
+}
+
+/// 把字节数转换为`Xms`/`Xmx`常用的写法, 是[`to_bytes`](crate::pages::create::to_bytes)的逆操作
+///
+/// # 示例
+/// ```
+/// use mcscs::output::format_bytes;
+/// assert_eq!(format_bytes(1073741824), "1G");
+/// assert_eq!(format_bytes(1536 * 1024 * 1024), "1536M");
+/// assert_eq!(format_bytes(1000), "1000");
+/// ```
+pub fn format_bytes(bytes: u64) -> String {
+    for (unit, size) in [
+        ("T", 1u64 << 40),
+        ("G", 1 << 30),
+        ("M", 1 << 20),
+        ("K", 1 << 10),
+    ] {
+        if bytes != 0 && bytes.is_multiple_of(size) {
+            return format!("{}{unit}", bytes / size);
+        }
+    }
+    bytes.to_string()
+}
+
+/// 把文件大小转换为便于阅读的写法
+///
+/// # 示例
+/// ```
+/// use mcscs::output::format_size;
+/// assert_eq!(format_size(512), "512B");
+/// assert_eq!(format_size(1536), "1.5KiB");
+/// assert_eq!(format_size(20 * 1024 * 1024 * 1024), "20.0GiB");
+/// ```
+pub fn format_size(bytes: u64) -> String {
+    let mut size = bytes as f64;
+    for unit in ["B", "KiB", "MiB", "GiB"] {
+        if size < 1024.0 {
+            return match unit {
+                "B" => format!("{bytes}B"),
+                _ => format!("{size:.1}{unit}"),
+            };
+        }
+        size /= 1024.0;
+    }
+    format!("{size:.1}TiB")
+}
+
+/// 把表格格式化为字符串, 按显示宽度对齐, 中文也可以对齐
+///
+/// # 示例
+/// ```
+/// use mcscs::output::format_table;
+/// let table = format_table(&["名称", "核心"], &[vec!["test".to_string(), "Paper".to_string()]]);
+/// assert_eq!(table, "名称  核心\ntest  Paper\n");
+/// ```
+pub fn format_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths = headers
+        .iter()
+        .map(|header| measure_text_width(header))
+        .collect::<Vec<usize>>();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(measure_text_width(cell));
+        }
+    }
+    let mut table = String::new();
+    let headers = headers.iter().map(|header| header.to_string()).collect();
+    for row in std::iter::once(&headers).chain(rows) {
+        let mut line = String::new();
+        for (i, (cell, width)) in row.iter().zip(&widths).enumerate() {
+            line.push_str(cell);
+            if i + 1 < row.len() {
+                line.push_str(&" ".repeat(width - measure_text_width(cell) + 2));
+            }
+        }
+        table.push_str(&line);
+        table.push('\n');
+    }
+    table
+}
+
+/// 按指定的格式输出, `rows`只在输出表格时调用
+pub fn print<T: Serialize>(
+    format: OutputFormat,
+    value: &T,
+    headers: &[&str],
+    rows: impl FnOnce(&T) -> Vec<Vec<String>>,
+) -> Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value)?),
+        OutputFormat::Table => print!("{}", format_table(headers, &rows(value))),
+    }
+    Ok(())
+}
@@ -0,0 +1,146 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use dialoguer::{theme::ColorfulTheme, Confirm, Input};
+
+use crate::backup::{
+    backup_server, list_backups, restore_backup, restore_to_new_server, BackupFormat,
+    BackupSettings,
+};
+use crate::control::is_running;
+use crate::error::{Error, Result};
+use crate::output::{format_size, format_table};
+use crate::select::{select_option, select_server};
+use crate::utils::{clear_console, input};
+
+/// 让用户输入一个数字, 默认为`current`
+fn number<T>(description: &str, current: T) -> Result<T>
+where
+    T: Clone + ToString + std::str::FromStr,
+    T::Err: ToString,
+{
+    Ok(Input::<T>::with_theme(&ColorfulTheme::default())
+        .with_prompt(description)
+        .default(current)
+        .interact_text()?)
+}
+
+/// 修改备份设置
+fn settings_page(name: &str) -> Result<()> {
+    let mut settings = BackupSettings::load(name)?;
+    let formats = [
+        BackupFormat::TarZst,
+        BackupFormat::Zip,
+        BackupFormat::Incremental,
+    ];
+    settings.format = formats[select_option(
+        "请选择备份格式",
+        &[
+            "tar.zst(压缩率更高)",
+            "zip(Windows可以直接打开)",
+            "增量备份(没有变化的文件不占用空间, 适合大的世界)",
+        ],
+    )?];
+    let retention = &mut settings.retention;
+    retention.hourly = number("每小时保留一个备份, 最多保留几个", retention.hourly)?;
+    retention.daily = number("每天保留一个备份, 最多保留几个", retention.daily)?;
+    retention.weekly = number("每周保留一个备份, 最多保留几个", retention.weekly)?;
+    let interval = number(
+        "服务器运行时每隔几分钟自动备份(0表示不自动备份)",
+        settings.interval.unwrap_or_default() / 60,
+    )?;
+    settings.interval = (interval > 0).then_some(interval * 60);
+    settings.save(name)?;
+    println!("备份设置已保存");
+    Ok(())
+}
+
+/// 选择一个备份并恢复
+fn restore_page(name: &str) -> Result<()> {
+    let backups = list_backups(name)?;
+    if backups.is_empty() {
+        println!("服务器{name}还没有备份");
+        return Ok(());
+    }
+    let options = backups
+        .iter()
+        .map(|backup| format!("{} ({})", backup.id, format_size(backup.size)))
+        .collect::<Vec<_>>();
+    let backup = &backups[select_option("请选择要恢复的备份", &options)?];
+    let to_new = select_option(
+        "请选择恢复的方式",
+        &[
+            "覆盖当前的世界(会先自动备份当前的世界)",
+            "恢复为一个新的服务器",
+        ],
+    )? == 1;
+    if to_new {
+        let new_name = input("请输入新服务器的名称")?;
+        restore_to_new_server(name, &backup.id, &new_name)?;
+        println!("已把备份{}恢复为新的服务器{new_name}", backup.id);
+        return Ok(());
+    }
+    let running = is_running(name)?;
+    if running
+        && !Confirm::new()
+            .with_prompt(format!("服务器{name}正在运行, 是否关闭服务器并恢复?"))
+            .interact()?
+    {
+        return Err(Error::Cancelled);
+    }
+    if let Some(snapshot) = restore_backup(name, &backup.id, running)? {
+        println!("恢复前的世界已备份为{}", snapshot.id);
+    }
+    println!("已把服务器{name}恢复到备份{}", backup.id);
+    Ok(())
+}
+
+/// 备份服务器页面
+pub fn main() -> Result<()> {
+    let server = match select_server()? {
+        Some(server) => server,
+        None => {
+            println!("你还没有创建任何一个服务器!");
+            return Ok(());
+        }
+    };
+    let options = vec!["立即备份", "查看备份", "恢复备份", "备份设置", "返回"];
+    loop {
+        let selection = select_option("请选择一个选项", &options)?;
+        if selection == options.len() - 1 {
+            return Ok(());
+        }
+        clear_console();
+        if selection == 0 {
+            println!("正在备份服务器{}的世界...", server.name);
+            let (backup, removed) = backup_server(&server.name)?;
+            println!(
+                "已备份到{}({})",
+                backup.path.display(),
+                format_size(backup.size)
+            );
+            if !removed.is_empty() {
+                println!("删除了{}个旧的备份", removed.len());
+            }
+        } else if selection == 1 {
+            let rows = list_backups(&server.name)?
+                .iter()
+                .map(|backup| vec![backup.id.clone(), format_size(backup.size)])
+                .collect::<Vec<_>>();
+            if rows.is_empty() {
+                println!("服务器{}还没有备份", server.name);
+            } else {
+                print!("{}", format_table(&["ID", "大小"], &rows));
+            }
+        } else if selection == 2 {
+            restore_page(&server.name)?;
+        } else {
+            settings_page(&server.name)?;
+        }
+    }
+}
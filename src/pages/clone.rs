@@ -0,0 +1,101 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::fs;
+
+use dialoguer::Confirm;
+use log::info;
+
+use crate::backup::world_dirs;
+use crate::control::is_running;
+use crate::error::{Error, Result};
+use crate::properties::assign_free_ports;
+use crate::select::select_server;
+use crate::server::{is_local_file, load_servers_lists, save_servers_lists, ServerConfig};
+use crate::session::SESSIONS_DIR;
+use crate::utils::{copy_entry, input};
+use crate::workspace::Workspace;
+
+/// 把服务器`name`复制为新的服务器`new_name`, 返回新服务器的配置
+///
+/// `worlds`为`false`时不复制世界文件夹, 新的服务器第一次启动时会生成新的世界.
+/// 新服务器的端口会改为其他服务器没有使用的端口. 复制世界时原来的服务器不能正在运行
+///
+/// # 示例
+/// ```no_run
+/// use mcscs::pages::clone::clone_server;
+/// let server = clone_server("survival", "survival-test", true).unwrap();
+/// assert_eq!(server.name, "survival-test");
+/// ```
+pub fn clone_server(name: &str, new_name: &str, worlds: bool) -> Result<ServerConfig> {
+    let mut server = load_servers_lists(Some(name))?
+        .remove(name)
+        .ok_or_else(|| Error::ServerNotFound(name.to_string()))?;
+    let workspace = Workspace::current()?;
+    let server_dir = workspace.server_dir(name);
+    let new_dir = workspace.server_dir(new_name);
+    server.name = new_name.to_string();
+    server.validate()?;
+    if new_dir.exists() {
+        return Err(Error::Other(format!("服务器{new_name}已存在")));
+    }
+    // 运行中的世界随时在写入, 复制出来的存档可能不完整
+    if worlds && is_running(name)? {
+        return Err(Error::Other(format!(
+            "服务器{name}正在运行, 请先关闭服务器或者不复制世界"
+        )));
+    }
+
+    let result = (|| {
+        // 配置会使用新的名称重新写入, 控制台日志属于原来的服务器
+        let mut skipped = vec![SESSIONS_DIR.to_string()];
+        if !worlds {
+            skipped.extend(world_dirs(&server_dir).unwrap_or_default());
+        }
+        fs::create_dir_all(&new_dir)?;
+        for entry in fs::read_dir(&server_dir)? {
+            let entry = entry?;
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            if skipped.contains(&file_name) || is_local_file(&file_name) {
+                continue;
+            }
+            copy_entry(&entry.path(), &new_dir.join(&file_name))?;
+        }
+        save_servers_lists(new_name, &server)?;
+        assign_free_ports(new_name)
+    })();
+    match result {
+        Ok(ports) => {
+            info!("已把服务器{name}复制为{new_name}, 端口: {ports:?}");
+            Ok(server)
+        }
+        Err(err) => {
+            let _ = fs::remove_dir_all(&new_dir);
+            Err(err)
+        }
+    }
+}
+
+/// 复制服务器页面
+pub fn main() -> Result<()> {
+    let server = match select_server()? {
+        Some(server) => server,
+        None => {
+            println!("你还没有创建任何一个服务器!");
+            return Ok(());
+        }
+    };
+    let new_name = input("请输入新服务器的名称")?;
+    let worlds = Confirm::new()
+        .with_prompt("是否复制世界?")
+        .default(true)
+        .interact()?;
+    let copied = clone_server(&server.name, &new_name, worlds)?;
+    println!("已把服务器{}复制为{}", server.name, copied.name);
+    Ok(())
+}
@@ -6,23 +6,227 @@
  * Vestibulum commodo. Ut rhoncus gravida arcu.
  */
 
-use std::error::Error;
+use std::{fs, path::PathBuf};
 
-use serde_json::json;
+use dialoguer::{theme::ColorfulTheme, Input};
+use log::info;
 
-use crate::pages::create::{encoding, jvm_args, server_args, xms, xmx};
+use crate::control::is_running;
+use crate::error::{Error, Result};
+use crate::java::{get_java_version, Java};
+use crate::pages::create::{encoding, jvm_args, parse_memory, server_args, xms, xmx};
+use crate::properties::{
+    property_spec, validate_property, Properties, PropertyKind, KNOWN_PROPERTIES, PROPERTIES_FILE,
+};
 use crate::select::{select_option, select_server};
-use crate::server::save_servers_lists;
-use crate::utils::clear_console;
+use crate::server::{load_servers_lists, save_servers_lists, ConfigError, ServerConfig};
+use crate::session::{PID_FILE, SESSION_FILE};
+use crate::utils::{clear_console, input};
+use crate::workspace::Workspace;
 
-/// 配置服务器页面
-pub fn main() -> Result<(), Box<dyn Error>> {
-    let mut server = select_server();
-    if server.is_null() {
-        println!("你还没有创建任何一个服务器!");
-        return Ok(());
+/// 可以通过[`set_config_value`]修改的配置项
+pub const CONFIG_KEYS: [&str; 6] = ["Xms", "Xmx", "encoding", "jvm_args", "server_args", "java"];
+
+/// 返回服务器的一个配置项, 参数用空格连接
+pub fn get_config_value(server: &ServerConfig, key: &str) -> Result<String> {
+    Ok(match key.to_lowercase().as_str() {
+        "xms" => server.xms.to_string(),
+        "xmx" => server.xmx.to_string(),
+        "encoding" => server.encoding.clone(),
+        "jvm_args" => server.jvm_args.join(" "),
+        "server_args" => server.server_args.join(" "),
+        "java" => server.java.path.display().to_string(),
+        _ => return Err(unknown_key(key)),
+    })
+}
+
+/// 修改服务器的一个配置项, 不会保存到文件
+///
+/// * `Xms`/`Xmx`: 内存大小, 例如: `4G`
+/// * `jvm_args`/`server_args`: 用空格分隔的参数
+/// * `java`: Java可执行程序的路径
+///
+/// # 示例
+/// ```no_run
+/// use mcscs::pages::config::set_config_value;
+/// use mcscs::server::load_servers_lists;
+/// let mut server = load_servers_lists(Some("server_name")).unwrap().remove("server_name").unwrap();
+/// set_config_value(&mut server, "Xmx", "4G").unwrap();
+/// ```
+pub fn set_config_value(server: &mut ServerConfig, key: &str, value: &str) -> Result<()> {
+    match key.to_lowercase().as_str() {
+        "xms" => server.xms = parse_memory("Xms", value)?,
+        "xmx" => server.xmx = parse_memory("Xmx", value)?,
+        "encoding" => server.encoding = value.to_lowercase(),
+        "jvm_args" => server.jvm_args = value.split_whitespace().map(String::from).collect(),
+        "server_args" => server.server_args = value.split_whitespace().map(String::from).collect(),
+        "java" => {
+            let path = PathBuf::from(value);
+            let version = get_java_version(&path)?;
+            server.java = Java { path, version };
+        }
+        _ => return Err(unknown_key(key)),
+    }
+    server.validate()?;
+    Ok(())
+}
+
+fn unknown_key(key: &str) -> Error {
+    ConfigError::new(
+        key,
+        format!("未知的配置项, 可用的配置项: {}", CONFIG_KEYS.join(", ")),
+    )
+    .into()
+}
+
+/// 重命名服务器, 移动服务器文件夹和备份文件夹并修改`config.json`里的名称, 返回新的配置
+///
+/// 服务器正在运行时返回错误. 备份设置(包括自动备份)保存在备份文件夹里, 会一起移动;
+/// 已经退出的服务器留下的`server.pid`和`session.json`会被删除
+///
+/// # 示例
+/// ```no_run
+/// use mcscs::pages::config::rename_server;
+/// let server = rename_server("server_name", "new_name").unwrap();
+/// assert_eq!(server.name, "new_name");
+/// ```
+pub fn rename_server(name: &str, new_name: &str) -> Result<ServerConfig> {
+    let mut server = load_servers_lists(Some(name))?
+        .remove(name)
+        .ok_or_else(|| Error::ServerNotFound(name.to_string()))?;
+    server.name = new_name.to_string();
+    server.validate()?;
+    let workspace = Workspace::current()?;
+    let (server_dir, new_dir) = (workspace.server_dir(name), workspace.server_dir(new_name));
+    let (backup_dir, new_backup_dir) = (workspace.backup_dir(name), workspace.backup_dir(new_name));
+    if new_dir.exists() {
+        return Err(ConfigError::new("name", format!("服务器{new_name}已存在")).into());
+    }
+    if backup_dir.exists() && new_backup_dir.exists() {
+        return Err(Error::Other(format!(
+            "{}已存在, 请先移走其中的备份",
+            new_backup_dir.display()
+        )));
+    }
+    if is_running(name)? {
+        return Err(Error::Other(format!("服务器{name}正在运行, 请先关闭")));
+    }
+    for stale in [PID_FILE, SESSION_FILE] {
+        let path = server_dir.join(stale);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+
+    fs::rename(&server_dir, &new_dir)?;
+    let result = save_servers_lists(new_name, &server).and_then(|()| {
+        if backup_dir.exists() {
+            fs::rename(&backup_dir, &new_backup_dir)?;
+        }
+        Ok(())
+    });
+    if let Err(err) = result {
+        // 恢复原来的名称, 原来的config.json可能已经被覆盖
+        server.name = name.to_string();
+        let _ = save_servers_lists(new_name, &server);
+        let _ = fs::rename(&new_dir, &server_dir);
+        return Err(err);
+    }
+    info!("已把服务器{name}重命名为{new_name}");
+    Ok(server)
+}
+
+fn properties_path(name: &str) -> Result<PathBuf> {
+    Ok(Workspace::current()?.server_dir(name).join(PROPERTIES_FILE))
+}
+
+/// 读取服务器的`server.properties`, 文件不存在时(服务器还没有启动过)返回空的配置
+pub fn load_properties(name: &str) -> Result<Properties> {
+    Properties::load(&properties_path(name)?)
+}
+
+/// 检查并修改服务器`server.properties`里的一个配置项, 保留注释和其他配置项的顺序
+///
+/// 服务器正在运行时需要重启才会生效
+///
+/// # 示例
+/// ```no_run
+/// use mcscs::pages::config::set_property;
+/// set_property("server_name", "max-players", "50").unwrap();
+/// ```
+pub fn set_property(name: &str, key: &str, value: &str) -> Result<()> {
+    let value = validate_property(key, value)?;
+    let path = properties_path(name)?;
+    let mut properties = Properties::load(&path)?;
+    properties.set(key, &value);
+    properties.save(&path)?;
+    info!("{}: {key}={value}", path.display());
+    Ok(())
+}
+
+/// 让用户输入配置项的新值, 布尔值和可选的值从列表里选择
+fn property_value(key: &str, current: Option<&str>) -> Result<String> {
+    let kind = property_spec(key).map(|spec| spec.kind);
+    let values = match kind {
+        Some(PropertyKind::Bool) => &["true", "false"][..],
+        Some(PropertyKind::Enum(values)) => values,
+        _ => loop {
+            let value = Input::<String>::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!("请输入{key}的值"))
+                .with_initial_text(current.unwrap_or_default())
+                .allow_empty(true)
+                .interact_text()?;
+            match validate_property(key, &value) {
+                Ok(value) => return Ok(value),
+                Err(err) => println!("输入错误,{},请重新输入!", err.message),
+            }
+        },
+    };
+    let selection = select_option(&format!("请选择{key}的值"), values)?;
+    Ok(values[selection].to_string())
+}
+
+/// 编辑`server.properties`页面
+fn properties_page(name: &str) -> Result<()> {
+    loop {
+        let properties = load_properties(name)?;
+        let mut options = KNOWN_PROPERTIES
+            .iter()
+            .map(|spec| {
+                format!(
+                    "{}({}) = {}",
+                    spec.description,
+                    spec.key,
+                    properties.get(spec.key).unwrap_or_default()
+                )
+            })
+            .collect::<Vec<String>>();
+        options.push("其他配置项".to_string());
+        options.push("返回".to_string());
+        let selection = select_option("请选择要修改的配置项", &options)?;
+        if selection == options.len() - 1 {
+            return Ok(());
+        }
+        let key = match KNOWN_PROPERTIES.get(selection) {
+            Some(spec) => spec.key.to_string(),
+            None => input("请输入配置项的名称")?,
+        };
+        let value = property_value(&key, properties.get(&key))?;
+        set_property(name, &key, &value)?;
+        clear_console();
     }
-    let server_name = &server["name"].as_str().unwrap_or_default().to_string();
+}
+
+/// 配置服务器页面
+pub fn main() -> Result<()> {
+    let mut server = match select_server()? {
+        Some(server) => server,
+        None => {
+            println!("你还没有创建任何一个服务器!");
+            return Ok(());
+        }
+    };
+    let mut server_name = server.name.clone();
 
     let options = vec![
         "XMS(JVM初始堆内存)",
@@ -30,6 +234,8 @@ pub fn main() -> Result<(), Box<dyn Error>> {
         "输入和输出的编码",
         "其他JVM参数",
         "服务器参数",
+        "服务器属性(server.properties)",
+        "重命名服务器",
         "返回",
     ];
     loop {
@@ -42,23 +248,28 @@ pub fn main() -> Result<(), Box<dyn Error>> {
             println!("1GiB = 1024MB, 1GB = 1000MB");
             println!("1MiB = 1024KB, 1MB = 1000KB");
             println!("1KiB = 1024Bytes, 1KB = 1000Bytes");
-            server["Xms"] = json!(xms(Some(server["Xmx"].as_u64().unwrap_or_default())));
-            save_servers_lists(server_name, &server);
+            server.xms = xms(Some(server.xmx))?;
+            save_servers_lists(&server_name, &server)?;
         } else if selection == 1 {
             println!("1GiB = 1024MB, 1GB = 1000MB");
             println!("1MiB = 1024KB, 1MB = 1000KB");
             println!("1KiB = 1024Bytes, 1KB = 1000Bytes");
-            server["Xmx"] = json!(xmx(server["Xms"].as_u64().unwrap_or_default()));
-            save_servers_lists(server_name, &server);
+            server.xmx = xmx(server.xms)?;
+            save_servers_lists(&server_name, &server)?;
         } else if selection == 2 {
-            server["encoding"] = json!(encoding());
-            save_servers_lists(server_name, &server);
+            server.encoding = encoding()?;
+            save_servers_lists(&server_name, &server)?;
         } else if selection == 3 {
-            server["jvm_args"] = json!(jvm_args(Some(&server["jvm_args"])));
-            save_servers_lists(server_name, &server);
+            server.jvm_args = jvm_args(Some(&server.jvm_args))?;
+            save_servers_lists(&server_name, &server)?;
         } else if selection == 4 {
-            server["server_args"] = json!(server_args(Some(&server["server_args"])));
-            save_servers_lists(server_name, &server);
+            server.server_args = server_args(Some(&server.server_args))?;
+            save_servers_lists(&server_name, &server)?;
+        } else if selection == 5 {
+            properties_page(&server_name)?;
+        } else if selection == 6 {
+            server = rename_server(&server_name, &input("请输入新的名称")?)?;
+            server_name = server.name.clone();
         }
         clear_console();
     }
@@ -6,59 +6,84 @@
  * Vestibulum commodo. Ut rhoncus gravida arcu.
  */
 
-use std::error::Error;
-use std::{collections::HashMap, env, fs};
+use std::{collections::HashMap, fs};
 
-use log::error;
-use serde_json::{json, Value};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input};
+use log::{error, info};
 
+use crate::error::{Error, Result};
 use crate::select::{select_file, select_option};
 use crate::utils::input;
 use crate::{
-    fastmirror::{download_server_core, get_fastmirror_builds_value, get_fastmirror_value},
-    java::{detect_java, get_java_version, load_java_lists, save_java_lists},
-    server::{load_servers_lists, save_servers_lists},
+    java::{detect_java, find_java, get_java_version, load_java_lists, save_java_lists, Java},
+    migrate::SCHEMA_VERSION,
+    mojang::Mojang,
+    properties::{free_port, validate_property, Properties, DEFAULT_SERVER_PORT, PROPERTIES_FILE},
+    provider::{CoreProvider, ProviderKind},
+    rcon::enable_rcon,
+    server::{
+        load_servers_lists, save_servers_lists, validate_name, ConfigError, CoreInfo, ServerConfig,
+    },
+    workspace::Workspace,
 };
 
 /// 返回用户输入的服务器名称
-pub fn name() -> String {
-    let servers = load_servers_lists(None);
+pub fn name() -> Result<String> {
+    let servers = load_servers_lists(None)?;
     loop {
-        let name = input("请输入该服务器的名称");
-        if servers.get(&name).is_some() {
+        let name = input("请输入该服务器的名称")?;
+        if let Err(err) = validate_name(&name) {
+            println!("输入错误,{},请重新输入!", err.message);
+            continue;
+        }
+        if servers.contains_key(&name) {
             println!("输入错误,服务器已存在,请重新输入!");
             continue;
         }
-        return name;
+        return Ok(name);
     }
 }
 
 /// 返回用户选择/手动输入的Java环境
-pub fn java() -> Result<Value, Box<dyn Error>> {
+///
+/// `required`为需要的Java主版本号, 有满足要求的Java时先询问是否使用它
+pub fn java(required: Option<u32>) -> Result<Java> {
+    if let Some(required) = required {
+        match find_java(&load_java_lists().unwrap_or_default(), required) {
+            Some(java)
+                if Confirm::new()
+                    .with_prompt(format!(
+                        "是否使用Java {}({})?",
+                        java.version,
+                        java.path.display()
+                    ))
+                    .default(true)
+                    .interact()? =>
+            {
+                return Ok(java);
+            }
+            _ => println!("需要Java {required}或更高的版本"),
+        }
+    }
     loop {
-        let javas = load_java_lists();
+        let javas = load_java_lists()?;
         let mut options: Vec<String> = Vec::new();
-        for java in javas.as_array().unwrap() {
-            let info = format!(
-                "{}: {}",
-                java["version"].as_str().unwrap(),
-                java["path"].as_str().unwrap()
-            );
-            options.push(info);
+        for java in &javas {
+            options.push(format!("{}: {}", java.version, java.path.display()));
         }
         options.push("重新检测Java环境".to_string());
         options.push("手动选择Java可执行程序".to_string());
         let selection = select_option("请选择一个Java环境或选项", &options)?;
         if selection == options.len() - 2 {
-            save_java_lists(&detect_java());
+            save_java_lists(&detect_java()?)?;
             println!("刷新成功!");
             continue;
         }
         if selection == options.len() - 1 {
             #[cfg(target_os = "windows")]
-            let java_path = select_file("java.exe").unwrap();
+            let java_path = select_file("java.exe")?;
             #[cfg(not(target_os = "windows"))]
-            let java_path = select_file("请选择一个Java可执行程序").unwrap();
+            let java_path = select_file("请选择一个Java可执行程序")?;
             if let Ok(metadata) = fs::metadata(&java_path) {
                 if metadata.is_file() {
                     let java_ver = get_java_version(&java_path);
@@ -66,24 +91,27 @@ pub fn java() -> Result<Value, Box<dyn Error>> {
                         println!("Java无效!");
                         continue;
                     }
-                    return Ok(json!({"path": java_path, "version": java_ver.unwrap()}));
+                    return Ok(Java {
+                        path: java_path,
+                        version: java_ver?,
+                    });
                 }
-                println!("Java不存在!");
-                continue;
             }
+            println!("Java不存在!");
+            continue;
         }
         return Ok(javas[selection].clone());
     }
 }
 
 /// 返回用户选择的编码格式
-pub fn encoding() -> String {
+pub fn encoding() -> Result<String> {
     let options = vec!["UTF-8", "GBK", "ANSI", "ASCII"];
-    let selection = select_option("请选择需要使用的编码格式", &options).unwrap();
-    options[selection].to_lowercase()
+    let selection = select_option("请选择需要使用的编码格式", &options)?;
+    Ok(options[selection].to_lowercase())
 }
 
-/// 将类似1G,1M等等的字节单位转换为Bytes
+/// 将类似1G,1M等等的字节单位转换为Bytes, 格式无效或者超出`u64`范围时返回0
 ///
 /// # 示例
 /// ```
@@ -128,7 +156,12 @@ pub fn to_bytes(byte: &str) -> u64 {
     unit_json.insert("".to_string(), 1);
 
     match num_part.parse::<u64>() {
-        Ok(num) => num * (*unit_json.get(&unit_part).unwrap_or(&0)),
+        Ok(num) => num
+            .checked_mul(*unit_json.get(&unit_part).unwrap_or(&0))
+            .unwrap_or_else(|| {
+                error!("{byte}超出范围");
+                0
+            }),
         Err(e) => {
             error!("{e}");
             0
@@ -136,76 +169,86 @@ pub fn to_bytes(byte: &str) -> u64 {
     }
 }
 
+/// 将类似1G的内存大小转换为Bytes并检查是否有效, `field`为出错时提示的配置项
+///
+/// # 示例
+/// ```
+/// use mcscs::pages::create::parse_memory;
+/// assert_eq!(parse_memory("Xms", "512M").unwrap(), 512 * 1024 * 1024);
+/// assert!(parse_memory("Xms", "1K").is_err());
+/// ```
+pub fn parse_memory(field: &str, value: &str) -> Result<u64> {
+    let bytes = to_bytes(value);
+    if bytes == 0 {
+        return Err(ConfigError::new(field, format!("无效的内存大小: {value}")).into());
+    }
+    if bytes < to_bytes("1MiB") {
+        return Err(ConfigError::new(field, format!("{field}不能小于1MiB")).into());
+    }
+    if let Ok(mem) = sys_info::mem_info() {
+        if bytes > (mem.total * 1024) {
+            return Err(ConfigError::new(field, format!("{field}不能大于系统内存")).into());
+        }
+    }
+    Ok(bytes)
+}
+
 /// 返回用户输入的XMS(JVM虚拟机初始堆内存)
 ///
 /// # 示例
 /// * 使用场景: 创建服务器
 /// ```
 /// use mcscs::pages::create::xms;
-/// let xms = xms(None);
+/// let xms = xms(None).unwrap();
 /// println!("{xms}");
 /// ```
 /// * 使用场景: 配置服务器, 服务器的XMX为1GB
 /// ```
 /// use mcscs::pages::create::{to_bytes, xmx};
-/// let xms = xmx(to_bytes("1GB"));
+/// let xms = xmx(to_bytes("1GB")).unwrap();
 /// println!("{xms}");
 /// ```
-pub fn xms(xmx: Option<u64>) -> u64 {
+pub fn xms(xmx: Option<u64>) -> Result<u64> {
     loop {
-        let input_value = input("请输入Xms(JVM虚拟机初始堆内存)的大小");
-        let bytes = to_bytes(&input_value);
-        if bytes == 0 {
-            println!("输入错误,请重新输入!");
-            continue;
-        }
-        if bytes < to_bytes("1MiB") {
-            println!("输入错误,Xms不能小于1MiB,请重新输入!");
-            continue;
-        }
-        if let Ok(mem) = sys_info::mem_info() {
-            if bytes > (mem.total * 1024) {
-                println!("输入错误,Xms不能大于系统内存,请重新输入!");
+        let input_value = input("请输入Xms(JVM虚拟机初始堆内存)的大小")?;
+        let bytes = match parse_memory("Xms", &input_value) {
+            Ok(bytes) => bytes,
+            Err(Error::Config(err)) => {
+                println!("输入错误,{},请重新输入!", err.message);
                 continue;
             }
-        }
+            Err(err) => return Err(err),
+        };
         return match xmx {
             Some(xmx) => {
                 if bytes > xmx {
                     println!("输入错误,Xms不能大于Xmx,请重新输入!");
                     continue;
                 }
-                bytes
+                Ok(bytes)
             }
-            None => bytes,
+            None => Ok(bytes),
         };
     }
 }
 
 /// 返回用户输入的XMX(JVM虚拟机最大堆内存)
-pub fn xmx(xms: u64) -> u64 {
+pub fn xmx(xms: u64) -> Result<u64> {
     loop {
-        let input_value = input("请输入Xmx(JVM虚拟机最大堆内存)的大小");
-        let bytes = to_bytes(&input_value);
-        if bytes == 0 {
-            println!("输入错误,请重新输入!");
-            continue;
-        }
-        if bytes < to_bytes("1MiB") {
-            println!("输入错误,Xmx不能小于1MiB,请重新输入!");
-            continue;
-        }
-        if let Ok(mem) = sys_info::mem_info() {
-            if bytes > (mem.total * 1024) {
-                println!("输入错误,Xms不能大于系统内存,请重新输入!");
+        let input_value = input("请输入Xmx(JVM虚拟机最大堆内存)的大小")?;
+        let bytes = match parse_memory("Xmx", &input_value) {
+            Ok(bytes) => bytes,
+            Err(Error::Config(err)) => {
+                println!("输入错误,{},请重新输入!", err.message);
                 continue;
             }
-        }
+            Err(err) => return Err(err),
+        };
         if bytes < xms {
             println!("输入错误,Xmx不能小于Xms,请重新输入!");
             continue;
         }
-        return bytes;
+        return Ok(bytes);
     }
 }
 
@@ -214,53 +257,43 @@ pub fn xmx(xms: u64) -> u64 {
 /// # 示例
 /// * 使用场景: 创建服务器
 /// ```
-/// // 如果是None配置默认会是json!(["-Dlog4j2.formatMsgNoLookups=true"])
+/// // 如果是None配置默认会是["-Dlog4j2.formatMsgNoLookups=true"]
 /// use mcscs::pages::create::jvm_args;
-/// let jvm_args = jvm_args(None);
-/// println!("{jvm_args}");
+/// let jvm_args = jvm_args(None).unwrap();
+/// println!("{jvm_args:?}");
 /// ```
 /// * 使用场景: 配置服务器
 /// ```
-/// use serde_json::json;
 /// use mcscs::pages::create::jvm_args;
-/// let config = json!(["JVM虚拟机参数", "..."]);
-/// let jvm_args = jvm_args(Some(&config));
-/// println!("{jvm_args}");
+/// let config = vec!["JVM虚拟机参数".to_string(), "...".to_string()];
+/// let jvm_args = jvm_args(Some(&config)).unwrap();
+/// println!("{jvm_args:?}");
 /// ```
-pub fn jvm_args(jvm_args: Option<&Value>) -> Value {
-    let mut args = Vec::<Value>::new();
-    if let Some(jvm_args) = jvm_args {
-        for arg in jvm_args.as_array().unwrap() {
-            args.push(arg.clone());
-        }
-    } else {
-        args.push(json!("-Dlog4j2.formatMsgNoLookups=true"));
-    }
+pub fn jvm_args(jvm_args: Option<&[String]>) -> Result<Vec<String>> {
+    let mut args = match jvm_args {
+        Some(jvm_args) => jvm_args.to_vec(),
+        None => vec!["-Dlog4j2.formatMsgNoLookups=true".to_string()],
+    };
     loop {
-        let mut display_args = args
-            .iter()
-            .filter_map(|value| value.as_str())
-            .collect::<Vec<&str>>();
+        let mut display_args = args.iter().map(String::as_str).collect::<Vec<&str>>();
         display_args.push("新参数");
         display_args.push("确认");
         let selection = select_option(
             "请选择一个选项或要更改的JVM虚拟机参数(如果为空即为移除参数)",
             &display_args,
-        )
-        .unwrap();
+        )?;
         if selection == display_args.len() - 2 {
-            let input_arg = input("请输入参数");
-            args.push(json!(input_arg));
+            args.push(input("请输入参数")?);
             continue;
         }
         if selection == display_args.len() - 1 {
-            return json!(args);
+            return Ok(args);
         }
-        let input_arg = input("请输入参数");
+        let input_arg = input("请输入参数")?;
         if input_arg.is_empty() {
             args.remove(selection);
         } else {
-            args[selection] = json!(input_arg);
+            args[selection] = input_arg;
         }
         continue;
     }
@@ -271,153 +304,314 @@ pub fn jvm_args(jvm_args: Option<&Value>) -> Value {
 /// # 使用
 /// * 使用场景: 创建服务器
 /// ```
-/// // 如果是None配置默认会是json!(["--nogui"])
+/// // 如果是None配置默认会是["--nogui"]
 /// use mcscs::pages::create::server_args;
-/// let server_args = server_args(None);
-/// println!("{server_args}");
+/// let server_args = server_args(None).unwrap();
+/// println!("{server_args:?}");
 /// ```
 /// * 使用场景: 配置服务器
 /// ```
-/// use serde_json::json;
 /// use mcscs::pages::create::server_args;
-/// let config = json!(["服务器参数", "..."]);
-/// let server_args = server_args(Some(&config));
-/// println!("{server_args}");
+/// let config = vec!["服务器参数".to_string(), "...".to_string()];
+/// let server_args = server_args(Some(&config)).unwrap();
+/// println!("{server_args:?}");
 /// ```
-pub fn server_args(server_args: Option<&Value>) -> Value {
-    let mut args = Vec::<Value>::new();
-    if let Some(server_args) = server_args {
-        for arg in server_args.as_array().unwrap() {
-            args.push(arg.clone());
-        }
-    } else {
-        args.push(json!("--nogui"));
-    }
+pub fn server_args(server_args: Option<&[String]>) -> Result<Vec<String>> {
+    let mut args = match server_args {
+        Some(server_args) => server_args.to_vec(),
+        None => vec!["--nogui".to_string()],
+    };
     loop {
-        let mut display_args = args
-            .iter()
-            .filter_map(|value| value.as_str())
-            .collect::<Vec<&str>>();
+        let mut display_args = args.iter().map(String::as_str).collect::<Vec<&str>>();
         display_args.push("新参数");
         display_args.push("确认");
         let selection = select_option(
             "请选择一个选项或要更改的服务器参数(如果为空即为移除参数)",
             &display_args,
-        )
-        .unwrap();
+        )?;
         if selection == display_args.len() - 2 {
-            let input_arg = input("请输入参数");
-            args.push(json!(input_arg));
+            args.push(input("请输入参数")?);
             continue;
         }
         if selection == display_args.len() - 1 {
-            return json!(args);
+            return Ok(args);
         }
-        let input_arg = input("请输入参数");
+        let input_arg = input("请输入参数")?;
         if input_arg.is_empty() {
             args.remove(selection);
         } else {
-            args[selection] = json!(input_arg);
+            args[selection] = input_arg;
         }
         continue;
     }
 }
 
-/// 返回用户选择的服务器核心
-pub async fn core() -> String {
-    let fastmirror = get_fastmirror_value().await;
-    let mut options = Vec::<String>::new();
-    let mut cores = Vec::<&str>::new();
-    if let Some(obj) = fastmirror.as_object() {
-        for (core, value) in obj {
-            options.push(format!("{core}(标签: {})", value["tag"].as_str().unwrap()));
-            cores.push(core);
-        }
+/// 返回用户选择的核心来源和服务器核心
+///
+/// 有多个来源时先让用户选择, 选择的来源不可用时可以换成其他来源
+pub async fn core() -> Result<(ProviderKind, String)> {
+    let mut kinds = ProviderKind::ALL.to_vec();
+    loop {
+        let kind = match kinds.as_slice() {
+            [kind] => *kind,
+            _ => {
+                let options = kinds.iter().map(ToString::to_string).collect::<Vec<_>>();
+                kinds[select_option("请选择核心的来源", &options)?]
+            }
+        };
+        let cores = match kind.provider().cores().await {
+            Ok(cores) => cores,
+            Err(err) => {
+                error!("{kind}不可用: {err}");
+                kinds.retain(|other| *other != kind);
+                if kinds.is_empty() {
+                    return Err(err);
+                }
+                println!("{kind}不可用({err}), 请选择其他来源");
+                continue;
+            }
+        };
+        let options = cores
+            .iter()
+            .map(|core| format!("{}(标签: {})", core.name, core.tag))
+            .collect::<Vec<_>>();
+        let selection = select_option("请选择一个使用的核心", &options)?;
+        return Ok((kind, cores[selection].name.clone()));
     }
-    let selection = select_option("请选择一个使用的核心", &options).unwrap();
-    cores[selection].to_string()
 }
 
 /// 返回用户选择的服务器核心支持的Minecraft版本
-pub async fn mc_version(core: &str) -> String {
-    let fastmirror = get_fastmirror_value().await;
-    let mut options = Vec::<&str>::new();
-    if let Some(arr) = fastmirror[&core]["mc_versions"].as_array() {
-        for version in arr {
-            if let Some(version) = version.as_str() {
-                options.push(version);
-            }
-        }
+pub async fn mc_version(provider: &dyn CoreProvider, core: &str) -> Result<String> {
+    let options = provider.mc_versions(core).await?;
+    if options.is_empty() {
+        return Err(Error::Other(format!(
+            "{}上没有{core}可用的Minecraft版本",
+            provider.name()
+        )));
     }
-    let selection = select_option("请选择一个使用的minecraft版本", &options).unwrap();
-    options[selection].to_string()
+    let selection = select_option("请选择一个使用的minecraft版本", &options)?;
+    Ok(options[selection].to_string())
 }
 
 /// 返回用户选择的构建版本
-pub async fn build_version(core: &str, mc_version: &str) -> String {
-    let fastmirror = get_fastmirror_builds_value(core, mc_version).await;
-    let mut options = Vec::<String>::new();
-    let mut builds = Vec::<&str>::new();
-    if let Some(obj) = fastmirror.as_object() {
-        for (build, value) in obj {
-            options.push(format!("{build}(更新时间: {})", value["update_time"]));
-            builds.push(build);
-        }
+pub async fn build_version(
+    provider: &dyn CoreProvider,
+    core: &str,
+    mc_version: &str,
+) -> Result<String> {
+    let builds = provider.builds(core, mc_version).await?;
+    if builds.is_empty() {
+        return Err(Error::Other(format!(
+            "{}上没有{core} {mc_version}可用的构建版本",
+            provider.name()
+        )));
     }
-    let selection = select_option("请选择一个使用的构建版本", &options).unwrap();
-    builds[selection].to_string()
+    let options = builds
+        .iter()
+        .map(|build| format!("{}(更新时间: {})", build.version, build.update_time))
+        .collect::<Vec<_>>();
+    let selection = select_option("请选择一个使用的构建版本", &options)?;
+    Ok(builds[selection].version.clone())
 }
 
-/// 创建服务器页面
-pub async fn main() -> Result<(), Box<dyn Error>> {
-    let mut configs = json!({});
+/// 世界类型, 新版本的服务器也能识别这些旧的名称
+pub const LEVEL_TYPES: [&str; 4] = ["default", "flat", "largeBiomes", "amplified"];
 
-    // 服务器的名称
-    let name = name();
-    configs["name"] = json!(name);
+/// 创建服务器时预先写入`server.properties`的设置, 为`None`的配置项在第一次启动时使用服务器的默认值
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorldSettings {
+    pub port: Option<u16>,
+    pub level_seed: Option<String>,
+    pub level_type: Option<String>,
+    pub gamemode: Option<String>,
+    pub difficulty: Option<String>,
+    pub online_mode: Option<bool>,
+    pub max_players: Option<u32>,
+}
 
-    // 设置使用的Java
-    configs["java"] = java()?;
+impl WorldSettings {
+    /// 返回要写入`server.properties`的配置项
+    ///
+    /// # 示例
+    /// ```
+    /// use mcscs::pages::create::WorldSettings;
+    /// let settings = WorldSettings {
+    ///     port: Some(25566),
+    ///     online_mode: Some(false),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(
+    ///     settings.properties(),
+    ///     [("server-port", "25566".to_string()), ("online-mode", "false".to_string())]
+    /// );
+    /// ```
+    pub fn properties(&self) -> Vec<(&'static str, String)> {
+        [
+            ("server-port", self.port.map(|port| port.to_string())),
+            ("level-seed", self.level_seed.clone()),
+            ("level-type", self.level_type.clone()),
+            ("gamemode", self.gamemode.clone()),
+            ("difficulty", self.difficulty.clone()),
+            ("online-mode", self.online_mode.map(|mode| mode.to_string())),
+            ("max-players", self.max_players.map(|max| max.to_string())),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value?)))
+        .collect()
+    }
+}
+
+/// 返回一个其他服务器没有使用的服务器端口, 从25565开始
+pub fn free_server_port(name: &str) -> Result<u16> {
+    free_port(DEFAULT_SERVER_PORT, name)?.ok_or_else(|| Error::Other("没有可用的端口".into()))
+}
+
+/// 把世界设置写入服务器的`server.properties`, 写入前检查每个值是否有效
+pub fn write_world_settings(name: &str, settings: &WorldSettings) -> Result<()> {
+    let path = Workspace::current()?.server_dir(name).join(PROPERTIES_FILE);
+    let mut properties = Properties::load(&path)?;
+    for (key, value) in settings.properties() {
+        properties.set(key, &validate_property(key, &value)?);
+    }
+    properties.save(&path)?;
+    info!("{}: {:?}", path.display(), settings);
+    Ok(())
+}
+
+/// 返回用户输入的世界设置
+pub fn world_settings(name: &str) -> Result<WorldSettings> {
+    let theme = ColorfulTheme::default();
+    let port = Input::<u16>::with_theme(&theme)
+        .with_prompt("请输入服务器端口")
+        .default(free_server_port(name)?)
+        .validate_with(|port: &u16| {
+            if *port == 0 {
+                Err("端口不能为0")
+            } else {
+                Ok(())
+            }
+        })
+        .interact_text()?;
+    let level_seed = Input::<String>::with_theme(&theme)
+        .with_prompt("请输入世界种子(留空随机生成)")
+        .allow_empty(true)
+        .interact_text()?;
+    let level_type = LEVEL_TYPES[select_option(
+        "请选择世界类型",
+        &["默认", "超平坦", "巨型生物群系", "放大化"],
+    )?];
+    let gamemodes = ["survival", "creative", "adventure", "spectator"];
+    let gamemode =
+        gamemodes[select_option("请选择默认游戏模式", &["生存", "创造", "冒险", "旁观"])?];
+    let difficulties = ["peaceful", "easy", "normal", "hard"];
+    let difficulty = difficulties[select_option("请选择难度", &["和平", "简单", "普通", "困难"])?];
+    let online_mode = Confirm::new()
+        .with_prompt("是否开启正版验证(online-mode)?")
+        .default(true)
+        .interact()?;
+    let max_players = Input::<u32>::with_theme(&theme)
+        .with_prompt("请输入最大玩家数量")
+        .default(20)
+        .interact_text()?;
+    Ok(WorldSettings {
+        port: Some(port),
+        level_seed: (!level_seed.trim().is_empty()).then_some(level_seed),
+        level_type: Some(level_type.to_string()),
+        gamemode: Some(gamemode.to_string()),
+        difficulty: Some(difficulty.to_string()),
+        online_mode: Some(online_mode),
+        max_players: Some(max_players),
+    })
+}
+
+/// 按照配置创建服务器: 从`provider`下载并校验核心, 复制到`MCSCS/servers/<name>/server.jar`, 然后保存配置
+pub async fn create_server(config: &ServerConfig, provider: &dyn CoreProvider) -> Result<()> {
+    config.validate()?;
+    let current_dir = Workspace::current()?.server_dir(&config.name);
+    if current_dir.join("config.json").exists() {
+        return Err(ConfigError::new("name", format!("服务器{}已存在", config.name)).into());
+    }
+
+    // 下载核心
+    let info = &config.info;
+    let file_path = provider
+        .download(&info.core, &info.mc_version, &info.build_version)
+        .await?;
+    fs::create_dir_all(&current_dir)?;
+    fs::copy(file_path, current_dir.join("server.jar"))?;
+
+    save_servers_lists(&config.name, config)
+}
+
+/// 创建服务器页面
+pub async fn main() -> Result<()> {
+    // 服务器的名称
+    let name = name()?;
 
     // JVM参数
-    configs["jvm_args"] = jvm_args(None);
+    let jvm_args = jvm_args(None)?;
 
     // 设置编码
-    configs["encoding"] = json!(encoding());
+    let encoding = encoding()?;
 
     // 设置Xmx和Xms
     println!("1GiB = 1024MB, 1GB = 1000MB");
     println!("1MiB = 1024KB, 1MB = 1000KB");
     println!("1KiB = 1024Bytes, 1KB = 1000Bytes");
-    let xms = xms(None);
-    let xmx = xmx(xms);
-    configs["Xms"] = json!(xms);
-    configs["Xmx"] = json!(xmx);
+    let xms = xms(None)?;
+    let xmx = xmx(xms)?;
 
-    // 下载核心
-    let core = core().await;
-    let mc_version = mc_version(&core).await;
-    let build_version = build_version(&core, &mc_version).await;
-
-    let file_path = download_server_core(&core, &mc_version, &build_version).await?;
-    let current_dir = env::current_dir()?
-        .join("MCSCS")
-        .join("servers")
-        .join(&name);
-    fs::create_dir_all(&current_dir)?;
-    fs::copy(file_path, current_dir.join("server.jar"))?;
+    // 选择核心
+    let (kind, core) = core().await?;
+    let provider: Box<dyn CoreProvider> = match kind {
+        ProviderKind::Mojang => Box::new(Mojang {
+            snapshots: Confirm::new()
+                .with_prompt("是否列出快照版本?")
+                .default(false)
+                .interact()?,
+        }),
+        kind => kind.provider(),
+    };
+    let mc_version = mc_version(provider.as_ref(), &core).await?;
+    let build_version = build_version(provider.as_ref(), &core, &mc_version).await?;
 
-    configs["info"] = json!(
-        {
-            "core": core,
-            "mc_version": mc_version,
-            "build_version": build_version
-        }
-    );
+    // 设置使用的Java, 来源记录了需要的Java版本时优先使用满足要求的Java
+    let java = java(provider.java_version(&core, &mc_version).await?)?;
 
     // 服务器参数
-    configs["server_args"] = server_args(None);
+    let server_args = server_args(None)?;
+
+    // 端口和世界设置
+    let settings = world_settings(&name)?;
+
+    let configs = ServerConfig {
+        schema_version: SCHEMA_VERSION,
+        name,
+        java,
+        jvm_args,
+        encoding,
+        xms,
+        xmx,
+        info: CoreInfo {
+            core,
+            mc_version,
+            build_version,
+            provider: kind,
+        },
+        server_args,
+    };
 
-    save_servers_lists(&name, &configs);
+    create_server(&configs, provider.as_ref()).await?;
+    write_world_settings(&configs.name, &settings)?;
+
+    // RCON
+    if Confirm::new()
+        .with_prompt("是否启用RCON(可以使用`mcscs rcon`远程执行命令)?")
+        .default(true)
+        .interact()?
+    {
+        let (port, _) = enable_rcon(&configs.name)?;
+        println!("已启用RCON, 端口: {port}, 密码保存在server.properties");
+    }
     Ok(())
 }
@@ -6,31 +6,42 @@
  * Vestibulum commodo. Ut rhoncus gravida arcu.
  */
 
-use std::error::Error;
-use std::{env, fs};
+use std::fs;
 
 use dialoguer::Confirm;
 
+use crate::control::is_running;
+use crate::error::{Error, Result};
 use crate::select::select_server;
+use crate::workspace::Workspace;
 
-/// 删除服务器页面
-pub fn main() -> Result<(), Box<dyn Error>> {
-    let mut server = select_server();
-    if server.is_null() {
-        println!("你还没有创建任何一个服务器!");
-        return Ok(());
+/// 删除服务器, 包括服务器文件夹内的所有文件, 服务器正在运行时返回错误
+pub fn delete_server(name: &str) -> Result<()> {
+    let path = Workspace::current()?.server_dir(name);
+    if !path.join("config.json").exists() {
+        return Err(Error::ServerNotFound(name.to_string()));
+    }
+    if is_running(name)? {
+        return Err(Error::Other(format!("服务器{name}正在运行, 请先关闭")));
     }
+    fs::remove_dir_all(path)?;
+    Ok(())
+}
+
+/// 删除服务器页面
+pub fn main() -> Result<()> {
+    let server = match select_server()? {
+        Some(server) => server,
+        None => {
+            println!("你还没有创建任何一个服务器!");
+            return Ok(());
+        }
+    };
     if Confirm::new()
         .with_prompt("你是否真的要删除此服务器?")
-        .interact()
-        .unwrap()
+        .interact()?
     {
-        fs::remove_dir_all(
-            env::current_dir()?
-                .join("MCSCS")
-                .join("servers")
-                .join(server["name"].take().as_str().unwrap()),
-        )?;
+        delete_server(&server.name)?;
     }
 
     Ok(())
@@ -0,0 +1,232 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+use dialoguer::{theme::ColorfulTheme, Input, MultiSelect};
+use log::info;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::archive::{add_dir_all, archive_name, ArchiveFormat, ArchiveWriter};
+use crate::control::is_running;
+use crate::error::{Error, Result};
+use crate::java::Java;
+use crate::select::{select_option, select_server};
+use crate::server::{is_local_file, load_servers_lists, ServerConfig};
+use crate::session::SESSIONS_DIR;
+use crate::workspace::Workspace;
+
+/// 压缩包里的清单文件, 导入时用它代替`config.json`
+pub const MANIFEST_FILE: &str = "mcscs.json";
+
+/// 清单格式的版本
+pub const MANIFEST_VERSION: u64 = 1;
+
+/// 日志
+const LOGS: [&str; 2] = ["logs", SESSIONS_DIR];
+/// 崩溃报告
+const CRASH_REPORTS: [&str; 1] = ["crash-reports"];
+/// 启动时会重新下载或者生成的缓存
+const CACHES: [&str; 5] = [
+    "cache",
+    "libraries",
+    "versions",
+    ".paper-remapped",
+    ".fabric",
+];
+
+/// 导出的服务器清单, 对应压缩包里的`mcscs.json`
+///
+/// `config`是去掉`java`的`config.json`, 导入时按照`java_major`重新选择本机的Java
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportManifest {
+    pub manifest_version: u64,
+    /// 需要的Java主版本号
+    pub java_major: u32,
+    /// 导出时间(Unix时间戳)
+    pub exported_at: i64,
+    pub config: Value,
+}
+
+impl ExportManifest {
+    /// 从服务器配置生成清单
+    pub fn new(server: &ServerConfig) -> Result<Self> {
+        let java_major = server
+            .java
+            .major_version()
+            .ok_or_else(|| Error::Java(format!("无法识别Java版本{}", server.java.version)))?;
+        let mut config = serde_json::to_value(server)?;
+        if let Some(config) = config.as_object_mut() {
+            config.remove("java");
+        }
+        Ok(Self {
+            manifest_version: MANIFEST_VERSION,
+            java_major,
+            exported_at: Local::now().timestamp(),
+            config,
+        })
+    }
+
+    /// 读取文件夹里的清单, 没有清单时返回`None`
+    pub fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let manifest: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if manifest.manifest_version > MANIFEST_VERSION {
+            return Err(Error::Other(format!(
+                "清单版本{}比当前支持的版本{MANIFEST_VERSION}新, 请更新MCSCS",
+                manifest.manifest_version
+            )));
+        }
+        Ok(Some(manifest))
+    }
+
+    /// 使用新的名称和本机的Java生成服务器配置, 旧版本的配置会先升级
+    pub fn to_config(&self, name: &str, java: &Java) -> Result<ServerConfig> {
+        let mut config = self.config.clone();
+        if let Some(object) = config.as_object_mut() {
+            object.insert("name".to_string(), Value::from(name));
+            object.insert("java".to_string(), serde_json::to_value(java)?);
+        }
+        Ok(ServerConfig::from_json(&config.to_string())?)
+    }
+}
+
+/// 导出时的选项
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportOptions {
+    pub format: ArchiveFormat,
+    /// 不导出`logs`和控制台日志
+    pub exclude_logs: bool,
+    /// 不导出`crash-reports`
+    pub exclude_crash_reports: bool,
+    /// 不导出`cache`, `libraries`等启动时会重新下载的文件
+    pub exclude_caches: bool,
+    /// 其他不导出的文件或文件夹, 相对于服务器文件夹, 例如: `plugins/dynmap/web`
+    pub exclude: Vec<String>,
+}
+
+impl ExportOptions {
+    /// 文件是否不导出, `name`是使用`/`分隔的相对路径
+    ///
+    /// # 示例
+    /// ```
+    /// use mcscs::pages::export::ExportOptions;
+    /// let options = ExportOptions { exclude_logs: true, ..Default::default() };
+    /// assert!(options.is_excluded("logs"));
+    /// assert!(options.is_excluded("config.json"));
+    /// assert!(!options.is_excluded("world/level.dat"));
+    /// ```
+    pub fn is_excluded(&self, name: &str) -> bool {
+        let mut excluded = vec![MANIFEST_FILE];
+        if self.exclude_logs {
+            excluded.extend(LOGS);
+        }
+        if self.exclude_crash_reports {
+            excluded.extend(CRASH_REPORTS);
+        }
+        if self.exclude_caches {
+            excluded.extend(CACHES);
+        }
+        excluded.extend(self.exclude.iter().map(String::as_str));
+        is_local_file(name)
+            || excluded
+                .iter()
+                .map(|path| path.trim_matches('/'))
+                .any(|path| name == path || name.starts_with(&format!("{path}/")))
+    }
+}
+
+/// 把服务器导出为压缩包, 服务器正在运行时返回错误
+///
+/// 压缩包里是服务器文件夹的内容和[`MANIFEST_FILE`], 返回导出的文件数量
+///
+/// # 示例
+/// ```no_run
+/// use std::path::Path;
+/// use mcscs::pages::export::{export_server, ExportOptions};
+/// export_server("survival", Path::new("survival.tar.zst"), &ExportOptions::default()).unwrap();
+/// ```
+pub fn export_server(name: &str, path: &Path, options: &ExportOptions) -> Result<u64> {
+    let server = load_servers_lists(Some(name))?
+        .remove(name)
+        .ok_or_else(|| Error::ServerNotFound(name.to_string()))?;
+    if is_running(name)? {
+        return Err(Error::Other(format!(
+            "服务器{name}正在运行, 请先关闭服务器再导出"
+        )));
+    }
+    let manifest = ExportManifest::new(&server)?;
+    let server_dir = Workspace::current()?.server_dir(name);
+
+    let mut writer = ArchiveWriter::create(path, options.format)?;
+    writer.add_data(MANIFEST_FILE, &serde_json::to_vec_pretty(&manifest)?)?;
+    let result = add_dir_all(&mut writer, &server_dir, "", &|name| {
+        options.is_excluded(name)
+    })
+    .and_then(|count| writer.finish().map(|()| count));
+    match result {
+        Ok(count) => {
+            info!(
+                "{} -> {} ({count}个文件)",
+                server_dir.display(),
+                path.display()
+            );
+            Ok(count)
+        }
+        Err(err) => {
+            // 不留下不完整的压缩包
+            let _ = fs::remove_file(path);
+            Err(err)
+        }
+    }
+}
+
+/// 导出服务器页面
+pub fn main() -> Result<()> {
+    let server = match select_server()? {
+        Some(server) => server,
+        None => {
+            println!("你还没有创建任何一个服务器!");
+            return Ok(());
+        }
+    };
+    let formats = [ArchiveFormat::TarZst, ArchiveFormat::Zip];
+    let format = formats[select_option(
+        "请选择压缩包格式",
+        &["tar.zst(压缩率更高)", "zip(Windows可以直接打开)"],
+    )?];
+    let excludes = MultiSelect::with_theme(&ColorfulTheme::default())
+        .with_prompt("请选择不导出的文件(空格选择, Enter确认)")
+        .items(&["日志", "崩溃报告", "缓存和依赖库"])
+        .defaults(&[true, true, true])
+        .interact()?;
+    let path = PathBuf::from(
+        Input::<String>::with_theme(&ColorfulTheme::default())
+            .with_prompt("请输入压缩包的路径")
+            .default(archive_name(&server.name, format).display().to_string())
+            .interact_text()?,
+    );
+    let options = ExportOptions {
+        format,
+        exclude_logs: excludes.contains(&0),
+        exclude_crash_reports: excludes.contains(&1),
+        exclude_caches: excludes.contains(&2),
+        exclude: Vec::new(),
+    };
+    let count = export_server(&server.name, &path, &options)?;
+    println!("已导出{count}个文件到{}", path.display());
+    Ok(())
+}
@@ -6,7 +6,487 @@
  * Vestibulum commodo. Ut rhoncus gravida arcu.
  */
 
-/// 导入服务器
-pub fn main() {
-    todo!("编写导入服务器...");
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use dialoguer::Confirm;
+use log::{info, warn};
+use regex::Regex;
+use serde_json::Value;
+use zip::ZipArchive;
+
+use crate::archive::extract;
+use crate::error::{Error, Result};
+use crate::java::{find_java, load_java_lists, required_java, Java};
+use crate::migrate::SCHEMA_VERSION;
+use crate::pages::create::{self, xms, xmx};
+use crate::pages::export::{ExportManifest, MANIFEST_FILE};
+use crate::provider::ProviderKind;
+use crate::select::select_option;
+use crate::server::{save_servers_lists, validate_name, ConfigError, CoreInfo, ServerConfig};
+use crate::utils::{copy_dir, input, move_dir};
+use crate::workspace::Workspace;
+
+/// 服务器核心的文件名, [`crate::pages::start`]使用这个文件启动服务器
+pub const SERVER_JAR: &str = "server.jar";
+
+/// 文件名里的核心名称, 这些核心的启动类相同, 只能通过文件名区分
+const CORE_NAMES: [&str; 16] = [
+    "Paper",
+    "Purpur",
+    "Folia",
+    "Pufferfish",
+    "Leaves",
+    "Spigot",
+    "CraftBukkit",
+    "Mohist",
+    "CatServer",
+    "Arclight",
+    "Velocity",
+    "BungeeCord",
+    "Waterfall",
+    "Fabric",
+    "Forge",
+    "Vanilla",
+];
+
+/// 启动类的前缀和对应的核心
+const MAIN_CLASSES: [(&str, &str); 11] = [
+    ("io.papermc.paperclip", "Paper"),
+    ("com.destroystokyo.paperclip", "Paper"),
+    ("org.bukkit.craftbukkit", "CraftBukkit"),
+    ("org.spigotmc", "Spigot"),
+    ("com.mohistmc", "Mohist"),
+    ("catserver", "CatServer"),
+    ("io.izzel.arclight", "Arclight"),
+    ("com.velocitypowered", "Velocity"),
+    ("net.md_5.bungee", "BungeeCord"),
+    ("net.fabricmc", "Fabric"),
+    ("net.minecraft", "Vanilla"),
+];
+
+/// 从服务器核心里检测到的信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedCore {
+    pub info: CoreInfo,
+    /// 核心里记录的Java主版本号, 1.17以前的核心没有记录
+    pub java_major: Option<u32>,
+}
+
+impl DetectedCore {
+    /// 需要的Java主版本号, 核心里没有记录时按Minecraft版本推断
+    pub fn required_java(&self) -> u32 {
+        self.java_major
+            .unwrap_or_else(|| required_java(&self.info.mc_version))
+    }
+}
+
+/// 解析`META-INF/MANIFEST.MF`, 以空格开头的行是上一行的继续
+fn parse_manifest(data: &str) -> BTreeMap<String, String> {
+    let mut manifest = BTreeMap::<String, String>::new();
+    let mut last: Option<String> = None;
+    for line in data.lines() {
+        if let Some(rest) = line.strip_prefix(' ') {
+            if let Some(value) = last.as_ref().and_then(|key| manifest.get_mut(key)) {
+                value.push_str(rest);
+            }
+            continue;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            manifest.insert(key.trim().to_string(), value.trim().to_string());
+            last = Some(key.trim().to_string());
+        }
+    }
+    manifest
+}
+
+fn read_entry(archive: &mut ZipArchive<File>, name: &str) -> Option<String> {
+    let mut entry = archive.by_name(name).ok()?;
+    let mut data = String::new();
+    entry.read_to_string(&mut data).ok()?;
+    Some(data)
+}
+
+/// 从服务器核心的`META-INF/MANIFEST.MF`, `version.json`和文件名检测核心名称, Minecraft版本和构建版本
+///
+/// 检测不到的版本为`unknown`
+pub fn detect_core(jar: &Path) -> Result<DetectedCore> {
+    let mut archive = ZipArchive::new(File::open(jar)?)?;
+    let manifest = read_entry(&mut archive, "META-INF/MANIFEST.MF")
+        .map(|data| parse_manifest(&data))
+        .unwrap_or_default();
+    let version_json = read_entry(&mut archive, "version.json")
+        .and_then(|data| serde_json::from_str::<Value>(&data).ok());
+    // Paperclip里记录了原版核心的文件名, 例如: mojang_1.20.1.jar
+    let paperclip = ["META-INF/download-context", "META-INF/versions.list"]
+        .iter()
+        .filter_map(|name| read_entry(&mut archive, name))
+        .collect::<String>();
+    let file_name = jar
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let get = |key: &str| manifest.get(key).map(String::as_str).unwrap_or_default();
+
+    let lower_name = file_name.to_lowercase();
+    let main_class = get("Main-Class");
+    let core = CORE_NAMES
+        .iter()
+        .find(|core| lower_name.contains(&core.to_lowercase()))
+        .map(|core| core.to_string())
+        .or_else(|| {
+            MAIN_CLASSES
+                .iter()
+                .find(|(prefix, _)| main_class.starts_with(prefix))
+                .map(|(_, core)| core.to_string())
+        })
+        .or_else(|| {
+            let title = get("Implementation-Title");
+            (!title.is_empty()).then(|| title.to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let version_re =
+        Regex::new(r"1\.\d+(?:\.\d+)?").map_err(|err| Error::Other(err.to_string()))?;
+    let mc_version = version_json
+        .as_ref()
+        .and_then(|json| json["name"].as_str().or(json["id"].as_str()))
+        .filter(|version| !version.is_empty())
+        .map(String::from)
+        .or_else(|| {
+            // Spigot系列: git-Paper-196 (MC: 1.20.1)
+            [get("Implementation-Version"), &paperclip, &file_name]
+                .iter()
+                .find_map(|text| version_re.find(text))
+                .map(|version| version.as_str().to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let build_re = Regex::new(r"^git-\w+-(\d+)(?:\s|$)|-(\d+)\.jar$")
+        .map_err(|err| Error::Other(err.to_string()))?;
+    let build_version = [get("Implementation-Version"), file_name.as_str()]
+        .iter()
+        .find_map(|text| {
+            let captures = build_re.captures(text)?;
+            captures.get(1).or(captures.get(2))
+        })
+        .map(|build| format!("build{}", build.as_str()))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let java_major = version_json
+        .as_ref()
+        .and_then(|json| json["java_version"].as_u64())
+        .map(|major| major as u32);
+
+    let detected = DetectedCore {
+        info: CoreInfo {
+            core,
+            mc_version,
+            build_version,
+            provider: ProviderKind::default(),
+        },
+        java_major,
+    };
+    info!("{} -> {detected:?}", jar.display());
+    Ok(detected)
+}
+
+/// 导入时的选项
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportOptions {
+    /// 服务器核心的文件名, 为`None`时使用`server.jar`或者唯一的jar文件
+    pub jar: Option<String>,
+    /// 使用的Java, 为`None`时从`java.json`里选择满足版本要求的Java
+    pub java: Option<Java>,
+    /// 移动文件夹而不是复制, 导入压缩包时总是移动解压出来的文件
+    pub move_files: bool,
+    /// JVM初始堆内存, 为`None`时使用清单里的设置, 没有清单时使用1GiB
+    pub xms: Option<u64>,
+    /// JVM最大堆内存, 为`None`时使用清单里的设置, 没有清单时使用4GiB
+    pub xmx: Option<u64>,
+}
+
+/// 没有清单时默认的JVM初始堆内存
+const DEFAULT_XMS: u64 = 1024 * 1024 * 1024;
+
+/// 没有清单时默认的JVM最大堆内存
+const DEFAULT_XMX: u64 = 4 * 1024 * 1024 * 1024;
+
+/// 要导入的服务器文件
+///
+/// 导入压缩包时会先解压到`MCSCS/temp/import-<name>`, 没有导入完成时会被删除
+#[derive(Debug)]
+pub struct ImportSource {
+    root: PathBuf,
+    temp: Option<PathBuf>,
+}
+
+impl ImportSource {
+    /// 打开服务器文件夹, zip或者tar.zst压缩包
+    pub fn open(source: &Path, name: &str) -> Result<Self> {
+        if source.is_dir() {
+            return Ok(Self {
+                root: source.to_path_buf(),
+                temp: None,
+            });
+        }
+        let temp = Workspace::current()?
+            .temp_dir()
+            .join(format!("import-{name}"));
+        if temp.exists() {
+            fs::remove_dir_all(&temp)?;
+        }
+        let mut import = Self {
+            root: temp.clone(),
+            temp: Some(temp.clone()),
+        };
+        extract(source, &temp)?;
+        import.root = server_root(&temp)?;
+        Ok(import)
+    }
+
+    /// 服务器文件所在的文件夹
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// 服务器文件夹里的jar文件, `server.jar`排在最前面
+    pub fn jars(&self) -> Result<Vec<String>> {
+        let mut jars = list_jars(&self.root)?;
+        jars.sort_by_key(|jar| (jar != SERVER_JAR, jar.clone()));
+        Ok(jars)
+    }
+
+    /// 没有指定时选择`server.jar`或者唯一的jar文件
+    fn default_jar(&self) -> Result<String> {
+        let jars = self.jars()?;
+        match jars.as_slice() {
+            [] => Err(Error::Other(format!(
+                "{}里没有服务器核心(jar文件)",
+                self.root.display()
+            ))),
+            [jar] => Ok(jar.clone()),
+            [first, ..] if first == SERVER_JAR => Ok(first.clone()),
+            _ => Err(Error::Other(format!(
+                "找到多个jar文件, 请指定服务器核心: {}",
+                jars.join(", ")
+            ))),
+        }
+    }
+
+    /// [`crate::pages::export`]导出的压缩包里的清单
+    pub fn manifest(&self) -> Result<Option<ExportManifest>> {
+        ExportManifest::load(&self.root)
+    }
+
+    /// 把文件复制或者移动到`MCSCS/servers/<name>`, 核心重命名为`server.jar`, 然后保存配置
+    ///
+    /// 有清单时使用清单里的配置, 只按照清单里的Java主版本号重新选择本机的Java
+    pub fn import(mut self, name: &str, options: &ImportOptions) -> Result<ServerConfig> {
+        validate_name(name)?;
+        let dest = Workspace::current()?.server_dir(name);
+        if dest.exists() {
+            return Err(ConfigError::new("name", format!("服务器{name}已存在")).into());
+        }
+        let manifest = self.manifest()?;
+        let jar = match (&options.jar, &manifest) {
+            (Some(jar), _) => jar.clone(),
+            (None, Some(_)) => SERVER_JAR.to_string(),
+            (None, None) => self.default_jar()?,
+        };
+        if !self.root.join(&jar).is_file() {
+            return Err(Error::Other(format!("{}里没有{jar}", self.root.display())));
+        }
+        if jar != SERVER_JAR && self.root.join(SERVER_JAR).exists() {
+            return Err(Error::Other(format!(
+                "已经有{SERVER_JAR}, 无法把{jar}重命名为{SERVER_JAR}"
+            )));
+        }
+        let config = match &manifest {
+            Some(manifest) => {
+                let java = match &options.java {
+                    Some(java) => java.clone(),
+                    None => matching_java(manifest.java_major)?,
+                };
+                let mut config = manifest.to_config(name, &java)?;
+                config.xms = options.xms.unwrap_or(config.xms);
+                config.xmx = options.xmx.unwrap_or(config.xmx);
+                config
+            }
+            None => {
+                let detected = detect_core(&self.root.join(&jar))?;
+                let java = match &options.java {
+                    Some(java) => java.clone(),
+                    None => matching_java(detected.required_java())?,
+                };
+                ServerConfig {
+                    schema_version: SCHEMA_VERSION,
+                    name: name.to_string(),
+                    java,
+                    jvm_args: vec!["-Dlog4j2.formatMsgNoLookups=true".to_string()],
+                    encoding: "utf-8".to_string(),
+                    xms: options.xms.unwrap_or(DEFAULT_XMS),
+                    xmx: options.xmx.unwrap_or(DEFAULT_XMX),
+                    info: detected.info,
+                    server_args: vec!["--nogui".to_string()],
+                }
+            }
+        };
+        config.validate()?;
+
+        if self.temp.is_some() || options.move_files {
+            move_dir(&self.root, &dest)?;
+        } else {
+            copy_dir(&self.root, &dest)?;
+        }
+        if let Some(temp) = self.temp.take() {
+            if temp.exists() {
+                fs::remove_dir_all(temp)?;
+            }
+        }
+        if jar != SERVER_JAR {
+            fs::rename(dest.join(&jar), dest.join(SERVER_JAR))?;
+        }
+        if manifest.is_some() {
+            fs::remove_file(dest.join(MANIFEST_FILE))?;
+        }
+        save_servers_lists(name, &config)?;
+        info!("{} -> {}", self.root.display(), dest.display());
+        Ok(config)
+    }
+}
+
+impl Drop for ImportSource {
+    fn drop(&mut self) {
+        if let Some(temp) = &self.temp {
+            if let Err(err) = fs::remove_dir_all(temp) {
+                warn!("{}: {err}", temp.display());
+            }
+        }
+    }
+}
+
+/// 从`java.json`里选择满足版本要求的Java
+fn matching_java(required: u32) -> Result<Java> {
+    find_java(&load_java_lists().unwrap_or_default(), required).ok_or_else(|| {
+        Error::Java(format!(
+            "没有找到Java {required}或更高的版本, 请指定使用的Java"
+        ))
+    })
+}
+
+fn list_jars(dir: &Path) -> Result<Vec<String>> {
+    let mut jars = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if entry.file_type()?.is_file() && name.to_lowercase().ends_with(".jar") {
+            jars.push(name);
+        }
+    }
+    Ok(jars)
+}
+
+/// 压缩包里只有一个文件夹并且外面没有jar文件时, 服务器文件在这个文件夹里
+fn server_root(dir: &Path) -> Result<PathBuf> {
+    let entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    match entries.as_slice() {
+        [entry] if entry.file_type()?.is_dir() => Ok(entry.path()),
+        _ => Ok(dir.to_path_buf()),
+    }
+}
+
+/// 按照选项导入服务器文件夹或者压缩包
+///
+/// # 示例
+/// ```no_run
+/// use std::path::Path;
+/// use mcscs::pages::import::{import_server, ImportOptions};
+/// let server = import_server(Path::new("/srv/minecraft"), "survival", &ImportOptions::default()).unwrap();
+/// println!("{:?}", server.info);
+/// ```
+pub fn import_server(source: &Path, name: &str, options: &ImportOptions) -> Result<ServerConfig> {
+    ImportSource::open(source, name)?.import(name, options)
+}
+
+/// 导入服务器页面
+pub fn main() -> Result<()> {
+    let source = loop {
+        let path = PathBuf::from(input("请输入服务器文件夹或者压缩包(zip, tar.zst)的路径")?);
+        if path.exists() {
+            break path;
+        }
+        println!("输入错误,路径不存在,请重新输入!");
+    };
+    let name = create::name()?;
+    let move_files = source.is_dir()
+        && Confirm::new()
+            .with_prompt("是否移动文件夹(否则复制)?")
+            .default(false)
+            .interact()?;
+    let import = ImportSource::open(&source, &name)?;
+
+    // 导出的服务器只需要重新选择Java
+    let manifest = import.manifest()?;
+    let (jar, required) = match &manifest {
+        Some(manifest) => {
+            let info = &manifest.config["info"];
+            println!(
+                "导出的服务器: {} {} {}",
+                info["core"].as_str().unwrap_or_default(),
+                info["mc_version"].as_str().unwrap_or_default(),
+                info["build_version"].as_str().unwrap_or_default()
+            );
+            (SERVER_JAR.to_string(), manifest.java_major)
+        }
+        None => {
+            let jars = import.jars()?;
+            if jars.is_empty() {
+                return Err(Error::Other(format!(
+                    "{}里没有服务器核心(jar文件)",
+                    import.root().display()
+                )));
+            }
+            let jar = if jars.len() == 1 || jars[0] == SERVER_JAR {
+                jars[0].clone()
+            } else {
+                jars[select_option("请选择服务器核心", &jars)?].clone()
+            };
+            let detected = detect_core(&import.root().join(&jar))?;
+            println!(
+                "检测到的核心: {} {} {}",
+                detected.info.core, detected.info.mc_version, detected.info.build_version
+            );
+            (jar, detected.required_java())
+        }
+    };
+
+    let java = create::java(Some(required))?;
+
+    // 导出的服务器使用清单里的内存设置
+    let (xms, xmx) = match manifest {
+        Some(_) => (None, None),
+        None => {
+            println!("1GiB = 1024MB, 1GB = 1000MB");
+            println!("1MiB = 1024KB, 1MB = 1000KB");
+            println!("1KiB = 1024Bytes, 1KB = 1000Bytes");
+            let xms = xms(None)?;
+            (Some(xms), Some(xmx(xms)?))
+        }
+    };
+
+    let options = ImportOptions {
+        jar: Some(jar),
+        java: Some(java),
+        move_files,
+        xms,
+        xmx,
+    };
+    import.import(&name, &options)?;
+    println!("服务器{name}导入成功");
+    Ok(())
 }
@@ -6,8 +6,12 @@
  * Vestibulum commodo. Ut rhoncus gravida arcu.
  */
 
-use std::path::PathBuf;
-use std::{env, error::Error, fs, path::Path, process::Command, time::Duration};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    time::Duration,
+};
 
 use chrono::Local;
 use lazy_static::lazy_static;
@@ -24,45 +28,53 @@ use tokio::sync::Mutex;
 use tokio::time::sleep;
 
 use crate::aria2c::{call_aria2c_rpc, get_aria2c_execute};
+use crate::error::{Error, Result};
 use crate::java::load_java_lists;
+use crate::migrate::import_legacy_servers;
+use crate::workspace::Workspace;
 use crate::{
     aria2c::install_aria2c,
     java::{detect_java, save_java_lists},
 };
 
 lazy_static! {
-    static ref INITIALIZED: Mutex<bool> = Mutex::new(false);
+    /// 初始化完成后保存本次运行的日志文件夹
+    static ref LOG_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+    static ref ARIA2C_INITIALIZED: Mutex<bool> = Mutex::new(false);
 }
 
 /// 初始化页面
-pub async fn main() -> Result<(), Box<dyn Error>> {
-    let mut initialized = INITIALIZED.lock().await;
+pub async fn main() -> Result<()> {
+    let log_path = init_workspace().await?;
+    let mut initialized = ARIA2C_INITIALIZED.lock().await;
     if !*initialized {
-        let res = {
-            let current_dir = env::current_dir()?.join("MCSCS");
-            let log_path = current_dir
-                .join("logs")
-                .join(Local::now().format("%Y%m%d%H%M").to_string());
-            fs::create_dir_all(&log_path)?;
-            init_log(&log_path)?;
-            init_aria2(&current_dir, &log_path).await?;
-            init_servers(&current_dir)?;
-            Ok(())
-        };
-        match res {
-            Ok(_) => {
-                *initialized = true;
-                Ok(())
-            }
-            Err(err) => Err(err),
-        }
-    } else {
-        Ok(())
+        init_aria2(&Workspace::current()?, &log_path).await?;
+        *initialized = true;
     }
+    Ok(())
+}
+
+/// 初始化日志和服务器相关的文件夹, 不需要下载文件时只调用这个就可以, 不会启动aria2c
+///
+/// 返回本次运行的日志文件夹
+pub async fn init_workspace() -> Result<PathBuf> {
+    let mut initialized = LOG_PATH.lock().await;
+    if let Some(log_path) = initialized.as_ref() {
+        return Ok(log_path.clone());
+    }
+    let workspace = Workspace::current()?;
+    let log_path = workspace
+        .logs_dir()
+        .join(Local::now().format("%Y%m%d%H%M").to_string());
+    fs::create_dir_all(&log_path)?;
+    init_log(&log_path)?;
+    init_servers(&workspace)?;
+    *initialized = Some(log_path.clone());
+    Ok(log_path)
 }
 
 /// 初始化日志
-fn init_log(log_path: &Path) -> Result<(), Box<dyn Error>> {
+fn init_log(log_path: &Path) -> Result<()> {
     // 文件输出
     let file = FileAppender::builder()
         .encoder(Box::new(PatternEncoder::new(
@@ -78,16 +90,17 @@ fn init_log(log_path: &Path) -> Result<(), Box<dyn Error>> {
                 .additive(false)
                 .build("app", LevelFilter::Trace),
         )
-        .build(Root::builder().appender("file").build(LevelFilter::Trace))?;
+        .build(Root::builder().appender("file").build(LevelFilter::Trace))
+        .map_err(|err| Error::Other(format!("初始化日志失败: {err}")))?;
 
-    log4rs::init_config(config)?;
+    log4rs::init_config(config).map_err(|err| Error::Other(format!("初始化日志失败: {err}")))?;
     Ok(())
 }
 
 // 初始化aria2c
-async fn init_aria2(current_dir: &Path, log_path: &Path) -> Result<(), Box<dyn Error>> {
-    let aria2c_current_dir = current_dir.join("aria2c");
-    install_aria2c().await;
+async fn init_aria2(workspace: &Workspace, log_path: &Path) -> Result<()> {
+    let aria2c_current_dir = workspace.aria2c_dir();
+    install_aria2c().await?;
     match call_aria2c_rpc("aria2.getVersion", json!([])) {
         Ok(version) => {
             info!(
@@ -97,38 +110,32 @@ async fn init_aria2(current_dir: &Path, log_path: &Path) -> Result<(), Box<dyn E
         }
         Err(_) => {
             warn!("检测到aria2c似乎未开启,正在开启aria2c中...");
-            fs::create_dir_all(current_dir.join("downloads"))?;
+            fs::create_dir_all(workspace.downloads_dir())?;
             fs::create_dir_all(&aria2c_current_dir)?;
             if !aria2c_current_dir.join("aria2c.conf").exists() {
                 let data = reqwest::get(
                     "https://github.com/Arama-Vanarana/MCSCS-Rust/releases/latest/aria2c.conf",
                 )
-                .await
-                .unwrap()
+                .await?
+                .error_for_status()?
                 .text()
-                .await
-                .unwrap();
+                .await?;
                 fs::write(aria2c_current_dir.join("aria2c.conf"), data)?;
             }
             let mut aria2c = Command::new(get_aria2c_execute()?);
-            aria2c.arg(format!("--dir={}", current_dir.join("downloads").display()));
+            aria2c.arg(format!("--dir={}", workspace.downloads_dir().display()));
             aria2c.arg(format!("--log={}", log_path.join("aria2c.log").display()));
             aria2c.arg(format!(
                 "--conf-path={}",
-                current_dir.join("aria2c").join("aria2c.conf").display()
+                aria2c_current_dir.join("aria2c.conf").display()
             ));
             aria2c.arg("--enable-rpc=true");
             aria2c.arg("--rpc-listen-port=6800");
             aria2c.arg("--rpc-secret=MCSCS");
             aria2c.arg("--quiet=true");
-            trace!("shell <- {}", format!("{:?}", aria2c));
-            if aria2c.spawn().is_err() {
-                panic!(
-                    "aria2c未安装, 请安装后再次运行本程序:
-Ubuntu/Debian: 
-sudo apt update
-sudo apt install aria2"
-                );
+            trace!("shell <- {aria2c:?}");
+            if let Err(err) = aria2c.spawn() {
+                return Err(Error::Aria2(format!("启动aria2c失败: {err}")));
             }
             sleep(Duration::from_millis(100)).await;
         }
@@ -137,9 +144,9 @@ sudo apt install aria2"
 }
 
 /// 初始化服务器页面相关文件夹和文件
-fn init_servers(current_dir: &Path) -> Result<(), Box<dyn Error>> {
-    let configs_current_dir = current_dir.join("configs");
-    fs::create_dir_all(current_dir.join("servers"))?;
+fn init_servers(workspace: &Workspace) -> Result<()> {
+    let configs_current_dir = workspace.configs_dir();
+    fs::create_dir_all(workspace.servers_dir())?;
     fs::create_dir_all(&configs_current_dir)?;
     match fs::metadata(configs_current_dir.join("java.json")) {
         Ok(_) => {
@@ -147,25 +154,21 @@ fn init_servers(current_dir: &Path) -> Result<(), Box<dyn Error>> {
                 "find -> {}",
                 configs_current_dir.join("java.json").display()
             );
-            let mut javas = load_java_lists();
-            for java in javas.as_array_mut().unwrap() {
-                if !PathBuf::from(java["path"].as_str().unwrap()).exists() {
-                    save_java_lists(&detect_java());
-                    break;
-                }
+            // java.json损坏或者有Java已经被删除时重新检测
+            let outdated = match load_java_lists() {
+                Ok(javas) => javas.iter().any(|java| !java.path.exists()),
+                Err(_) => true,
+            };
+            if outdated {
+                save_java_lists(&detect_java()?)?;
             }
         }
-        Err(_) => save_java_lists(&detect_java()),
+        Err(_) => save_java_lists(&detect_java()?)?,
     }
-    match fs::metadata(configs_current_dir.join("servers.json")) {
-        Ok(_) => trace!(
-            "find -> {}",
-            configs_current_dir.join("servers.json").display()
-        ),
-        Err(_) => {
-            let file = fs::File::create(configs_current_dir.join("servers.json"))?;
-            serde_json::to_writer_pretty(file, &json!({}))?;
-        }
+    // 旧版本把所有服务器保存在configs/servers.json, 现在每个服务器有自己的config.json
+    let legacy = configs_current_dir.join("servers.json");
+    for (name, err) in import_legacy_servers(&configs_current_dir, &workspace.servers_dir())? {
+        eprintln!("无法导入{}中的服务器{name}: {err}", legacy.display());
     }
     Ok(())
 }
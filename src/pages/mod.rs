@@ -6,9 +6,14 @@
  * Vestibulum commodo. Ut rhoncus gravida arcu.
  */
 
+pub mod backup;
+pub mod clone;
 pub mod config;
 pub mod create;
 pub mod delete;
+pub mod export;
 pub mod import;
 pub mod init;
 pub mod start;
+pub mod stop;
+pub mod update;
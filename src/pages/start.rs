@@ -6,64 +6,72 @@
  * Vestibulum commodo. Ut rhoncus gravida arcu.
  */
 
-use std::error::Error;
-use std::{env, fs, path::Path, process::Command};
+use std::{fs, path::Path, process::Command};
 
 use dialoguer::Confirm;
-use log::trace;
 
+use crate::error::{Error, Result};
 use crate::select::select_server;
+use crate::server::ServerConfig;
+use crate::session::{spawn_host, Session};
+use crate::supervisor::RestartPolicy;
+use crate::workspace::Workspace;
+
+/// 写入eula.txt, 表示同意Minecraft EULA(https://aka.ms/MinecraftEULA)
+pub fn accept_eula(path: &Path) -> Result<()> {
+    let time = chrono::Local::now().format("%a %b %d %H:%M:%S %Z %Y");
+    let contents = format!("# Create By Minecraft Server Config Script\n# By changing the setting below to TRUE you are indicating your agreement to Minecraft EULA(https://aka.ms/MinecraftEULA).\n# {time}\neula=true");
+    fs::write(path.join("eula.txt"), contents)?;
+    Ok(())
+}
 
 /// 如果path路径参数内没有找到eula.txt(不会寻找子文件夹), 就会要求用户同意EULA协议(https://aka.ms/MinecraftEULA)
-pub fn eula(path: &Path) -> Result<(), Box<dyn Error>> {
+pub fn eula(path: &Path) -> Result<()> {
     if fs::metadata(path.join("eula.txt")).is_err() {
         if Confirm::new()
             .with_prompt("你是否同意Minecraft EULA(https://aka.ms/MinecraftEULA)?")
-            .interact()
-            .unwrap()
+            .interact()?
         {
-            let time = chrono::Local::now().format("%a %b %d %H:%M:%S %Z %Y");
-            let contents = format!("# Create By Minecraft Server Config Script\n# By changing the setting below to TRUE you are indicating your agreement to Minecraft EULA(https://aka.ms/MinecraftEULA).\n# {time}\neula=true");
-            fs::write(path.join("eula.txt"), contents)?
+            accept_eula(path)?;
         } else {
-            return Err("必须同意EULA".into());
+            return Err(Error::Eula);
         }
     }
     Ok(())
 }
 
-/// 启动服务器页面
-pub fn main() -> Result<(), Box<dyn Error>> {
-    let mut server = select_server();
-    if server.is_null() {
-        println!("你还没有创建任何一个服务器!");
-        return Ok(());
-    }
-    let name = server["name"].as_str().unwrap();
-    let current_dir = env::current_dir()?.join("MCSCS").join("servers").join(name);
-    eula(&current_dir)?;
-    let mut process = Command::new(server["java"]["path"].as_str().unwrap());
-    process.current_dir(current_dir);
-    for arg in server["jvm_args"].as_array_mut().unwrap() {
-        // 在配置文件设置的JVM参数
-        process.arg(arg.as_str().unwrap());
-    }
-    process.arg(format!("-Xms{}", server["Xms"])); // JVM初始堆内存
-    process.arg(format!("-Xmx{}", server["Xmx"])); // JVM最大堆内存
-    process.arg(format!(
-        // 输出和输入的编码格式
-        "-Dfile.encoding={}",
-        server["encoding"].as_str().unwrap()
-    ));
+/// 返回启动服务器的命令
+pub fn server_command(server: &ServerConfig) -> Result<Command> {
+    let mut process = Command::new(&server.java.path);
+    process.current_dir(Workspace::current()?.server_dir(&server.name));
+    process.args(&server.jvm_args); // 在配置文件设置的JVM参数
+    process.arg(format!("-Xms{}", server.xms)); // JVM初始堆内存
+    process.arg(format!("-Xmx{}", server.xmx)); // JVM最大堆内存
+    process.arg(format!("-Dfile.encoding={}", server.encoding)); // 输出和输入的编码格式
     process.arg("-jar"); // 使用Jar
     process.arg("server.jar"); // Jar路径
-    for arg in server["server_args"].as_array_mut().unwrap() {
-        // 在配置文件设置的服务器参数
-        process.arg(arg.as_str().unwrap());
-    }
-    trace!("shell <- {}", format!("{:?}", process));
-
-    process.spawn().expect("main()").wait().expect("main()");
+    process.args(&server.server_args); // 在配置文件设置的服务器参数
+    Ok(process)
+}
 
-    Ok(())
+/// 启动服务器页面
+pub fn main() -> Result<()> {
+    let server = match select_server()? {
+        Some(server) => server,
+        None => {
+            println!("你还没有创建任何一个服务器!");
+            return Ok(());
+        }
+    };
+    if let Some(session) = Session::load(&server.name)? {
+        println!("服务器{}已经在运行", server.name);
+        return session.attach();
+    }
+    eula(&Workspace::current()?.server_dir(&server.name))?;
+    let policy = Confirm::new()
+        .with_prompt("是否在服务器崩溃后自动重启?")
+        .default(false)
+        .interact()?
+        .then(RestartPolicy::default);
+    spawn_host(&server, policy.as_ref())?.attach()
 }
@@ -0,0 +1,43 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use dialoguer::Confirm;
+
+use crate::control::{is_running, kill_server, restart_server, stop_server, DEFAULT_STOP_TIMEOUT};
+use crate::error::Result;
+use crate::select::{select_option, select_server};
+
+/// 关闭服务器页面
+pub fn main() -> Result<()> {
+    let server = match select_server()? {
+        Some(server) => server,
+        None => {
+            println!("你还没有创建任何一个服务器!");
+            return Ok(());
+        }
+    };
+    if !is_running(&server.name)? {
+        println!("服务器{}没有在运行", server.name);
+        return Ok(());
+    }
+    let options = vec!["关闭", "重启", "强制结束(可能会损坏存档)"];
+    match select_option("请选择一个操作", &options)? {
+        0 => println!("{}", stop_server(&server.name, DEFAULT_STOP_TIMEOUT)?),
+        1 => println!("{}", restart_server(&server, DEFAULT_STOP_TIMEOUT)?),
+        _ => {
+            if Confirm::new()
+                .with_prompt("你是否真的要强制结束此服务器?")
+                .interact()?
+            {
+                kill_server(&server.name)?;
+                println!("服务器{}已强制结束", server.name);
+            }
+        }
+    }
+    Ok(())
+}
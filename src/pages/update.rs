@@ -0,0 +1,338 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use dialoguer::Confirm;
+use log::{info, warn};
+
+use crate::control::is_running;
+use crate::error::{Error, Result};
+use crate::pages::create::{build_version, mc_version};
+use crate::pages::import::SERVER_JAR;
+use crate::provider::{Build, ProviderKind};
+use crate::select::{select_option, select_server};
+use crate::server::{
+    load_servers_lists, save_servers_lists, CoreInfo, ServerConfig, CORE_BACKUP_DIR,
+};
+use crate::workspace::Workspace;
+
+/// 更新前的核心信息, 在[`CORE_BACKUP_DIR`]里
+const CORE_BACKUP_INFO: &str = "info.json";
+
+/// 从[`CoreProvider::builds`]的返回值里找出比`current`更新的构建版本, 从新到旧排序
+///
+/// 来源只返回最新的一部分构建版本, `current`不在里面时说明它更旧, 返回所有构建版本
+///
+/// # 示例
+/// ```
+/// use mcscs::pages::update::newer_builds;
+/// use mcscs::provider::Build;
+/// let builds = ["build3", "build2", "build1"].map(|version| Build {
+///     version: version.to_string(),
+///     update_time: String::new(),
+/// });
+/// assert_eq!(newer_builds("build2", &builds), &builds[..1]);
+/// assert_eq!(newer_builds("build0", &builds), &builds);
+/// ```
+pub fn newer_builds<'a>(current: &str, builds: &'a [Build]) -> &'a [Build] {
+    let end = builds
+        .iter()
+        .position(|build| build.version == current)
+        .unwrap_or(builds.len());
+    &builds[..end]
+}
+
+/// 把Minecraft版本转换为可以比较大小的数字, 快照等不是`1.x.y`格式的版本返回`None`
+fn version_key(version: &str) -> Option<Vec<u32>> {
+    version.split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// 从核心支持的Minecraft版本`mc_versions`里找出比`current`新的正式版本, 从新到旧排序
+///
+/// # 示例
+/// ```
+/// use mcscs::pages::update::newer_mc_versions;
+/// let versions = ["1.19.4", "1.20.4", "1.20", "23w13a", "1.20.1"].map(String::from);
+/// assert_eq!(newer_mc_versions("1.20", &versions), ["1.20.4", "1.20.1"]);
+/// assert!(newer_mc_versions("1.20.4", &versions).is_empty());
+/// ```
+pub fn newer_mc_versions(current: &str, mc_versions: &[String]) -> Vec<String> {
+    let Some(current) = version_key(current) else {
+        return Vec::new();
+    };
+    let mut newer = mc_versions
+        .iter()
+        .filter_map(|version| Some((version_key(version)?, version.clone())))
+        .filter(|(key, _)| *key > current)
+        .collect::<Vec<_>>();
+    newer.sort_by(|a, b| b.cmp(a));
+    newer.into_iter().map(|(_, version)| version).collect()
+}
+
+fn core_backup_dir(name: &str) -> Result<PathBuf> {
+    Ok(Workspace::current()?.server_dir(name).join(CORE_BACKUP_DIR))
+}
+
+/// 读取更新前的核心信息, 没有更新过时返回`None`
+pub fn core_backup(name: &str) -> Result<Option<CoreInfo>> {
+    let path = core_backup_dir(name)?.join(CORE_BACKUP_INFO);
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+}
+
+fn load_stopped_server(name: &str) -> Result<ServerConfig> {
+    let server = load_servers_lists(Some(name))?
+        .remove(name)
+        .ok_or_else(|| Error::ServerNotFound(name.to_string()))?;
+    if is_running(name)? {
+        return Err(Error::Other(format!("服务器{name}正在运行, 请先关闭")));
+    }
+    Ok(server)
+}
+
+/// 用`jar`替换服务器的核心并把配置里的核心信息改为`info`, 原来的核心移动到[`CORE_BACKUP_DIR`]
+///
+/// 任何一步失败时都会恢复原来的核心和配置. 服务器正在运行时返回错误
+pub fn install_core(name: &str, jar: &Path, info: CoreInfo) -> Result<ServerConfig> {
+    let mut server = load_stopped_server(name)?;
+    let server_dir = Workspace::current()?.server_dir(name);
+    let backup_dir = core_backup_dir(name)?;
+    let current = server_dir.join(SERVER_JAR);
+    // 先复制到服务器文件夹, 替换时只需要重命名
+    let part = server_dir.join(format!("{SERVER_JAR}.part"));
+    fs::copy(jar, &part)?;
+
+    let staging = server_dir.join(format!("{CORE_BACKUP_DIR}.part"));
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    let result = (|| {
+        fs::create_dir_all(&staging)?;
+        fs::write(
+            staging.join(CORE_BACKUP_INFO),
+            serde_json::to_string_pretty(&server.info)?,
+        )?;
+        if current.exists() {
+            fs::rename(&current, staging.join(SERVER_JAR))?;
+        }
+        fs::rename(&part, &current)?;
+        let previous = server.info.clone();
+        server.info = info;
+        if let Err(err) = save_servers_lists(name, &server) {
+            server.info = previous;
+            return Err(err);
+        }
+        Ok(())
+    })();
+    if let Err(err) = result {
+        // 把原来的核心放回去
+        let old = staging.join(SERVER_JAR);
+        if old.exists() {
+            let _ = fs::rename(&old, &current);
+        }
+        let _ = fs::remove_file(&part);
+        let _ = fs::remove_dir_all(&staging);
+        return Err(err);
+    }
+    if backup_dir.exists() {
+        fs::remove_dir_all(&backup_dir)?;
+    }
+    fs::rename(&staging, &backup_dir)?;
+    info!(
+        "服务器{name}的核心已更新为{} {} {}",
+        server.info.core, server.info.mc_version, server.info.build_version
+    );
+    Ok(server)
+}
+
+/// 从服务器记录的来源下载并校验核心, 然后用[`install_core`]替换服务器的核心
+///
+/// 更换Minecraft版本后世界会在第一次启动时升级, 回滚核心也无法恢复, 调用前需要提醒用户
+///
+/// # 示例
+/// ```no_run
+/// use mcscs::pages::update::update_core;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     update_core("server_name", "1.20.1", "build594").await.unwrap();
+/// }
+/// ```
+pub async fn update_core(
+    name: &str,
+    mc_version: &str,
+    build_version: &str,
+) -> Result<ServerConfig> {
+    let server = load_stopped_server(name)?;
+    let info = CoreInfo {
+        mc_version: mc_version.to_string(),
+        build_version: build_version.to_string(),
+        ..server.info.clone()
+    };
+    if info == server.info {
+        return Err(Error::Other(format!(
+            "服务器{name}已经在使用{} {mc_version} {build_version}",
+            info.core
+        )));
+    }
+    if info.mc_version != server.info.mc_version {
+        warn!(
+            "服务器{name}的Minecraft版本从{}更换为{mc_version}, 世界升级后无法回滚",
+            server.info.mc_version
+        );
+    }
+    let jar = info
+        .provider
+        .provider()
+        .download(&info.core, mc_version, build_version)
+        .await?;
+    install_core(name, &jar, info)
+}
+
+/// 检查[`ProviderKind::find_core`]找到的来源`kind`是不是服务器记录的来源
+///
+/// 不同来源的构建版本格式不同, 无法和当前的构建版本比较, 记录的来源不可用时返回错误,
+/// 不会使用其他来源的核心
+///
+/// # 示例
+/// ```
+/// use mcscs::pages::update::check_provider;
+/// use mcscs::provider::ProviderKind;
+/// use mcscs::server::CoreInfo;
+/// let info = CoreInfo {
+///     core: "Vanilla".to_string(),
+///     mc_version: "1.20.4".to_string(),
+///     build_version: "release".to_string(),
+///     provider: ProviderKind::Mojang,
+/// };
+/// assert!(check_provider(&info, ProviderKind::Mojang).is_ok());
+/// assert!(check_provider(&info, ProviderKind::FastMirror).is_err());
+/// ```
+pub fn check_provider(info: &CoreInfo, kind: ProviderKind) -> Result<()> {
+    if kind == info.provider {
+        return Ok(());
+    }
+    Err(Error::Other(format!(
+        "{}暂时不可用, {kind}的构建版本无法和当前的构建版本{}比较, 请稍后再试",
+        info.provider, info.build_version
+    )))
+}
+
+/// 返回检查和下载新的构建版本使用的来源, 也就是服务器记录的来源, 不可用时返回错误
+pub async fn update_provider(info: &CoreInfo) -> Result<ProviderKind> {
+    let (kind, _) = info.provider.find_core(&info.core).await?;
+    check_provider(info, kind)?;
+    Ok(kind)
+}
+
+/// 回滚到更新前的核心, 当前的核心会成为新的备份, 可以再次回滚来撤销
+pub fn rollback_core(name: &str) -> Result<ServerConfig> {
+    let backup_dir = core_backup_dir(name)?;
+    let info =
+        core_backup(name)?.ok_or_else(|| Error::Other(format!("服务器{name}没有更新前的核心")))?;
+    // install_core会替换备份文件夹, 先移走
+    let previous = Workspace::current()?
+        .server_dir(name)
+        .join(format!("{CORE_BACKUP_DIR}.rollback"));
+    if previous.exists() {
+        fs::remove_dir_all(&previous)?;
+    }
+    fs::rename(&backup_dir, &previous)?;
+    let result = install_core(name, &previous.join(SERVER_JAR), info);
+    match result {
+        Ok(server) => {
+            fs::remove_dir_all(&previous)?;
+            Ok(server)
+        }
+        Err(err) => {
+            let _ = fs::rename(&previous, &backup_dir);
+            Err(err)
+        }
+    }
+}
+
+/// 提醒用户更换Minecraft版本无法撤销, 用户取消时返回[`Error::Cancelled`]
+fn confirm_version_change(from: &str, to: &str) -> Result<()> {
+    if from == to {
+        return Ok(());
+    }
+    println!("警告: Minecraft版本会从{from}更换为{to}, 服务器启动后世界会按照新的版本升级, 之后即使回滚核心也无法恢复, 请先备份世界!");
+    if Confirm::new().with_prompt("是否继续?").interact()? {
+        Ok(())
+    } else {
+        Err(Error::Cancelled)
+    }
+}
+
+/// 更新服务器核心页面
+pub async fn main() -> Result<()> {
+    let server = match select_server()? {
+        Some(server) => server,
+        None => {
+            println!("你还没有创建任何一个服务器!");
+            return Ok(());
+        }
+    };
+    let info = &server.info;
+    println!(
+        "当前核心: {} {} {}",
+        info.core, info.mc_version, info.build_version
+    );
+    let options = [
+        "更新到新的构建版本",
+        "切换Minecraft版本或构建版本",
+        "回滚到更新前的核心",
+        "返回",
+    ];
+    let selection = select_option("请选择一个选项", &options)?;
+    let updated = if selection == 0 {
+        let kind = update_provider(info).await?;
+        let builds = kind.provider().builds(&info.core, &info.mc_version).await?;
+        let newer = newer_builds(&info.build_version, &builds);
+        if newer.is_empty() {
+            println!("已经是最新的构建版本");
+            return Ok(());
+        }
+        let options = newer
+            .iter()
+            .map(|build| format!("{}(更新时间: {})", build.version, build.update_time))
+            .collect::<Vec<_>>();
+        let build = &newer[select_option("请选择一个构建版本", &options)?];
+        update_core(&server.name, &info.mc_version, &build.version).await?
+    } else if selection == 1 {
+        let provider = update_provider(info).await?.provider();
+        let version = mc_version(provider.as_ref(), &info.core).await?;
+        let build = build_version(provider.as_ref(), &info.core, &version).await?;
+        confirm_version_change(&info.mc_version, &version)?;
+        update_core(&server.name, &version, &build).await?
+    } else if selection == 2 {
+        let backup = match core_backup(&server.name)? {
+            Some(backup) => backup,
+            None => {
+                println!("服务器{}没有更新前的核心", server.name);
+                return Ok(());
+            }
+        };
+        confirm_version_change(&info.mc_version, &backup.mc_version)?;
+        rollback_core(&server.name)?
+    } else {
+        return Ok(());
+    };
+    let info = &updated.info;
+    println!(
+        "服务器{}的核心已更换为{} {} {}",
+        updated.name, info.core, info.mc_version, info.build_version
+    );
+    Ok(())
+}
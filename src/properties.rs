@@ -0,0 +1,483 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::{fmt, fs, net::TcpListener, path::Path};
+
+use crate::error::{Error, Result};
+use crate::server::{load_servers_lists, ConfigError};
+use crate::workspace::Workspace;
+
+/// `server.properties`的文件名, 在服务器文件夹里
+pub const PROPERTIES_FILE: &str = "server.properties";
+
+/// `server.properties`配置项的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropertyKind {
+    /// `true`或者`false`
+    Bool,
+    /// 范围内的整数
+    Int { min: i64, max: i64 },
+    /// 端口, 1-65535
+    Port,
+    /// 可选的值, 旧版本的服务器使用序号, 所以也可以输入序号
+    Enum(&'static [&'static str]),
+    /// 任意文本
+    Text,
+}
+
+/// 一个已知的`server.properties`配置项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PropertySpec {
+    pub key: &'static str,
+    pub kind: PropertyKind,
+    pub description: &'static str,
+}
+
+const fn spec(key: &'static str, kind: PropertyKind, description: &'static str) -> PropertySpec {
+    PropertySpec {
+        key,
+        kind,
+        description,
+    }
+}
+
+/// 常用的`server.properties`配置项, 修改时会检查值是否有效
+pub const KNOWN_PROPERTIES: &[PropertySpec] = &[
+    spec("server-port", PropertyKind::Port, "服务器端口"),
+    spec(
+        "server-ip",
+        PropertyKind::Text,
+        "监听的IP, 留空监听所有地址",
+    ),
+    spec("motd", PropertyKind::Text, "服务器列表里显示的描述"),
+    spec(
+        "max-players",
+        PropertyKind::Int {
+            min: 0,
+            max: i32::MAX as i64,
+        },
+        "最大玩家数量",
+    ),
+    spec("online-mode", PropertyKind::Bool, "正版验证"),
+    spec(
+        "difficulty",
+        PropertyKind::Enum(&["peaceful", "easy", "normal", "hard"]),
+        "难度",
+    ),
+    spec(
+        "gamemode",
+        PropertyKind::Enum(&["survival", "creative", "adventure", "spectator"]),
+        "默认游戏模式",
+    ),
+    spec("hardcore", PropertyKind::Bool, "极限模式"),
+    spec("pvp", PropertyKind::Bool, "允许玩家互相攻击"),
+    spec(
+        "view-distance",
+        PropertyKind::Int { min: 3, max: 32 },
+        "视距(区块)",
+    ),
+    spec(
+        "simulation-distance",
+        PropertyKind::Int { min: 3, max: 32 },
+        "模拟距离(区块)",
+    ),
+    spec("white-list", PropertyKind::Bool, "启用白名单"),
+    spec(
+        "enforce-whitelist",
+        PropertyKind::Bool,
+        "重新加载白名单时踢出不在白名单里的玩家",
+    ),
+    spec(
+        "spawn-protection",
+        PropertyKind::Int {
+            min: 0,
+            max: i32::MAX as i64,
+        },
+        "出生点保护半径, 0为关闭",
+    ),
+    spec("allow-flight", PropertyKind::Bool, "允许飞行"),
+    spec("enable-command-block", PropertyKind::Bool, "启用命令方块"),
+    spec("level-name", PropertyKind::Text, "世界文件夹的名称"),
+    spec("level-seed", PropertyKind::Text, "世界种子, 留空随机生成"),
+    spec(
+        "level-type",
+        PropertyKind::Text,
+        "世界类型, 例如: minecraft:normal",
+    ),
+    spec("enable-query", PropertyKind::Bool, "启用UDP查询协议"),
+    spec("query.port", PropertyKind::Port, "查询协议的端口"),
+    spec("enable-rcon", PropertyKind::Bool, "启用RCON"),
+    spec("rcon.port", PropertyKind::Port, "RCON端口"),
+    spec("rcon.password", PropertyKind::Text, "RCON密码"),
+];
+
+/// 返回已知配置项的说明和类型
+pub fn property_spec(key: &str) -> Option<&'static PropertySpec> {
+    KNOWN_PROPERTIES.iter().find(|spec| spec.key == key)
+}
+
+/// 检查配置项的值是否有效, 返回规范化后的值, 不认识的配置项原样返回
+///
+/// # 示例
+/// ```
+/// use mcscs::properties::validate_property;
+/// assert_eq!(validate_property("online-mode", "TRUE").unwrap(), "true");
+/// assert_eq!(validate_property("difficulty", "Hard").unwrap(), "hard");
+/// assert!(validate_property("server-port", "70000").is_err());
+/// assert_eq!(validate_property("custom-key", "abc").unwrap(), "abc");
+/// ```
+pub fn validate_property(key: &str, value: &str) -> std::result::Result<String, ConfigError> {
+    let Some(spec) = property_spec(key) else {
+        return Ok(value.to_string());
+    };
+    let trimmed = value.trim();
+    match spec.kind {
+        PropertyKind::Bool => match trimmed.to_lowercase().as_str() {
+            value @ ("true" | "false") => Ok(value.to_string()),
+            _ => Err(ConfigError::new(key, "只能是true或者false")),
+        },
+        PropertyKind::Int { min, max } => match trimmed.parse::<i64>() {
+            Ok(number) if (min..=max).contains(&number) => Ok(number.to_string()),
+            _ => Err(ConfigError::new(
+                key,
+                format!("必须是{min}到{max}之间的整数"),
+            )),
+        },
+        PropertyKind::Port => match trimmed.parse::<u16>() {
+            Ok(port) if port != 0 => Ok(port.to_string()),
+            _ => Err(ConfigError::new(key, "必须是1到65535之间的端口")),
+        },
+        PropertyKind::Enum(values) => {
+            let lower = trimmed.to_lowercase();
+            if values.contains(&lower.as_str()) {
+                return Ok(lower);
+            }
+            match trimmed.parse::<usize>() {
+                Ok(index) if index < values.len() => Ok(index.to_string()),
+                _ => Err(ConfigError::new(
+                    key,
+                    format!("可选的值: {}", values.join(", ")),
+                )),
+            }
+        }
+        PropertyKind::Text => {
+            if key == "level-name" && trimmed.is_empty() {
+                return Err(ConfigError::new(key, "世界名称不能为空"));
+            }
+            Ok(value.to_string())
+        }
+    }
+}
+
+/// Minecraft默认的服务器端口
+pub const DEFAULT_SERVER_PORT: u16 = 25565;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Line {
+    /// 注释, 空行, 原样保留
+    Raw(String),
+    /// 配置项, `raw`是文件里原来的内容, 修改后为`None`
+    Entry {
+        key: String,
+        value: String,
+        raw: Option<String>,
+    },
+}
+
+/// Java的`.properties`文件, 修改时保留注释和配置项的顺序
+///
+/// # 示例
+/// ```
+/// use mcscs::properties::Properties;
+/// let mut properties = Properties::parse("#Minecraft server properties\nserver-port=25565\n");
+/// assert_eq!(properties.get("server-port"), Some("25565"));
+/// properties.set("server-port", "25566");
+/// properties.set("motd", "A Minecraft Server");
+/// assert_eq!(
+///     properties.to_string(),
+///     "#Minecraft server properties\nserver-port=25566\nmotd=A Minecraft Server\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Properties {
+    lines: Vec<Line>,
+}
+
+impl Properties {
+    /// 解析`.properties`文件的内容
+    pub fn parse(data: &str) -> Self {
+        let mut lines = Vec::new();
+        let mut raw_lines = data.lines();
+        while let Some(line) = raw_lines.next() {
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('!') {
+                lines.push(Line::Raw(line.to_string()));
+                continue;
+            }
+            // 以奇数个`\`结尾的行和下一行是同一个配置项
+            let mut raw = line.to_string();
+            let mut logical = trimmed.to_string();
+            while ends_with_continuation(&logical) {
+                logical.pop();
+                match raw_lines.next() {
+                    Some(next) => {
+                        raw.push('\n');
+                        raw.push_str(next);
+                        logical.push_str(next.trim_start());
+                    }
+                    None => break,
+                }
+            }
+            let (key, value) = split_entry(&logical);
+            lines.push(Line::Entry {
+                key: unescape(key),
+                value: unescape(value),
+                raw: Some(raw),
+            });
+        }
+        Self { lines }
+    }
+
+    /// 读取`.properties`文件, 文件不存在时返回空的配置
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    /// 保存到文件
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    /// 返回配置项的值
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.lines.iter().rev().find_map(|line| match line {
+            Line::Entry { key: k, value, .. } if k == key => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    /// 修改配置项, 不存在时添加到末尾
+    pub fn set(&mut self, key: &str, value: &str) {
+        for line in self.lines.iter_mut().rev() {
+            if let Line::Entry {
+                key: k,
+                value: v,
+                raw,
+            } = line
+            {
+                if k == key {
+                    if v != value {
+                        *v = value.to_string();
+                        *raw = None;
+                    }
+                    return;
+                }
+            }
+        }
+        self.lines.push(Line::Entry {
+            key: key.to_string(),
+            value: value.to_string(),
+            raw: None,
+        });
+    }
+
+    /// 删除配置项, 返回原来的值
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let index = self
+            .lines
+            .iter()
+            .position(|line| matches!(line, Line::Entry { key: k, .. } if k == key))?;
+        match self.lines.remove(index) {
+            Line::Entry { value, .. } => Some(value),
+            Line::Raw(_) => None,
+        }
+    }
+
+    /// 按文件里的顺序返回所有配置项
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Entry { key, value, .. } => Some((key.as_str(), value.as_str())),
+            Line::Raw(_) => None,
+        })
+    }
+}
+
+impl fmt::Display for Properties {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            match line {
+                Line::Raw(line) => writeln!(f, "{line}")?,
+                Line::Entry { raw: Some(raw), .. } => writeln!(f, "{raw}")?,
+                Line::Entry {
+                    key,
+                    value,
+                    raw: None,
+                } => writeln!(f, "{}={}", escape(key, true), escape(value, false))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 除了`except`以外的服务器在`server.properties`里使用的端口, 包括`server-port`, `rcon.port`和`query.port`
+///
+/// 没有设置`server-port`和`rcon.port`时使用Minecraft的默认值
+pub fn used_ports(except: &str) -> Result<Vec<u16>> {
+    let workspace = Workspace::current()?;
+    let mut used = Vec::new();
+    for name in load_servers_lists(None)?.into_keys() {
+        if name == except {
+            continue;
+        }
+        let properties = Properties::load(&workspace.server_dir(&name).join(PROPERTIES_FILE))?;
+        for (key, default) in [
+            ("server-port", Some(DEFAULT_SERVER_PORT)),
+            ("rcon.port", Some(crate::rcon::DEFAULT_PORT)),
+            ("query.port", None),
+        ] {
+            let port = match properties.get(key) {
+                None | Some("") => default,
+                Some(port) => port.parse().ok(),
+            };
+            used.extend(port);
+        }
+    }
+    Ok(used)
+}
+
+/// 从`start`开始找一个其他服务器没有使用并且可以监听的端口
+pub fn free_port(start: u16, except: &str) -> Result<Option<u16>> {
+    let used = used_ports(except)?;
+    Ok((start..u16::MAX)
+        .find(|port| !used.contains(port) && TcpListener::bind(("0.0.0.0", *port)).is_ok()))
+}
+
+/// 给服务器重新分配其他服务器没有使用的端口, 用于从其他服务器复制出来的服务器
+///
+/// 修改`server-port`, 启用RCON时的`rcon.port`和设置过的`query.port`, 返回修改后的端口
+pub fn assign_free_ports(name: &str) -> Result<Vec<(&'static str, u16)>> {
+    let path = Workspace::current()?.server_dir(name).join(PROPERTIES_FILE);
+    let mut properties = Properties::load(&path)?;
+    let rcon = properties.get("enable-rcon") == Some("true");
+    let query = properties
+        .get("query.port")
+        .is_some_and(|port| !port.is_empty());
+    let mut assigned: Vec<(&'static str, u16)> = Vec::new();
+    for (key, start, enabled) in [
+        ("server-port", DEFAULT_SERVER_PORT, true),
+        ("rcon.port", crate::rcon::DEFAULT_PORT, rcon),
+        ("query.port", DEFAULT_SERVER_PORT, query),
+    ] {
+        if !enabled {
+            continue;
+        }
+        let mut start = start;
+        let port = loop {
+            let port =
+                free_port(start, name)?.ok_or_else(|| Error::Other("没有可用的端口".into()))?;
+            if !assigned.iter().any(|(_, used)| *used == port) {
+                break port;
+            }
+            start = port + 1;
+        };
+        properties.set(key, &port.to_string());
+        assigned.push((key, port));
+    }
+    properties.save(&path)?;
+    Ok(assigned)
+}
+
+fn ends_with_continuation(line: &str) -> bool {
+    line.chars().rev().take_while(|c| *c == '\\').count() % 2 == 1
+}
+
+/// 把一行分成键和值, 键和值之间可以是`=`, `:`或者空白
+fn split_entry(line: &str) -> (&str, &str) {
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '=' | ':' | ' ' | '\t' | '\x0c' => {
+                let key = &line[..i];
+                let mut rest = line[i..].trim_start_matches([' ', '\t', '\x0c']);
+                if c.is_whitespace() || c == '\x0c' {
+                    if let Some(r) = rest.strip_prefix(['=', ':']) {
+                        rest = r;
+                    }
+                } else {
+                    rest = &rest[1..];
+                }
+                return (key, rest.trim_start_matches([' ', '\t', '\x0c']));
+            }
+            _ => {}
+        }
+    }
+    (line, "")
+}
+
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('f') => result.push('\x0c'),
+            Some('u') => {
+                let hex = chars.by_ref().take(4).collect::<String>();
+                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some(c) => result.push(c),
+                    None => result.push_str(&hex),
+                }
+            }
+            Some(c) => result.push(c),
+            None => {}
+        }
+    }
+    result
+}
+
+/// 转义后写入文件, 和Java的`Properties.store`一样, 非ASCII字符写成`\uXXXX`
+fn escape(value: &str, is_key: bool) -> String {
+    let mut result = String::with_capacity(value.len());
+    for (i, c) in value.chars().enumerate() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '\t' => result.push_str("\\t"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\x0c' => result.push_str("\\f"),
+            '=' | ':' | '#' | '!' => {
+                result.push('\\');
+                result.push(c);
+            }
+            ' ' if is_key || i == 0 => result.push_str("\\ "),
+            c if c.is_ascii() => result.push(c),
+            c => {
+                let mut buf = [0u16; 2];
+                for unit in c.encode_utf16(&mut buf) {
+                    result.push_str(&format!("\\u{unit:04X}"));
+                }
+            }
+        }
+    }
+    result
+}
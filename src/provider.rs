@@ -0,0 +1,206 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::{fmt, path::PathBuf};
+
+use async_trait::async_trait;
+use clap::ValueEnum;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::aria2c::download;
+use crate::error::{Error, Result};
+use crate::fastmirror::{get_file_sha1, FastMirror};
+use crate::mojang::Mojang;
+use crate::output::CoreEntry;
+
+/// 核心的一个构建版本
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Build {
+    /// 构建版本, 例如: `build593`
+    pub version: String,
+    /// 更新时间, 例如: `2024-03-04T06:38:48`
+    pub update_time: String,
+}
+
+impl Build {
+    /// 把[`get_fastmirror_builds_value`](crate::fastmirror::get_fastmirror_builds_value)的返回值转换为构建版本列表,
+    /// 按更新时间从新到旧排序
+    ///
+    /// # 示例
+    /// ```
+    /// use mcscs::provider::Build;
+    /// use serde_json::json;
+    /// let builds = Build::from_fastmirror(&json!({
+    ///     "build1": {"core_version": "build1", "update_time": "2024-03-01T00:00:00"},
+    ///     "build2": {"core_version": "build2", "update_time": "2024-03-02T00:00:00"},
+    /// }));
+    /// assert_eq!(builds[0].version, "build2");
+    /// ```
+    pub fn from_fastmirror(builds: &Value) -> Vec<Self> {
+        let mut builds = builds
+            .as_object()
+            .into_iter()
+            .flatten()
+            .map(|(version, build)| Build {
+                version: version.clone(),
+                update_time: build["update_time"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+            })
+            .collect::<Vec<_>>();
+        builds.sort_by(|a, b| (&b.update_time, &b.version).cmp(&(&a.update_time, &a.version)));
+        builds
+    }
+}
+
+/// 服务器核心的来源, 负责查询可用的核心和版本, 以及下载并校验核心
+///
+/// # 示例
+/// ```no_run
+/// use mcscs::provider::{CoreProvider, ProviderKind};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let provider = ProviderKind::FastMirror.provider();
+///     let builds = provider.builds("Mohist", "1.20.1").await.unwrap();
+///     let path = provider.download("Mohist", "1.20.1", &builds[0].version).await.unwrap();
+///     println!("{}", path.display());
+/// }
+/// ```
+#[async_trait]
+pub trait CoreProvider: Send + Sync {
+    /// 显示给用户的名称, 例如: `FastMirror`
+    fn name(&self) -> &'static str;
+
+    /// 可用的核心
+    async fn cores(&self) -> Result<Vec<CoreEntry>>;
+
+    /// 核心支持的Minecraft版本, 从新到旧排序
+    async fn mc_versions(&self, core: &str) -> Result<Vec<String>> {
+        self.cores()
+            .await?
+            .into_iter()
+            .find(|entry| entry.name == core)
+            .map(|entry| entry.mc_versions)
+            .ok_or_else(|| Error::Other(format!("{}上没有核心{core}", self.name())))
+    }
+
+    /// 核心在Minecraft版本`mc_version`下的构建版本, 从新到旧排序
+    async fn builds(&self, core: &str, mc_version: &str) -> Result<Vec<Build>>;
+
+    /// 核心需要的Java主版本号, 来源没有记录时返回`None`
+    async fn java_version(&self, _core: &str, _mc_version: &str) -> Result<Option<u32>> {
+        Ok(None)
+    }
+
+    /// 下载核心的地址
+    async fn download_url(&self, core: &str, mc_version: &str, build: &str) -> Result<String>;
+
+    /// 核心的SHA1, 下载后用来校验文件
+    async fn sha1(&self, core: &str, mc_version: &str, build: &str) -> Result<String>;
+
+    /// 下载核心并检查SHA1, 返回下载的文件
+    async fn download(&self, core: &str, mc_version: &str, build: &str) -> Result<PathBuf> {
+        let url = self.download_url(core, mc_version, build).await?;
+        let expected = self.sha1(core, mc_version, build).await?;
+        let file_path = download(&url)?;
+        let actual = get_file_sha1(&file_path)?;
+        if actual != expected {
+            error!("{}: {expected} != File: {actual}", self.name());
+            return Err(Error::Checksum { expected, actual });
+        }
+        info!("{} -> {}", url, file_path.display());
+        Ok(file_path)
+    }
+}
+
+/// 可以选择的核心来源
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ValueEnum)]
+pub enum ProviderKind {
+    /// FastMirror(download.fastmirror.net), 有各种服务器核心
+    #[default]
+    #[value(name = "fastmirror")]
+    #[serde(rename = "fastmirror")]
+    FastMirror,
+    /// Mojang官方的版本清单, 只有原版核心
+    #[value(name = "mojang")]
+    #[serde(rename = "mojang")]
+    Mojang,
+}
+
+impl ProviderKind {
+    /// 所有的核心来源, 按照默认的优先级排序
+    pub const ALL: [ProviderKind; 2] = [ProviderKind::FastMirror, ProviderKind::Mojang];
+
+    /// 返回对应的[`CoreProvider`]
+    pub fn provider(self) -> Box<dyn CoreProvider> {
+        match self {
+            ProviderKind::FastMirror => Box::new(FastMirror),
+            ProviderKind::Mojang => Box::new(Mojang::default()),
+        }
+    }
+
+    /// 先使用`self`, 不可用时依次使用的其他来源
+    ///
+    /// # 示例
+    /// ```
+    /// use mcscs::provider::ProviderKind;
+    /// assert_eq!(
+    ///     ProviderKind::Mojang.with_fallbacks(),
+    ///     [ProviderKind::Mojang, ProviderKind::FastMirror]
+    /// );
+    /// ```
+    pub fn with_fallbacks(self) -> Vec<ProviderKind> {
+        let mut kinds = vec![self];
+        kinds.extend(Self::ALL.into_iter().filter(|kind| *kind != self));
+        kinds
+    }
+
+    /// 按照[`with_fallbacks`](Self::with_fallbacks)的顺序找到第一个可用并且有核心`core`的来源,
+    /// 返回这个来源和核心支持的Minecraft版本
+    ///
+    /// 所有来源都不可用时返回`self`的错误
+    ///
+    /// # 示例
+    /// ```no_run
+    /// use mcscs::provider::ProviderKind;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let (kind, mc_versions) = ProviderKind::Mojang.find_core("Vanilla").await.unwrap();
+    ///     println!("{kind}: {mc_versions:?}");
+    /// }
+    /// ```
+    pub async fn find_core(self, core: &str) -> Result<(ProviderKind, Vec<String>)> {
+        let mut first_err = None;
+        for kind in self.with_fallbacks() {
+            match kind.provider().mc_versions(core).await {
+                Ok(mc_versions) => {
+                    if kind != self {
+                        warn!("{self}不可用, 使用{kind}");
+                    }
+                    return Ok((kind, mc_versions));
+                }
+                Err(err) => {
+                    warn!("无法从{kind}获取核心{core}: {err}");
+                    first_err.get_or_insert(err);
+                }
+            }
+        }
+        Err(first_err.unwrap_or_else(|| Error::Other(format!("没有可用的来源提供核心{core}"))))
+    }
+}
+
+impl fmt::Display for ProviderKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.provider().name())
+    }
+}
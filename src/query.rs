@@ -0,0 +1,315 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+//! GameSpy4 UDP查询协议, 协议见<https://wiki.vg/Query>
+//!
+//! 需要在`server.properties`里设置`enable-query=true`, 可以获取完整的玩家列表, 地图和插件
+
+use std::{
+    collections::BTreeMap,
+    net::{ToSocketAddrs, UdpSocket},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::properties::{Properties, PROPERTIES_FILE};
+use crate::status::{server_address, strip_formatting};
+use crate::workspace::Workspace;
+
+/// 握手
+pub const TYPE_HANDSHAKE: u8 = 9;
+/// 查询状态
+pub const TYPE_STAT: u8 = 0;
+
+/// 每个数据包开头的魔数
+const MAGIC: [u8; 2] = [0xfe, 0xfd];
+
+/// 完整状态回复里键值对前面的填充
+const KV_PADDING: &[u8] = b"splitnum\0\x80\0";
+/// 完整状态回复里玩家列表前面的填充
+const PLAYERS_PADDING: &[u8] = b"\x01player_\0\0";
+
+/// 基本状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BasicStat {
+    pub motd: String,
+    pub game_type: String,
+    pub map: String,
+    pub online: u32,
+    pub max: u32,
+    pub host_port: u16,
+    pub host_ip: String,
+}
+
+/// 完整状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FullStat {
+    pub motd: String,
+    pub game_type: String,
+    pub version: String,
+    /// 服务器核心, 例如: Paper on 1.20.1, 原版服务器为空
+    pub server_mod: String,
+    /// 插件和版本, 例如: WorldEdit 7.2.15
+    pub plugins: Vec<String>,
+    pub map: String,
+    pub online: u32,
+    pub max: u32,
+    pub host_port: u16,
+    pub host_ip: String,
+    /// 所有在线玩家
+    pub players: Vec<String>,
+}
+
+/// 生成请求数据包
+///
+/// # 示例
+/// ```
+/// use mcscs::query::{encode_request, TYPE_HANDSHAKE};
+/// assert_eq!(encode_request(TYPE_HANDSHAKE, 1, &[]), [0xfe, 0xfd, 9, 0, 0, 0, 1]);
+/// ```
+pub fn encode_request(kind: u8, session_id: i32, payload: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(7 + payload.len());
+    packet.extend_from_slice(&MAGIC);
+    packet.push(kind);
+    packet.extend_from_slice(&session_id.to_be_bytes());
+    packet.extend_from_slice(payload);
+    packet
+}
+
+/// 检查回复的类型和会话ID, 返回后面的内容
+fn response_payload(data: &[u8], kind: u8, session_id: i32) -> Result<&[u8]> {
+    if data.len() < 5 || data[0] != kind || data[1..5] != session_id.to_be_bytes() {
+        return Err(Error::Status("无效的查询回复".into()));
+    }
+    Ok(&data[5..])
+}
+
+/// 按`\0`分隔读取字符串
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn string(&mut self) -> Result<String> {
+        let end = self
+            .data
+            .iter()
+            .position(|b| *b == 0)
+            .ok_or_else(|| Error::Status("查询回复不完整".into()))?;
+        let value = String::from_utf8_lossy(&self.data[..end]).into_owned();
+        self.data = &self.data[end + 1..];
+        Ok(value)
+    }
+
+    fn skip(&mut self, expected: &[u8]) -> Result<()> {
+        match self.data.strip_prefix(expected) {
+            Some(rest) => {
+                self.data = rest;
+                Ok(())
+            }
+            None => Err(Error::Status("无效的查询回复".into())),
+        }
+    }
+
+    fn number<T: std::str::FromStr>(&mut self, field: &str) -> Result<T> {
+        let value = self.string()?;
+        value
+            .parse()
+            .map_err(|_| Error::Status(format!("无效的{field}: {value}")))
+    }
+}
+
+/// 解析握手回复, 返回挑战令牌
+pub fn parse_handshake(data: &[u8], session_id: i32) -> Result<i32> {
+    let mut reader = Reader {
+        data: response_payload(data, TYPE_HANDSHAKE, session_id)?,
+    };
+    reader.number("挑战令牌")
+}
+
+/// 解析基本状态回复
+pub fn parse_basic_stat(data: &[u8], session_id: i32) -> Result<BasicStat> {
+    let mut reader = Reader {
+        data: response_payload(data, TYPE_STAT, session_id)?,
+    };
+    let motd = strip_formatting(&reader.string()?);
+    let game_type = reader.string()?;
+    let map = reader.string()?;
+    let online = reader.number("numplayers")?;
+    let max = reader.number("maxplayers")?;
+    if reader.data.len() < 2 {
+        return Err(Error::Status("查询回复不完整".into()));
+    }
+    // 只有这里是小端
+    let host_port = u16::from_le_bytes([reader.data[0], reader.data[1]]);
+    reader.data = &reader.data[2..];
+    let host_ip = reader.string()?;
+    Ok(BasicStat {
+        motd,
+        game_type,
+        map,
+        online,
+        max,
+        host_port,
+        host_ip,
+    })
+}
+
+/// 把`plugins`分成服务器核心和插件列表
+///
+/// # 示例
+/// ```
+/// use mcscs::query::split_plugins;
+/// let (server_mod, plugins) = split_plugins("Paper on 1.20.1: WorldEdit 7.2.15; LuckPerms 5.4.102");
+/// assert_eq!(server_mod, "Paper on 1.20.1");
+/// assert_eq!(plugins, ["WorldEdit 7.2.15", "LuckPerms 5.4.102"]);
+/// ```
+pub fn split_plugins(plugins: &str) -> (String, Vec<String>) {
+    match plugins.split_once(':') {
+        Some((server_mod, list)) => (
+            server_mod.trim().to_string(),
+            list.split(';')
+                .map(str::trim)
+                .filter(|plugin| !plugin.is_empty())
+                .map(String::from)
+                .collect(),
+        ),
+        None => (plugins.trim().to_string(), Vec::new()),
+    }
+}
+
+/// 解析完整状态回复
+pub fn parse_full_stat(data: &[u8], session_id: i32) -> Result<FullStat> {
+    let mut reader = Reader {
+        data: response_payload(data, TYPE_STAT, session_id)?,
+    };
+    reader.skip(KV_PADDING)?;
+    let mut values = BTreeMap::new();
+    loop {
+        let key = reader.string()?;
+        if key.is_empty() {
+            break;
+        }
+        values.insert(key, reader.string()?);
+    }
+    reader.skip(PLAYERS_PADDING)?;
+    let mut players = Vec::new();
+    loop {
+        let player = reader.string()?;
+        if player.is_empty() {
+            break;
+        }
+        players.push(player);
+    }
+    let get = |key: &str| values.get(key).cloned().unwrap_or_default();
+    let number = |key: &str| {
+        get(key)
+            .parse()
+            .map_err(|_| Error::Status(format!("无效的{key}: {}", get(key))))
+    };
+    let (server_mod, plugins) = split_plugins(&get("plugins"));
+    Ok(FullStat {
+        motd: strip_formatting(&get("hostname")),
+        game_type: get("gametype"),
+        version: get("version"),
+        server_mod,
+        plugins,
+        map: get("map"),
+        online: number("numplayers")?,
+        max: number("maxplayers")?,
+        host_port: get("hostport").parse().unwrap_or(0),
+        host_ip: get("hostip"),
+        players,
+    })
+}
+
+/// 查询协议的客户端
+///
+/// # 示例
+/// ```no_run
+/// use mcscs::query::QueryClient;
+/// use mcscs::status::DEFAULT_TIMEOUT;
+/// let client = QueryClient::connect("127.0.0.1:25565", DEFAULT_TIMEOUT).unwrap();
+/// println!("{:?}", client.full_stat().unwrap().players);
+/// ```
+#[derive(Debug)]
+pub struct QueryClient {
+    socket: UdpSocket,
+    session_id: i32,
+}
+
+impl QueryClient {
+    pub fn connect(addr: impl ToSocketAddrs, timeout: Duration) -> Result<Self> {
+        let addr = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| Error::Status("无效的地址".into()))?;
+        let bind = if addr.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(bind)?;
+        socket.set_read_timeout(Some(timeout))?;
+        socket.connect(addr)?;
+        // 服务器只使用会话ID每个字节的低4位
+        let session_id = rand::random::<i32>() & 0x0f0f0f0f;
+        Ok(Self { socket, session_id })
+    }
+
+    fn request(&self, kind: u8, payload: &[u8]) -> Result<Vec<u8>> {
+        self.socket
+            .send(&encode_request(kind, self.session_id, payload))?;
+        let mut buf = vec![0u8; 65535];
+        let len = self.socket.recv(&mut buf)?;
+        buf.truncate(len);
+        Ok(buf)
+    }
+
+    /// 握手, 返回挑战令牌, 令牌每30秒失效
+    pub fn handshake(&self) -> Result<i32> {
+        parse_handshake(&self.request(TYPE_HANDSHAKE, &[])?, self.session_id)
+    }
+
+    /// 查询基本状态
+    pub fn basic_stat(&self) -> Result<BasicStat> {
+        let token = self.handshake()?;
+        let data = self.request(TYPE_STAT, &token.to_be_bytes())?;
+        parse_basic_stat(&data, self.session_id)
+    }
+
+    /// 查询完整状态, 包括所有玩家和插件
+    pub fn full_stat(&self) -> Result<FullStat> {
+        let token = self.handshake()?;
+        let mut payload = token.to_be_bytes().to_vec();
+        payload.extend_from_slice(&[0; 4]);
+        let data = self.request(TYPE_STAT, &payload)?;
+        parse_full_stat(&data, self.session_id)
+    }
+}
+
+/// 读取服务器的`server.properties`并连接查询端口, `query.port`默认和`server-port`相同
+pub fn connect_server(name: &str, timeout: Duration) -> Result<QueryClient> {
+    let path = Workspace::current()?.server_dir(name).join(PROPERTIES_FILE);
+    let properties = Properties::load(&path)?;
+    if properties.get("enable-query") != Some("true") {
+        return Err(Error::Status(format!(
+            "服务器{name}没有启用查询, 请在server.properties里设置enable-query=true"
+        )));
+    }
+    let (host, server_port) = server_address(name)?;
+    let port = match properties.get("query.port") {
+        None | Some("") => server_port,
+        Some(port) => port
+            .parse()
+            .map_err(|_| Error::Status(format!("无效的query.port: {port}")))?,
+    };
+    QueryClient::connect((host.as_str(), port), timeout)
+}
@@ -0,0 +1,256 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+//! RCON客户端, 协议见<https://wiki.vg/RCON>
+//!
+//! 每个数据包是: 长度(i32, 小端, 不包括自己), 请求ID(i32), 类型(i32), 以`\0`结尾的内容, 再加一个`\0`
+
+use std::{
+    io::{self, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    path::Path,
+    time::Duration,
+};
+
+use log::{info, trace};
+use rand::{distr::Alphanumeric, Rng};
+
+use crate::error::{Error, Result};
+use crate::properties::{free_port, Properties, PROPERTIES_FILE};
+use crate::workspace::Workspace;
+
+/// 登录
+pub const TYPE_LOGIN: i32 = 3;
+/// 执行命令
+pub const TYPE_COMMAND: i32 = 2;
+/// 命令的返回
+pub const TYPE_RESPONSE: i32 = 0;
+
+/// Minecraft默认的RCON端口
+pub const DEFAULT_PORT: u16 = 25575;
+
+/// 数据包的最大长度, Minecraft接收的请求最长1460字节, 返回的内容每个包最长4096字节
+const MAX_PACKET_LEN: i32 = 4096 + 10;
+
+/// 连接和读取的超时时间
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 一个RCON数据包
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packet {
+    pub id: i32,
+    pub kind: i32,
+    pub body: String,
+}
+
+impl Packet {
+    pub fn new(id: i32, kind: i32, body: impl Into<String>) -> Self {
+        Self {
+            id,
+            kind,
+            body: body.into(),
+        }
+    }
+
+    /// 编码为要发送的字节
+    ///
+    /// # 示例
+    /// ```
+    /// use mcscs::rcon::{Packet, TYPE_COMMAND};
+    /// let bytes = Packet::new(1, TYPE_COMMAND, "list").encode();
+    /// assert_eq!(bytes.len(), 4 + 4 + 4 + 4 + 2);
+    /// assert_eq!(&bytes[..4], &14i32.to_le_bytes());
+    /// ```
+    pub fn encode(&self) -> Vec<u8> {
+        let body = self.body.as_bytes();
+        let mut bytes = Vec::with_capacity(body.len() + 14);
+        bytes.extend_from_slice(&(body.len() as i32 + 10).to_le_bytes());
+        bytes.extend_from_slice(&self.id.to_le_bytes());
+        bytes.extend_from_slice(&self.kind.to_le_bytes());
+        bytes.extend_from_slice(body);
+        bytes.extend_from_slice(&[0, 0]);
+        bytes
+    }
+
+    /// 读取一个数据包
+    pub fn read(reader: &mut impl Read) -> Result<Self> {
+        let mut buf = [0u8; 4];
+        reader.read_exact(&mut buf)?;
+        let len = i32::from_le_bytes(buf);
+        if !(10..=MAX_PACKET_LEN).contains(&len) {
+            return Err(Error::Rcon(format!("无效的数据包长度{len}")));
+        }
+        let mut data = vec![0u8; len as usize];
+        reader.read_exact(&mut data)?;
+        let id = i32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        let kind = i32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+        let body = &data[8..data.len() - 2];
+        Ok(Self {
+            id,
+            kind,
+            body: String::from_utf8_lossy(body).into_owned(),
+        })
+    }
+}
+
+/// RCON客户端
+///
+/// # 示例
+/// ```no_run
+/// use mcscs::rcon::RconClient;
+/// let mut rcon = RconClient::connect("127.0.0.1:25575", "password").unwrap();
+/// println!("{}", rcon.command("list").unwrap());
+/// ```
+#[derive(Debug)]
+pub struct RconClient {
+    stream: TcpStream,
+    next_id: i32,
+}
+
+impl RconClient {
+    /// 连接并登录
+    pub fn connect(addr: impl ToSocketAddrs, password: &str) -> Result<Self> {
+        let addr = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| Error::Rcon("无效的地址".into()))?;
+        let stream = TcpStream::connect_timeout(&addr, TIMEOUT)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        let mut client = Self { stream, next_id: 1 };
+        let id = client.send(TYPE_LOGIN, password)?;
+        // 有些服务器会在登录的回复前先发送一个空的命令回复
+        loop {
+            let packet = Packet::read(&mut client.stream)?;
+            if packet.id == -1 {
+                return Err(Error::Rcon("密码错误".into()));
+            }
+            if packet.id == id && packet.kind == TYPE_COMMAND {
+                trace!("rcon <- 登录成功 {addr}");
+                return Ok(client);
+            }
+        }
+    }
+
+    fn send(&mut self, kind: i32, body: &str) -> Result<i32> {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1).max(1);
+        self.stream
+            .write_all(&Packet::new(id, kind, body).encode())?;
+        Ok(id)
+    }
+
+    /// 执行命令并返回结果
+    ///
+    /// 返回的内容较长时会被分成多个数据包, 所以命令后面再发送一个无效类型的数据包,
+    /// 收到它的回复时说明命令的返回已经结束
+    pub fn command(&mut self, command: &str) -> Result<String> {
+        trace!("rcon -> {command}");
+        let id = self.send(TYPE_COMMAND, command)?;
+        let end = self.send(TYPE_RESPONSE, "")?;
+        let mut response = String::new();
+        loop {
+            match Packet::read(&mut self.stream) {
+                Ok(packet) if packet.id == id => response.push_str(&packet.body),
+                Ok(packet) if packet.id == end => break,
+                Ok(_) => {}
+                // 不回复无效类型的服务器, 超时后返回已经收到的内容
+                Err(Error::Io(err))
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) && !response.is_empty() =>
+                {
+                    break
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        trace!("rcon <- {response}");
+        Ok(response)
+    }
+
+    /// 交互式的RCON控制台, 输入`:quit`或者输入结束(Ctrl+D/Ctrl+Z)时退出
+    pub fn shell(&mut self) -> Result<()> {
+        println!("已连接到RCON, 输入:quit退出");
+        let mut line = String::new();
+        loop {
+            print!("rcon> ");
+            io::stdout().flush()?;
+            line.clear();
+            if io::stdin().read_line(&mut line)? == 0 || line.trim() == ":quit" {
+                return Ok(());
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = self.command(line.trim())?;
+            if !response.is_empty() {
+                println!("{}", response.trim_end());
+            }
+        }
+    }
+}
+
+/// 读取服务器的`server.properties`并连接RCON
+pub fn connect_server(name: &str) -> Result<RconClient> {
+    let server_dir = Workspace::current()?.server_dir(name);
+    let properties = Properties::load(&server_dir.join(PROPERTIES_FILE))?;
+    if properties.get("enable-rcon") != Some("true") {
+        return Err(Error::Rcon(format!(
+            "服务器{name}没有启用RCON, 请使用`mcscs rcon {name} --enable`启用"
+        )));
+    }
+    let port = rcon_port(&properties)?;
+    let password = properties.get("rcon.password").unwrap_or_default();
+    if password.is_empty() {
+        return Err(Error::Rcon(format!("服务器{name}没有设置rcon.password")));
+    }
+    RconClient::connect(("127.0.0.1", port), password)
+}
+
+fn rcon_port(properties: &Properties) -> Result<u16> {
+    match properties.get("rcon.port") {
+        None | Some("") => Ok(DEFAULT_PORT),
+        Some(port) => port
+            .parse()
+            .map_err(|_| Error::Rcon(format!("无效的rcon.port: {port}"))),
+    }
+}
+
+/// 服务器是否启用了RCON
+pub fn is_enabled(server_dir: &Path) -> bool {
+    Properties::load(&server_dir.join(PROPERTIES_FILE))
+        .is_ok_and(|properties| properties.get("enable-rcon") == Some("true"))
+}
+
+/// 在服务器的`server.properties`里启用RCON, 使用随机生成的密码和一个没有被其他服务器使用的端口
+///
+/// 已经设置过的端口和密码不会修改, 返回端口和密码. 服务器正在运行时需要重启才会生效
+pub fn enable_rcon(name: &str) -> Result<(u16, String)> {
+    let path = Workspace::current()?.server_dir(name).join(PROPERTIES_FILE);
+    let mut properties = Properties::load(&path)?;
+    let port = match properties.get("rcon.port") {
+        Some(port) if !port.is_empty() => rcon_port(&properties)?,
+        _ => free_port(DEFAULT_PORT, name)?.ok_or_else(|| Error::Rcon("没有可用的端口".into()))?,
+    };
+    let password = match properties.get("rcon.password") {
+        Some(password) if !password.is_empty() => password.to_string(),
+        _ => rand::rng()
+            .sample_iter(&Alphanumeric)
+            .take(20)
+            .map(char::from)
+            .collect(),
+    };
+    properties.set("enable-rcon", "true");
+    properties.set("rcon.port", &port.to_string());
+    properties.set("rcon.password", &password);
+    properties.save(&path)?;
+    info!("{}: enable-rcon=true, rcon.port={port}", path.display());
+    Ok((port, password))
+}
@@ -6,18 +6,17 @@
  * Vestibulum commodo. Ut rhoncus gravida arcu.
  */
 
-use std::error::Error;
 use std::fs;
 use std::path::PathBuf;
 
 use dialoguer::theme::ColorfulTheme;
 use dialoguer::Select;
 use log::info;
-use serde_json::Value;
 
-use crate::server::load_servers_lists;
+use crate::error::{Error, Result};
+use crate::server::{load_servers_lists, ServerConfig};
 
-/// 让用户选择一个选项
+/// 让用户选择一个选项, 用户按Esc或q时返回[`Error::Cancelled`]
 ///
 /// # 示例
 /// ```
@@ -26,13 +25,13 @@ use crate::server::load_servers_lists;
 /// let selection = select_option("请选择一个选项", &options).unwrap();
 /// println!("用户选择了: {}", options[selection]);
 /// ```
-pub fn select_option<T: ToString>(description: &str, items: &[T]) -> Result<usize, Box<dyn Error>> {
+pub fn select_option<T: ToString>(description: &str, items: &[T]) -> Result<usize> {
     let selection = Select::with_theme(&ColorfulTheme::default())
         .with_prompt(description)
         .items(items)
-        .interact()?;
-    info!("user -> {selection}");
-    Ok(selection)
+        .interact_opt()?;
+    info!("user -> {selection:?}");
+    selection.ok_or(Error::Cancelled)
 }
 
 /// 让用户选择一个文件
@@ -43,7 +42,7 @@ pub fn select_option<T: ToString>(description: &str, items: &[T]) -> Result<usiz
 /// let file_path = select_file("请选择任意一个文件").unwrap();
 /// println!("选择的文件路径: {}", file_path.display());
 /// ```
-pub fn select_file(description: &str) -> Result<PathBuf, Box<dyn Error>> {
+pub fn select_file(description: &str) -> Result<PathBuf> {
     let mut current_dir = fs::canonicalize(".")?;
 
     loop {
@@ -71,14 +70,13 @@ pub fn select_file(description: &str) -> Result<PathBuf, Box<dyn Error>> {
         let selection = select_option(
             &format!("{} {description}", current_dir.display()),
             &options,
-        )
-        .unwrap();
+        )?;
 
         if selection == 0 {
             // 用户选择了返回上一级目录的选项
             current_dir.pop();
         } else {
-            let (selected_entry, is_dir) = entries.get(selection - 1).unwrap(); // 减去“..”选项的索引
+            let (selected_entry, is_dir) = &entries[selection - 1]; // 减去“..”选项的索引
             if *is_dir {
                 // 如果用户选择的是文件夹，则进入该文件夹
                 current_dir = selected_entry.clone();
@@ -89,19 +87,13 @@ pub fn select_file(description: &str) -> Result<PathBuf, Box<dyn Error>> {
     }
 }
 
-/// 让用户选择一个服务器
-pub fn select_server() -> Value {
-    let server_configs = load_servers_lists(None);
-    let mut server_names = Vec::<&String>::new();
-    if let Some(server) = server_configs.as_object() {
-        for (server, _) in server {
-            server_names.push(server);
-        }
-    }
+/// 让用户选择一个服务器, 如果还没有创建任何服务器则返回`None`
+pub fn select_server() -> Result<Option<ServerConfig>> {
+    let mut server_configs = load_servers_lists(None)?;
+    let server_names = server_configs.keys().cloned().collect::<Vec<String>>();
     if server_names.is_empty() {
-        return Value::Null;
+        return Ok(None);
     }
-    let selection = select_option("请选择一个服务器", &server_names).expect("");
-    let name = server_names[selection];
-    server_configs[name].clone()
+    let selection = select_option("请选择一个服务器", &server_names)?;
+    Ok(server_configs.remove(&server_names[selection]))
 }
@@ -6,72 +6,301 @@
  * Vestibulum commodo. Ut rhoncus gravida arcu.
  */
 
-use std::{env, fs, io::Read};
+use std::{collections::BTreeMap, fmt, fs, path::Path};
 
-use log::trace;
-use serde_json::{json, Value};
+use log::{trace, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-/// 保存服务器配置到`MCSCS\configs\servers.json`
+use crate::backup::STAGING_PREFIX;
+use crate::error::{Error, Result};
+use crate::java::Java;
+use crate::migrate::{self, load_and_upgrade};
+use crate::provider::ProviderKind;
+use crate::session::{PID_FILE, SESSION_FILE};
+use crate::supervisor::HISTORY_FILE;
+use crate::workspace::Workspace;
+
+/// 更新前的核心, 在服务器文件夹里, 只保留最近一次更新前的核心, 参考[`crate::pages::update`]
+pub const CORE_BACKUP_DIR: &str = ".core-backup";
+
+/// 服务器文件夹里MCSCS自己的文件: 配置里有本机的Java路径, 其他是运行时的状态
+const LOCAL_FILES: [&str; 4] = ["config.json", SESSION_FILE, PID_FILE, HISTORY_FILE];
+
+/// 服务器使用的核心信息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoreInfo {
+    /// 核心名称, 例如: `Mohist`
+    pub core: String,
+    /// Minecraft版本, 例如: `1.20.1`
+    pub mc_version: String,
+    /// 构建版本, 例如: `build593`
+    pub build_version: String,
+    /// 核心的来源, 检查和下载新的构建版本时使用, 旧的配置里没有时为FastMirror
+    #[serde(default)]
+    pub provider: ProviderKind,
+}
+
+/// 服务器配置, 对应`MCSCS/servers/<name>/config.json`
+///
+/// # 示例
+/// ```JSON
+/// {
+///     "schema_version": 1,
+///     "name": "server_name",
+///     "java": {
+///         "path": "/usr/lib/jvm/java-17-openjdk/bin/java",
+///         "version": "17.0.9"
+///     },
+///     "jvm_args": ["-Dlog4j2.formatMsgNoLookups=true"],
+///     "encoding": "utf-8",
+///     "Xms": 1073741824,
+///     "Xmx": 2147483648,
+///     "info": {
+///         "core": "Mohist",
+///         "mc_version": "1.20.1",
+///         "build_version": "build593",
+///         "provider": "fastmirror"
+///     },
+///     "server_args": ["--nogui"]
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerConfig {
+    /// 配置格式的版本, 参考[`migrate::SCHEMA_VERSION`]
+    pub schema_version: u64,
+    /// 服务器名称, 同时也是`MCSCS/servers`下的文件夹名称
+    pub name: String,
+    /// 使用的Java环境
+    pub java: Java,
+    /// JVM参数
+    pub jvm_args: Vec<String>,
+    /// 输入和输出的编码
+    pub encoding: String,
+    /// JVM初始堆内存(Bytes)
+    #[serde(rename = "Xms")]
+    pub xms: u64,
+    /// JVM最大堆内存(Bytes)
+    #[serde(rename = "Xmx")]
+    pub xmx: u64,
+    /// 核心信息
+    pub info: CoreInfo,
+    /// 服务器参数
+    pub server_args: Vec<String>,
+}
+
+impl ServerConfig {
+    /// 从JSON字符串解析服务器配置, 旧版本的配置会先升级到当前版本,
+    /// 解析失败或者配置无效时返回的错误会指出是哪个配置项
+    ///
+    /// # 示例
+    /// ```
+    /// use mcscs::server::ServerConfig;
+    /// let err = ServerConfig::from_json(r#"{"name": "test", "java": 17}"#).unwrap_err();
+    /// assert_eq!(err.field, "java");
+    /// ```
+    pub fn from_json(data: &str) -> std::result::Result<Self, ConfigError> {
+        let mut value = serde_json::from_str::<Value>(data)
+            .map_err(|err| ConfigError::new("", err.to_string()))?;
+        migrate::upgrade(&mut value)?;
+        Self::from_value(value)
+    }
+
+    /// 从已经升级到当前版本的JSON解析服务器配置
+    pub fn from_value(value: Value) -> std::result::Result<Self, ConfigError> {
+        let config: Self = serde_path_to_error::deserialize(value).map_err(|err| {
+            let mut field = err.path().to_string();
+            let message = err.into_inner().to_string();
+            // 缺少配置项时路径指向的是上一级, 需要从错误信息里取出缺少的配置项
+            if let Some(missing) = message
+                .strip_prefix("missing field `")
+                .and_then(|rest| rest.split('`').next())
+            {
+                field = match field.as_str() {
+                    "." => missing.to_string(),
+                    parent => format!("{parent}.{missing}"),
+                };
+            }
+            ConfigError::new(&field, message)
+        })?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// 检查配置是否有效
+    pub fn validate(&self) -> std::result::Result<(), ConfigError> {
+        validate_name(&self.name)?;
+        if self.java.path.as_os_str().is_empty() {
+            return Err(ConfigError::new("java.path", "Java路径不能为空"));
+        }
+        if self.encoding.trim().is_empty() {
+            return Err(ConfigError::new("encoding", "编码不能为空"));
+        }
+        if self.xms == 0 {
+            return Err(ConfigError::new("Xms", "Xms不能为0"));
+        }
+        if self.xmx < self.xms {
+            return Err(ConfigError::new("Xmx", "Xmx不能小于Xms"));
+        }
+        Ok(())
+    }
+}
+
+/// Windows上不能用作文件名的名称, 不区分大小写, 也不能加扩展名
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// 检查服务器名称能不能用作`MCSCS/servers`下的文件夹名称
+///
+/// 名称不能为空, 不能包含路径分隔符, 不能以`.`开头, 也不能是Windows的保留名称,
+/// 否则服务器会被保存到`MCSCS/servers`以外或者子文件夹里
 ///
 /// # 示例
 /// ```
-/// use serde_json::json;
+/// use mcscs::server::validate_name;
+/// assert!(validate_name("survival").is_ok());
+/// assert!(validate_name("../survival").is_err());
+/// ```
+pub fn validate_name(name: &str) -> std::result::Result<(), ConfigError> {
+    if name.trim().is_empty() {
+        return Err(ConfigError::new("name", "服务器名称不能为空"));
+    }
+    if name.contains(['/', '\\']) {
+        return Err(ConfigError::new("name", "服务器名称不能包含/或者\\"));
+    }
+    if name.starts_with('.') {
+        return Err(ConfigError::new("name", "服务器名称不能以.开头"));
+    }
+    if name.chars().any(char::is_control) {
+        return Err(ConfigError::new("name", "服务器名称不能包含控制字符"));
+    }
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    if RESERVED_NAMES
+        .iter()
+        .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+    {
+        return Err(ConfigError::new(
+            "name",
+            format!("{name}是系统保留的名称, 不能用作服务器名称"),
+        ));
+    }
+    Ok(())
+}
+
+/// 服务器配置无效
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    /// 出错的配置项, 例如: `java.path`
+    pub field: String,
+    /// 错误信息
+    pub message: String,
+}
+
+impl ConfigError {
+    pub fn new(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.field.is_empty() {
+            write!(f, "配置文件无效: {}", self.message)
+        } else {
+            write!(f, "配置项`{}`无效: {}", self.field, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// 导出, 复制和备份服务器时都不包括的文件, `path`是相对于服务器文件夹的路径, 用`/`分隔
+///
+/// 包括MCSCS自己的配置和运行时的状态, 更新前的核心, 恢复备份时的临时文件夹,
+/// 以及Minecraft运行时锁定的`session.lock`
+///
+/// # 示例
+/// ```
+/// use mcscs::server::is_local_file;
+/// assert!(is_local_file("config.json"));
+/// assert!(is_local_file(".core-backup/server.jar"));
+/// assert!(is_local_file("world/session.lock"));
+/// assert!(!is_local_file("world/level.dat"));
+/// ```
+pub fn is_local_file(path: &str) -> bool {
+    let top = path.split('/').next().unwrap_or(path);
+    LOCAL_FILES.contains(&top)
+        || top.starts_with(CORE_BACKUP_DIR)
+        || top.starts_with(STAGING_PREFIX)
+        || path.ends_with("/session.lock")
+}
+
+/// 读取并解析一个`config.json`, 旧版本的配置会被自动升级
+fn load_server_config(path: &Path) -> Result<ServerConfig> {
+    let config = load_and_upgrade(path).map_err(|err| match err {
+        Error::Config(mut err) => {
+            err.message = format!("{}: {}", path.display(), err.message);
+            Error::Config(err)
+        }
+        err => err,
+    })?;
+    trace!("{} -> {config:?}", path.display());
+    Ok(config)
+}
+
+/// 保存服务器配置到`MCSCS/servers/<server>/config.json`
+///
+/// # 示例
+/// ```no_run
 /// use mcscs::pages::create::to_bytes;
 /// use mcscs::server::{load_servers_lists, save_servers_lists};
-/// let mut server = load_servers_lists(None)["server_name"].take(); // 获取已经保存的配置
-/// server["Xms"] = json!(to_bytes("1G"));
-/// save_servers_lists("name", &server);
+/// let mut servers = load_servers_lists(Some("server_name")).unwrap(); // 获取已经保存的配置
+/// if let Some(server) = servers.get_mut("server_name") {
+///     server.xms = to_bytes("1G");
+///     save_servers_lists("server_name", server).unwrap();
+/// }
 /// ```
-pub fn save_servers_lists(server: &str, config: &Value) {
-    let path = env::current_dir()
-        .expect("save_servers_lists()")
-        .join("MCSCS")
-        .join("servers")
-        .join(server)
-        .join("config.json");
-    let file = fs::File::create(&path).expect("save_servers_lists()");
-    trace!("{} <- {config}", path.display());
-    serde_json::to_writer_pretty(file, &config).expect("save_servers_lists()");
+pub fn save_servers_lists(server: &str, config: &ServerConfig) -> Result<()> {
+    config.validate()?;
+    let path = Workspace::current()?.server_dir(server).join("config.json");
+    let file = fs::File::create(&path)?;
+    trace!("{} <- {config:?}", path.display());
+    serde_json::to_writer_pretty(file, config)?;
+    Ok(())
 }
 
-/// 从[`MCSCS\configs\servers.json`]读取所有服务器配置
-pub fn load_servers_lists(server: Option<&str>) -> Value {
-    let path = env::current_dir()
-        .expect("load_servers_lists()")
-        .join("MCSCS")
-        .join("servers");
+/// 从`MCSCS/servers/*/config.json`读取服务器配置, 以服务器名称为键
+///
+/// 如果指定了`server`则只读取该服务器的配置, 配置无效时返回错误;
+/// 否则读取所有服务器的配置, 无效的配置会被跳过并记录到日志
+pub fn load_servers_lists(server: Option<&str>) -> Result<BTreeMap<String, ServerConfig>> {
+    let path = Workspace::current()?.servers_dir();
+    let mut configs = BTreeMap::new();
     if let Some(server) = server {
         let path = path.join(server).join("config.json");
-        let mut data = String::new();
-        if fs::File::open(&path).is_err() {
-            fs::File::create(&path).expect("load_servers_lists()");
+        if !path.exists() {
+            return Err(Error::ServerNotFound(server.to_string()));
         }
-        let mut file = fs::File::open(&path).expect("load_servers_lists()");
-        file.read_to_string(&mut data)
-            .expect("load_servers_lists()");
-
-        let data = serde_json::from_str::<Value>(&data).expect("load_servers_lists()");
-        trace!("{} -> {data}", path.display());
-        data
+        let config = load_server_config(&path)?;
+        configs.insert(config.name.clone(), config);
     } else {
-        let mut configs = json!({});
-        let entries = fs::read_dir(&path).expect("load_servers_lists()");
-        for entry in entries.flatten() {
+        for entry in fs::read_dir(&path)?.flatten() {
             let path = entry.path().join("config.json");
-            if entry.path().is_dir() {
-                let mut data = String::new();
-                if fs::File::open(&path).is_err() {
-                    fs::File::create(&path).expect("load_servers_lists()");
+            if !entry.path().is_dir() || !path.exists() {
+                continue;
+            }
+            match load_server_config(&path) {
+                Ok(config) => {
+                    configs.insert(config.name.clone(), config);
                 }
-                let mut file = fs::File::open(&path).expect("load_servers_lists()");
-                file.read_to_string(&mut data)
-                    .expect("load_servers_lists()");
-
-                let data = serde_json::from_str::<Value>(&data).expect("load_servers_lists()");
-                trace!("{} -> {data}", path.display());
-                configs[data["name"].as_str().expect("load_servers_lists()")] = data.clone();
+                Err(err) => warn!("已跳过无效的服务器配置 {err}"),
             }
         }
-        configs
     }
+    Ok(configs)
 }
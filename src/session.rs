@@ -0,0 +1,613 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+//! 控制台会话
+//!
+//! 服务器由一个后台的宿主进程(`mcscs host <name>`)启动, 宿主进程接管服务器的输入和输出,
+//! 把输出写入`servers/<name>/sessions/<时间>.log`, 并在`127.0.0.1`上监听一个端口,
+//! 其他mcscs进程通过这个端口连接(attach)控制台或者发送命令, 断开连接(detach)后服务器继续运行.
+//!
+//! 宿主进程的信息保存在`servers/<name>/session.json`, 协议是按行分隔的文本:
+//! * `ATTACH <token>`: 先返回最近的输出, 然后持续返回新的输出, 之后客户端发送的每一行都会作为命令发给服务器
+//! * `COMMAND <token> <命令>`: 把一条命令发给服务器, 返回`OK`或者`ERR <原因>`
+//! * `INTENT <token> stop|restart`: 告诉宿主服务器接下来的退出是要关闭或者重启, 而不是崩溃, 返回`OK`或者`ERR <原因>`
+//!
+//! 服务器运行时它的PID保存在`servers/<name>/server.pid`, 宿主进程异常退出时也可以通过它找到服务器
+
+use std::{
+    collections::VecDeque,
+    env, fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    process::{self, ChildStdin, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use chrono::Local;
+use encoding_rs::{Encoding, UTF_8};
+use log::{error, info, trace, warn};
+use rand::{distr::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::backup;
+use crate::control::is_alive;
+use crate::error::{Error, Result};
+use crate::pages::start::server_command;
+use crate::server::ServerConfig;
+use crate::supervisor::{supervise, RestartPolicy, ServerExit};
+use crate::workspace::Workspace;
+
+/// 会话信息的文件名, 保存在服务器文件夹里
+pub const SESSION_FILE: &str = "session.json";
+
+/// 保存会话日志的文件夹名, 在服务器文件夹里
+pub const SESSIONS_DIR: &str = "sessions";
+
+/// 连接控制台时先发送的最近输出的行数
+const SCROLLBACK_LINES: usize = 500;
+
+/// 每个连接最多排队等待发送的行数, 超过时说明客户端卡住了, 断开它
+const CLIENT_QUEUE_LINES: usize = 2048;
+
+/// 向客户端写入输出的超时时间, 超时后断开连接
+const CLIENT_WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 在控制台输入这个会断开连接, 服务器继续运行
+pub const DETACH_COMMAND: &str = ":detach";
+
+/// 保存服务器PID的文件名, 在服务器文件夹里
+pub const PID_FILE: &str = "server.pid";
+
+/// 读取`server.pid`, 服务器没有运行时返回`None`
+pub fn read_pid(server_dir: &Path) -> Option<u32> {
+    fs::read_to_string(server_dir.join(PID_FILE))
+        .ok()
+        .and_then(|pid| pid.trim().parse().ok())
+}
+
+/// 服务器接下来的退出是关闭还是重启
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Intent {
+    Stop,
+    Restart,
+}
+
+impl Intent {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Intent::Stop => "stop",
+            Intent::Restart => "restart",
+        }
+    }
+
+    fn parse(intent: &str) -> Option<Self> {
+        match intent {
+            "stop" => Some(Intent::Stop),
+            "restart" => Some(Intent::Restart),
+            _ => None,
+        }
+    }
+}
+
+/// 正在运行的会话, 保存在`servers/<name>/session.json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    /// 宿主进程的PID
+    pub host_pid: u32,
+    /// 服务器(Java)进程的PID, 自动重启后会更新
+    pub server_pid: u32,
+    /// 宿主进程监听的端口
+    pub port: u16,
+    /// 连接时需要的令牌, 防止其他用户控制服务器
+    pub token: String,
+    /// 宿主进程启动的时间, Unix时间戳(秒)
+    pub started_at: i64,
+    /// 本次会话的日志
+    pub log: PathBuf,
+}
+
+impl Session {
+    /// 读取服务器正在运行的会话, 没有运行或者宿主进程已经退出时返回`None`
+    ///
+    /// 连接不上但是宿主进程还在时(例如正在启动)仍然返回会话, 只有宿主进程已经退出时才删除`session.json`
+    pub fn load(name: &str) -> Result<Option<Self>> {
+        let path = Workspace::current()?.server_dir(name).join(SESSION_FILE);
+        let data = match fs::read_to_string(&path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let session = serde_json::from_str::<Session>(&data)?;
+        if TcpStream::connect(("127.0.0.1", session.port)).is_err() && !is_alive(session.host_pid) {
+            // 宿主进程没有正常退出, 留下了旧的session.json
+            warn!("{}已过期, 删除", path.display());
+            fs::remove_file(&path)?;
+            return Ok(None);
+        }
+        Ok(Some(session))
+    }
+
+    /// 先写入临时文件再重命名, 同时读取的进程不会读到写了一半的文件
+    ///
+    /// 文件里有令牌, Unix上只有自己可以读写, 防止其他用户控制服务器
+    fn save(&self, server_dir: &Path) -> Result<()> {
+        let part = server_dir.join(format!("{SESSION_FILE}.part"));
+        // 权限只在创建文件时设置, 先删除上次留下的临时文件
+        let _ = fs::remove_file(&part);
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        serde_json::to_writer_pretty(options.open(&part)?, self)?;
+        fs::rename(&part, server_dir.join(SESSION_FILE))?;
+        Ok(())
+    }
+
+    /// 连接宿主进程并发送第一行请求
+    fn request(&self, request: &str) -> Result<TcpStream> {
+        let mut stream = TcpStream::connect(("127.0.0.1", self.port))?;
+        writeln!(stream, "{request}")?;
+        Ok(stream)
+    }
+
+    /// 发送一个请求并读取`OK`或者`ERR <原因>`
+    fn call(&self, request: &str) -> Result<()> {
+        let stream = self.request(request)?;
+        let mut reply = String::new();
+        BufReader::new(stream).read_line(&mut reply)?;
+        let reply = reply.trim_end();
+        match reply.strip_prefix("ERR ") {
+            None if reply == "OK" => Ok(()),
+            None => Err(Error::Other(format!("宿主进程返回了无效的回复: {reply}"))),
+            Some(err) => Err(Error::Other(err.to_string())),
+        }
+    }
+
+    /// 把一条命令发给服务器, 和在控制台输入一样
+    pub fn send_command(&self, command: &str) -> Result<()> {
+        self.call(&format!("COMMAND {} {command}", self.token))
+    }
+
+    /// 告诉宿主服务器接下来的退出是要关闭或者重启
+    pub fn set_intent(&self, intent: Intent) -> Result<()> {
+        self.call(&format!("INTENT {} {}", self.token, intent.as_str()))
+    }
+
+    /// 连接控制台, 输入[`DETACH_COMMAND`]或者输入结束(Ctrl+D/Ctrl+Z)时断开连接, 服务器继续运行
+    pub fn attach(&self) -> Result<()> {
+        println!("已连接到控制台, 输入{DETACH_COMMAND}断开连接(服务器会继续运行)");
+        let mut stream = self.request(&format!("ATTACH {}", self.token))?;
+        let detached = Arc::new(AtomicBool::new(false));
+        let output = {
+            let mut stream = stream.try_clone()?;
+            let detached = detached.clone();
+            thread::spawn(move || {
+                let _ = io::copy(&mut stream, &mut io::stdout());
+                // 不是自己断开的连接说明服务器已经关闭
+                if !detached.load(Ordering::SeqCst) {
+                    println!("服务器已关闭, 按回车键返回");
+                }
+            })
+        };
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if io::stdin().read_line(&mut line)? == 0 || line.trim() == DETACH_COMMAND {
+                detached.store(true, Ordering::SeqCst);
+                println!("已断开连接, 服务器继续在后台运行");
+                break;
+            }
+            if output.is_finished() || stream.write_all(line.as_bytes()).is_err() {
+                break;
+            }
+        }
+        let _ = stream.shutdown(Shutdown::Both);
+        let _ = output.join();
+        Ok(())
+    }
+}
+
+/// 连接控制台的客户端, 输出先放进队列再由单独的线程发送, 卡住的客户端不会阻塞服务器的输出
+struct Client {
+    queue: SyncSender<String>,
+    stream: TcpStream,
+    writer: JoinHandle<()>,
+}
+
+impl Client {
+    /// 先把`scrollback`放进队列, 然后开始发送
+    fn spawn<'a>(
+        stream: TcpStream,
+        scrollback: impl IntoIterator<Item = &'a String>,
+    ) -> Result<Self> {
+        stream.set_write_timeout(Some(CLIENT_WRITE_TIMEOUT))?;
+        let (queue, lines) = mpsc::sync_channel::<String>(CLIENT_QUEUE_LINES);
+        for line in scrollback {
+            let _ = queue.try_send(line.clone());
+        }
+        let mut output = stream.try_clone()?;
+        let writer = thread::spawn(move || {
+            for line in lines {
+                if output.write_all(line.as_bytes()).is_err() {
+                    break;
+                }
+            }
+            let _ = output.shutdown(Shutdown::Both);
+        });
+        Ok(Client {
+            queue,
+            stream,
+            writer,
+        })
+    }
+
+    /// 把一行输出放进队列, 队列满了或者已经断开时返回`false`
+    fn push(&self, line: &str) -> bool {
+        match self.queue.try_send(line.to_string()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                warn!("{:?}接收输出太慢, 断开连接", self.stream.peer_addr());
+                let _ = self.stream.shutdown(Shutdown::Both);
+                false
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    }
+
+    /// 发送完队列里剩下的输出后断开连接
+    fn close(self) {
+        drop(self.queue);
+        let _ = self.writer.join();
+    }
+}
+
+/// 宿主进程和连接之间共享的状态
+#[derive(Default)]
+struct HostState {
+    scrollback: VecDeque<String>,
+    clients: Vec<Client>,
+    stdin: Option<ChildStdin>,
+    log: Option<fs::File>,
+    intent: Option<Intent>,
+    /// 服务器配置的编码, 为`None`时使用UTF-8
+    encoding: Option<&'static Encoding>,
+}
+
+impl HostState {
+    /// 记录一行输出并发给所有连接的客户端
+    fn push_line(&mut self, line: String) {
+        if let Some(log) = self.log.as_mut() {
+            if let Err(err) = log.write_all(line.as_bytes()) {
+                error!("无法写入会话日志: {err}");
+            }
+        }
+        self.clients.retain(|client| client.push(&line));
+        if self.scrollback.len() == SCROLLBACK_LINES {
+            self.scrollback.pop_front();
+        }
+        self.scrollback.push_back(line);
+    }
+
+    /// 把一条命令写入服务器的输入
+    fn send(&mut self, command: &str) -> Result<()> {
+        let stdin = self
+            .stdin
+            .as_mut()
+            .ok_or_else(|| Error::Other("服务器没有运行".into()))?;
+        let (bytes, _, _) = self.encoding.unwrap_or(UTF_8).encode(command);
+        stdin.write_all(&bytes)?;
+        stdin.write_all(b"\n")?;
+        stdin.flush()?;
+        if let Some(log) = self.log.as_mut() {
+            let _ = writeln!(log, "> {command}");
+        }
+        Ok(())
+    }
+}
+
+/// 返回服务器配置的编码, 例如`utf-8`和`gbk`, 不认识的编码使用UTF-8
+pub fn console_encoding(label: &str) -> &'static Encoding {
+    Encoding::for_label(label.trim().as_bytes()).unwrap_or_else(|| {
+        warn!("不支持的编码{label}, 使用UTF-8");
+        UTF_8
+    })
+}
+
+/// 把子进程的输出按`encoding`解码后按行转发给[`HostState`]
+fn forward_output(reader: impl Read, encoding: &'static Encoding, state: Arc<Mutex<HostState>>) {
+    let mut reader = BufReader::new(reader);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                let mut line = encoding.decode_without_bom_handling(&buf).0.into_owned();
+                if !line.ends_with('\n') {
+                    line.push('\n');
+                }
+                state.lock().unwrap().push_line(line);
+            }
+        }
+    }
+}
+
+/// 处理一个连接
+fn handle_client(stream: TcpStream, token: &str, state: Arc<Mutex<HostState>>) -> Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut stream = stream;
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    let mut parts = request.trim_end_matches(['\r', '\n']).splitn(3, ' ');
+    let (kind, client_token) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    if client_token != token {
+        writeln!(stream, "ERR 令牌无效")?;
+        return Ok(());
+    }
+    match kind {
+        "COMMAND" => {
+            let result = state.lock().unwrap().send(parts.next().unwrap_or(""));
+            match result {
+                Ok(()) => writeln!(stream, "OK")?,
+                Err(err) => writeln!(stream, "ERR {err}")?,
+            }
+        }
+        "ATTACH" => {
+            {
+                let mut state = state.lock().unwrap();
+                let client = Client::spawn(stream.try_clone()?, &state.scrollback)?;
+                state.clients.push(client);
+            }
+            trace!("attach <- {:?}", stream.peer_addr());
+            let mut line = String::new();
+            while reader.read_line(&mut line)? != 0 {
+                let command = line.trim_end_matches(['\r', '\n']);
+                if let Err(err) = state.lock().unwrap().send(command) {
+                    writeln!(stream, "{err}")?;
+                }
+                line.clear();
+            }
+            trace!("detach <- {:?}", stream.peer_addr());
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        "INTENT" => match Intent::parse(parts.next().unwrap_or("")) {
+            Some(intent) => {
+                state.lock().unwrap().intent = Some(intent);
+                writeln!(stream, "OK")?
+            }
+            None => writeln!(stream, "ERR 未知的操作")?,
+        },
+        _ => writeln!(stream, "ERR 未知的请求{kind}")?,
+    }
+    Ok(())
+}
+
+/// 启动一次服务器并等待退出
+fn run_child(
+    server: &ServerConfig,
+    state: &Arc<Mutex<HostState>>,
+    session: &mut Session,
+    server_dir: &Path,
+) -> Result<ExitStatus> {
+    let mut process = server_command(server)?;
+    process
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    trace!("shell <- {process:?}");
+    let mut child = process.spawn()?;
+    session.server_pid = child.id();
+    session.save(server_dir)?;
+    fs::write(server_dir.join(PID_FILE), child.id().to_string())?;
+    let outputs = [
+        child
+            .stdout
+            .take()
+            .map(|out| Box::new(out) as Box<dyn Read + Send>),
+        child
+            .stderr
+            .take()
+            .map(|err| Box::new(err) as Box<dyn Read + Send>),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|reader| {
+        let state = state.clone();
+        let encoding = console_encoding(&server.encoding);
+        thread::spawn(move || forward_output(reader, encoding, state))
+    })
+    .collect::<Vec<_>>();
+    state.lock().unwrap().stdin = child.stdin.take();
+    let status = child.wait();
+    state.lock().unwrap().stdin = None;
+    for output in outputs {
+        let _ = output.join();
+    }
+    let status = status?;
+    // 等待自动重启时没有服务器进程
+    session.server_pid = 0;
+    session.save(server_dir)?;
+    let _ = fs::remove_file(server_dir.join(PID_FILE));
+    state
+        .lock()
+        .unwrap()
+        .push_line(format!("[mcscs] 服务器已退出({status})\n"));
+    Ok(status)
+}
+
+/// 在当前进程里运行宿主, 启动服务器并接管它的输入输出, 服务器退出后返回
+///
+/// `policy`不为`None`时崩溃后会自动重启, 见[`supervise`]
+pub fn host(server: &ServerConfig, policy: Option<&RestartPolicy>) -> Result<()> {
+    let server_dir = Workspace::current()?.server_dir(&server.name);
+    if !server_dir.join("eula.txt").exists() {
+        return Err(Error::Eula);
+    }
+    if Session::load(&server.name)?.is_some() {
+        return Err(Error::Other(format!("服务器{}已经在运行", server.name)));
+    }
+    let listener = TcpListener::bind(("127.0.0.1", 0))?;
+    let log_dir = server_dir.join(SESSIONS_DIR);
+    fs::create_dir_all(&log_dir)?;
+    let log = log_dir.join(format!("{}.log", Local::now().format("%Y%m%d-%H%M%S")));
+    let mut session = Session {
+        host_pid: process::id(),
+        server_pid: 0,
+        port: listener.local_addr()?.port(),
+        token: rand::rng()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect(),
+        started_at: Local::now().timestamp(),
+        log: log.clone(),
+    };
+    let state = Arc::new(Mutex::new(HostState {
+        log: Some(
+            fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&log)?,
+        ),
+        encoding: Some(console_encoding(&server.encoding)),
+        ..Default::default()
+    }));
+    session.save(&server_dir)?;
+    info!("{}的控制台: 127.0.0.1:{}", server.name, session.port);
+
+    let stopped = Arc::new(AtomicBool::new(false));
+    {
+        let state = state.clone();
+        let stopped = stopped.clone();
+        let token = session.token.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                let state = state.clone();
+                let token = token.clone();
+                thread::spawn(move || {
+                    if let Err(err) = handle_client(stream, &token, state) {
+                        trace!("连接断开: {err}");
+                    }
+                });
+            }
+        });
+    }
+
+    // 按照backup.json定时备份, 服务器关闭后停止
+    backup::spawn_scheduler(&server.name, stopped.clone());
+
+    // 请求重启时在这里重新启动服务器, 不算作崩溃
+    let mut run = || -> Result<ServerExit> {
+        loop {
+            let status = run_child(server, &state, &mut session, &server_dir)?;
+            match state.lock().unwrap().intent.take() {
+                Some(Intent::Restart) => {
+                    info!("重启服务器{}", server.name);
+                }
+                intent => {
+                    return Ok(ServerExit {
+                        status,
+                        stop_requested: intent == Some(Intent::Stop),
+                    })
+                }
+            }
+        }
+    };
+    let result = match policy {
+        Some(policy) => supervise(server, policy, run, || {
+            state.lock().unwrap().intent == Some(Intent::Stop)
+        }),
+        None => run().map(drop),
+    };
+
+    // 关闭监听和所有连接
+    stopped.store(true, Ordering::SeqCst);
+    let _ = fs::remove_file(server_dir.join(SESSION_FILE));
+    let _ = fs::remove_file(server_dir.join(PID_FILE));
+    let _ = TcpStream::connect(("127.0.0.1", session.port));
+    let clients = state.lock().unwrap().clients.drain(..).collect::<Vec<_>>();
+    for client in clients {
+        client.close();
+    }
+    result
+}
+
+/// 在后台启动宿主进程(`mcscs host <name>`), 等待会话就绪后返回
+pub fn spawn_host(server: &ServerConfig, policy: Option<&RestartPolicy>) -> Result<Session> {
+    if Session::load(&server.name)?.is_some() {
+        return Err(Error::Other(format!("服务器{}已经在运行", server.name)));
+    }
+    let server_dir = Workspace::current()?.server_dir(&server.name);
+    if !server_dir.join("eula.txt").exists() {
+        return Err(Error::Eula);
+    }
+    fs::create_dir_all(server_dir.join(SESSIONS_DIR))?;
+    let host_log = server_dir.join(SESSIONS_DIR).join("host.log");
+    let mut command = process::Command::new(env::current_exe()?);
+    command.arg("--home").arg(Workspace::current()?.root());
+    command.arg("host").arg(&server.name);
+    if let Some(policy) = policy {
+        command.arg("--supervise");
+        command.arg(format!("--max-restarts={}", policy.max_restarts));
+        command.arg(format!("--restart-window={}", policy.window.as_secs()));
+    }
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(fs::File::create(&host_log)?);
+    // 不跟随当前终端退出
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const DETACHED_PROCESS: u32 = 0x00000008;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x00000200;
+        command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+    }
+    trace!("shell <- {command:?}");
+    let mut child = command.spawn()?;
+    for _ in 0..100 {
+        if let Some(session) = Session::load(&server.name)? {
+            if session.host_pid == child.id() {
+                return Ok(session);
+            }
+        }
+        if let Some(status) = child.try_wait()? {
+            let reason = fs::read_to_string(&host_log).unwrap_or_default();
+            return Err(Error::Other(format!(
+                "启动服务器{}失败({status}): {}",
+                server.name,
+                reason.trim()
+            )));
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    Err(Error::Other(format!(
+        "启动服务器{}超时, 请查看{}",
+        server.name,
+        host_log.display()
+    )))
+}
@@ -0,0 +1,355 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+//! 查询服务器状态(Server List Ping), 协议见<https://wiki.vg/Server_List_Ping>
+//!
+//! 先使用1.7及以上版本的协议, 失败时使用1.6的旧协议
+
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+use crate::properties::{Properties, PROPERTIES_FILE};
+use crate::workspace::Workspace;
+
+/// Minecraft默认的端口
+pub const DEFAULT_PORT: u16 = 25565;
+
+/// 连接和读取的超时时间
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 状态回复的最大长度
+const MAX_PACKET_LEN: i32 = 1 << 21;
+
+/// 服务器的状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerStatus {
+    /// 服务器的版本, 例如: Paper 1.20.1
+    pub version: String,
+    /// 协议版本, 旧协议获取不到时为-1
+    pub protocol: i64,
+    /// 在线玩家数量
+    pub online: u64,
+    /// 最大玩家数量
+    pub max: u64,
+    /// 部分在线玩家的名字, 服务器最多返回12个
+    pub players: Vec<String>,
+    /// 去掉格式代码后的MOTD
+    pub motd: String,
+    /// 延迟(毫秒)
+    pub latency: u64,
+}
+
+/// 写入VarInt
+///
+/// # 示例
+/// ```
+/// use mcscs::status::{read_varint, write_varint};
+/// let mut buf = Vec::new();
+/// write_varint(&mut buf, 300);
+/// assert_eq!(buf, [0xac, 0x02]);
+/// assert_eq!(read_varint(&mut buf.as_slice()).unwrap(), 300);
+/// ```
+pub fn write_varint(buf: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
+    loop {
+        if value & !0x7f == 0 {
+            buf.push(value as u8);
+            return;
+        }
+        buf.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+}
+
+/// 读取VarInt, 最多5个字节
+pub fn read_varint(reader: &mut impl Read) -> Result<i32> {
+    let mut value = 0u32;
+    for i in 0..5 {
+        let mut byte = [0u8];
+        reader.read_exact(&mut byte)?;
+        value |= ((byte[0] & 0x7f) as u32) << (7 * i);
+        if byte[0] & 0x80 == 0 {
+            return Ok(value as i32);
+        }
+    }
+    Err(Error::Status("VarInt太长".into()))
+}
+
+fn write_string(buf: &mut Vec<u8>, value: &str) {
+    write_varint(buf, value.len() as i32);
+    buf.extend_from_slice(value.as_bytes());
+}
+
+/// 加上长度前缀后发送数据包
+fn send_packet(stream: &mut TcpStream, id: i32, data: &[u8]) -> Result<()> {
+    let mut body = Vec::with_capacity(data.len() + 1);
+    write_varint(&mut body, id);
+    body.extend_from_slice(data);
+    let mut packet = Vec::with_capacity(body.len() + 5);
+    write_varint(&mut packet, body.len() as i32);
+    packet.extend_from_slice(&body);
+    stream.write_all(&packet)?;
+    Ok(())
+}
+
+/// 读取一个数据包, 返回ID和内容
+fn read_packet(stream: &mut TcpStream) -> Result<(i32, Vec<u8>)> {
+    let len = read_varint(stream)?;
+    if !(1..=MAX_PACKET_LEN).contains(&len) {
+        return Err(Error::Status(format!("无效的数据包长度{len}")));
+    }
+    let mut data = vec![0u8; len as usize];
+    stream.read_exact(&mut data)?;
+    let mut reader = data.as_slice();
+    let id = read_varint(&mut reader)?;
+    Ok((id, reader.to_vec()))
+}
+
+fn connect(host: &str, port: u16, timeout: Duration) -> Result<TcpStream> {
+    let addr = (host, port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| Error::Status(format!("无效的地址{host}")))?;
+    let stream = TcpStream::connect_timeout(&addr, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    Ok(stream)
+}
+
+/// 去掉`§`开头的格式代码
+///
+/// # 示例
+/// ```
+/// use mcscs::status::strip_formatting;
+/// assert_eq!(strip_formatting("§aHello §lWorld"), "Hello World");
+/// ```
+pub fn strip_formatting(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// 把聊天组件转换为纯文本
+///
+/// # 示例
+/// ```
+/// use serde_json::json;
+/// use mcscs::status::chat_to_text;
+/// let motd = json!({"text": "A ", "extra": [{"text": "Minecraft", "bold": true}, " Server"]});
+/// assert_eq!(chat_to_text(&motd), "A Minecraft Server");
+/// ```
+pub fn chat_to_text(component: &Value) -> String {
+    match component {
+        Value::String(text) => strip_formatting(text),
+        Value::Array(components) => components.iter().map(chat_to_text).collect(),
+        Value::Object(object) => {
+            let mut text = object
+                .get("text")
+                .or_else(|| object.get("translate"))
+                .map(chat_to_text)
+                .unwrap_or_default();
+            if let Some(extra) = object.get("extra") {
+                text.push_str(&chat_to_text(extra));
+            }
+            text
+        }
+        _ => String::new(),
+    }
+}
+
+/// 把1.7及以上版本的状态JSON转换为[`ServerStatus`]
+pub fn parse_status_json(status: &Value, latency: u64) -> ServerStatus {
+    ServerStatus {
+        version: chat_to_text(&status["version"]["name"]),
+        protocol: status["version"]["protocol"].as_i64().unwrap_or(-1),
+        online: status["players"]["online"].as_u64().unwrap_or(0),
+        max: status["players"]["max"].as_u64().unwrap_or(0),
+        players: status["players"]["sample"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|player| player["name"].as_str().map(strip_formatting))
+            .collect(),
+        motd: chat_to_text(&status["description"]),
+        latency,
+    }
+}
+
+/// 使用1.7及以上版本的协议查询状态
+pub fn ping(host: &str, port: u16, timeout: Duration) -> Result<ServerStatus> {
+    let mut stream = connect(host, port, timeout)?;
+    let start = Instant::now();
+
+    // 握手, 协议版本-1表示只查询状态, 下一个状态1是status
+    let mut handshake = Vec::new();
+    write_varint(&mut handshake, -1);
+    write_string(&mut handshake, host);
+    handshake.extend_from_slice(&port.to_be_bytes());
+    write_varint(&mut handshake, 1);
+    send_packet(&mut stream, 0x00, &handshake)?;
+    send_packet(&mut stream, 0x00, &[])?;
+
+    let (id, data) = read_packet(&mut stream)?;
+    if id != 0x00 {
+        return Err(Error::Status(format!("无效的回复ID{id}")));
+    }
+    let mut reader = data.as_slice();
+    let len = read_varint(&mut reader)?;
+    if len < 0 || len as usize > reader.len() {
+        return Err(Error::Status("无效的状态JSON长度".into()));
+    }
+    let status = serde_json::from_slice::<Value>(&reader[..len as usize])?;
+    let mut latency = start.elapsed();
+
+    // ping/pong测量延迟, 有些服务器不回复pong, 这时使用查询状态的时间
+    let payload = start.elapsed().as_millis() as i64;
+    let ping_start = Instant::now();
+    if send_packet(&mut stream, 0x01, &payload.to_be_bytes()).is_ok() {
+        if let Ok((0x01, pong)) = read_packet(&mut stream) {
+            if pong == payload.to_be_bytes() {
+                latency = ping_start.elapsed();
+            }
+        }
+    }
+    Ok(parse_status_json(&status, latency.as_millis() as u64))
+}
+
+/// 解析1.6及以下版本的回复(去掉0xFF和长度后的字符串)
+///
+/// # 示例
+/// ```
+/// use mcscs::status::parse_legacy_response;
+/// let status = parse_legacy_response("§1\0127\01.6.4\0A Minecraft Server\03\020", 5).unwrap();
+/// assert_eq!((status.online, status.max, status.protocol), (3, 20, 127));
+/// let status = parse_legacy_response("A Minecraft Server§3§20", 5).unwrap();
+/// assert_eq!(status.motd, "A Minecraft Server");
+/// ```
+pub fn parse_legacy_response(response: &str, latency: u64) -> Result<ServerStatus> {
+    let invalid = || Error::Status(format!("无效的回复: {response}"));
+    if let Some(rest) = response.strip_prefix("§1\0") {
+        // 1.4 - 1.6
+        let fields = rest.split('\0').collect::<Vec<&str>>();
+        if fields.len() != 5 {
+            return Err(invalid());
+        }
+        return Ok(ServerStatus {
+            version: fields[1].to_string(),
+            protocol: fields[0].parse().map_err(|_| invalid())?,
+            online: fields[3].parse().map_err(|_| invalid())?,
+            max: fields[4].parse().map_err(|_| invalid())?,
+            players: Vec::new(),
+            motd: strip_formatting(fields[2]),
+            latency,
+        });
+    }
+    // beta 1.8 - 1.3: MOTD§在线人数§最大人数
+    let mut fields = response.rsplitn(3, '§');
+    let max = fields.next().ok_or_else(invalid)?;
+    let online = fields.next().ok_or_else(invalid)?;
+    let motd = fields.next().ok_or_else(invalid)?;
+    Ok(ServerStatus {
+        version: String::new(),
+        protocol: -1,
+        online: online.parse().map_err(|_| invalid())?,
+        max: max.parse().map_err(|_| invalid())?,
+        players: Vec::new(),
+        motd: strip_formatting(motd),
+        latency,
+    })
+}
+
+fn write_utf16(buf: &mut Vec<u8>, value: &str) {
+    for unit in value.encode_utf16() {
+        buf.extend_from_slice(&unit.to_be_bytes());
+    }
+}
+
+/// 使用1.6的旧协议查询状态, 1.6及以下版本的服务器只支持这个协议
+pub fn legacy_ping(host: &str, port: u16, timeout: Duration) -> Result<ServerStatus> {
+    let mut stream = connect(host, port, timeout)?;
+    let start = Instant::now();
+
+    let mut request = vec![0xfe, 0x01, 0xfa];
+    let channel = "MC|PingHost";
+    request.extend_from_slice(&(channel.encode_utf16().count() as u16).to_be_bytes());
+    write_utf16(&mut request, channel);
+    let host_len = host.encode_utf16().count() as u16;
+    request.extend_from_slice(&(7 + host_len * 2).to_be_bytes());
+    request.push(73); // 1.6.1的协议版本
+    request.extend_from_slice(&host_len.to_be_bytes());
+    write_utf16(&mut request, host);
+    request.extend_from_slice(&(port as i32).to_be_bytes());
+    stream.write_all(&request)?;
+
+    let mut header = [0u8; 3];
+    stream.read_exact(&mut header)?;
+    if header[0] != 0xff {
+        return Err(Error::Status(format!("无效的回复ID{}", header[0])));
+    }
+    let len = u16::from_be_bytes([header[1], header[2]]) as usize;
+    let mut data = vec![0u8; len * 2];
+    stream.read_exact(&mut data)?;
+    let latency = start.elapsed().as_millis() as u64;
+    let units = data
+        .chunks_exact(2)
+        .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+        .collect::<Vec<u16>>();
+    parse_legacy_response(&String::from_utf16_lossy(&units), latency)
+}
+
+/// 查询服务器状态, 新协议失败时使用旧协议
+///
+/// # 示例
+/// ```no_run
+/// use mcscs::status::{query_status, DEFAULT_TIMEOUT};
+/// let status = query_status("127.0.0.1", 25565, DEFAULT_TIMEOUT).unwrap();
+/// println!("{}/{} {}", status.online, status.max, status.motd);
+/// ```
+pub fn query_status(host: &str, port: u16, timeout: Duration) -> Result<ServerStatus> {
+    match ping(host, port, timeout) {
+        Ok(status) => Ok(status),
+        Err(err) => legacy_ping(host, port, timeout).map_err(|_| err),
+    }
+}
+
+/// 从`server.properties`读取服务器监听的地址, 没有设置`server-ip`时使用`127.0.0.1`
+pub fn server_address(name: &str) -> Result<(String, u16)> {
+    let path = Workspace::current()?.server_dir(name).join(PROPERTIES_FILE);
+    let properties = Properties::load(&path)?;
+    let host = match properties.get("server-ip") {
+        None | Some("") | Some("0.0.0.0") => "127.0.0.1",
+        Some(ip) => ip,
+    };
+    let port = match properties.get("server-port") {
+        None | Some("") => DEFAULT_PORT,
+        Some(port) => port
+            .parse()
+            .map_err(|_| Error::Status(format!("无效的server-port: {port}")))?,
+    };
+    Ok((host.to_string(), port))
+}
+
+/// 查询一个服务器的状态
+pub fn server_status(name: &str, timeout: Duration) -> Result<ServerStatus> {
+    let (host, port) = server_address(name)?;
+    query_status(&host, port, timeout)
+}
@@ -0,0 +1,372 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+//! 增量备份使用的内容寻址存储
+//!
+//! 文件按[`CHUNK_SIZE`]分块, 每一块用zstd压缩后以内容的SHA1命名, 保存在`MCSCS/backups/.store/objects/<前两位>/<SHA1>`.
+//! 区域文件(`.mca`)按4KiB的扇区存储区块, 分块大小是扇区大小的整数倍, 没有变化的区块所在的块只保存一次.
+//!
+//! 一次增量备份是一个[`Snapshot`], 记录每个文件由哪些块组成, 不同的备份和服务器之间共享相同的块
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use log::{info, trace, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::fastmirror::get_sha1;
+use crate::workspace::Workspace;
+
+/// 分块大小, 是区域文件扇区(4KiB)的整数倍
+pub const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// 块的zstd压缩等级
+const ZSTD_LEVEL: i32 = 3;
+
+/// 清理时不删除最近修改过的块, 避免删除正在进行的备份刚写入的块
+const PRUNE_GRACE: Duration = Duration::from_secs(60 * 60);
+
+/// 内容寻址存储
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Store {
+    root: PathBuf,
+}
+
+impl Store {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// 当前工作目录的存储, `MCSCS/backups/.store`
+    pub fn current() -> Result<Self> {
+        Ok(Self::new(Workspace::current()?.backup_store_dir()))
+    }
+
+    fn objects_dir(&self) -> PathBuf {
+        self.root.join("objects")
+    }
+
+    fn object_path(&self, hash: &str) -> PathBuf {
+        self.objects_dir().join(&hash[..2]).join(hash)
+    }
+
+    /// 块是否存在, 存在时更新它的修改时间, 防止被正在进行的清理删除
+    pub fn touch(&self, hash: &str) -> bool {
+        let path = self.object_path(hash);
+        match File::options().append(true).open(&path) {
+            Ok(file) => {
+                let _ = file.set_modified(SystemTime::now());
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// 保存一个块, 返回它的SHA1, 已经存在时不会重复保存
+    ///
+    /// 第二个返回值是新写入的字节数(压缩后)
+    pub fn put(&self, data: &[u8]) -> Result<(String, u64)> {
+        let hash = get_sha1(data);
+        if self.touch(&hash) {
+            return Ok((hash, 0));
+        }
+        let path = self.object_path(&hash);
+        fs::create_dir_all(path.parent().unwrap())?;
+        let compressed = zstd::encode_all(data, ZSTD_LEVEL)?;
+        // 先写入临时文件, 不会留下不完整的块
+        let part = path.with_extension("part");
+        fs::write(&part, &compressed)?;
+        fs::rename(&part, &path)?;
+        trace!("store <- {hash} ({}字节)", compressed.len());
+        Ok((hash, compressed.len() as u64))
+    }
+
+    /// 读取一个块并检查它的SHA1
+    pub fn get(&self, hash: &str) -> Result<Vec<u8>> {
+        let path = self.object_path(hash);
+        let file = File::open(&path).map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => Error::Backup(format!("缺少块{hash}")),
+            _ => Error::Io(err),
+        })?;
+        let data = zstd::decode_all(file)
+            .map_err(|err| Error::Backup(format!("块{hash}已损坏: {err}")))?;
+        let actual = get_sha1(&data);
+        if actual != hash {
+            return Err(Error::Checksum {
+                expected: hash.to_string(),
+                actual,
+            });
+        }
+        Ok(data)
+    }
+
+    /// 删除不在`referenced`里的块, 返回删除的块数量和字节数
+    pub fn prune(&self, referenced: &HashSet<String>) -> Result<(usize, u64)> {
+        let dir = self.objects_dir();
+        if !dir.exists() {
+            return Ok((0, 0));
+        }
+        let (mut count, mut bytes) = (0, 0);
+        for prefix in fs::read_dir(dir)? {
+            for entry in fs::read_dir(prefix?.path())? {
+                let entry = entry?;
+                let hash = entry.file_name().to_string_lossy().into_owned();
+                if referenced.contains(&hash) {
+                    continue;
+                }
+                let meta = entry.metadata()?;
+                let recent = meta
+                    .modified()
+                    .ok()
+                    .and_then(|modified| modified.elapsed().ok())
+                    .is_none_or(|elapsed| elapsed < PRUNE_GRACE);
+                if recent {
+                    continue;
+                }
+                fs::remove_file(entry.path())?;
+                count += 1;
+                bytes += meta.len();
+            }
+        }
+        info!("从{}删除了{count}个没有使用的块", self.root.display());
+        Ok((count, bytes))
+    }
+}
+
+/// 增量备份里的一个文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotFile {
+    /// 使用`/`分隔的相对路径
+    pub path: String,
+    pub size: u64,
+    /// 修改时间(Unix时间戳, 纳秒), 和上一次备份相同时不重新读取文件
+    pub modified: u64,
+    /// Unix权限
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+    /// 组成文件的块的SHA1
+    pub chunks: Vec<String>,
+}
+
+/// 一次增量备份, 保存为`MCSCS/backups/<name>/<ID>.snapshot.json`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// 使用`/`分隔的相对路径, 用于保留空文件夹
+    pub dirs: Vec<String>,
+    pub files: Vec<SnapshotFile>,
+    /// 这次备份新写入存储的字节数(压缩后)
+    #[serde(default)]
+    pub added: u64,
+}
+
+fn modified_nanos(meta: &fs::Metadata) -> u64 {
+    meta.modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |modified| modified.as_nanos() as u64)
+}
+
+#[cfg(unix)]
+fn file_mode(meta: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(meta.permissions().mode())
+}
+
+#[cfg(not(unix))]
+fn file_mode(_meta: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+impl Snapshot {
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// 保存清单, 先写入临时文件, 不会留下不完整的清单
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let part = path.with_extension("part");
+        fs::write(&part, serde_json::to_vec(self)?)?;
+        fs::rename(&part, path)?;
+        Ok(())
+    }
+
+    /// 文件的总大小
+    pub fn size(&self) -> u64 {
+        self.files.iter().map(|file| file.size).sum()
+    }
+
+    /// 用到的所有块
+    pub fn chunks(&self) -> impl Iterator<Item = &String> {
+        self.files.iter().flat_map(|file| &file.chunks)
+    }
+
+    /// 把`base`里的文件夹`dirs`保存到存储, `exclude`返回`true`的文件会被跳过
+    ///
+    /// 大小和修改时间与`previous`里相同, 并且块都还在存储里的文件直接使用上一次的块
+    pub fn create(
+        store: &Store,
+        base: &Path,
+        dirs: &[String],
+        previous: Option<&Snapshot>,
+        exclude: &dyn Fn(&str) -> bool,
+    ) -> Result<Self> {
+        let previous = previous
+            .map(|snapshot| {
+                snapshot
+                    .files
+                    .iter()
+                    .map(|file| (file.path.as_str(), file))
+                    .collect::<BTreeMap<_, _>>()
+            })
+            .unwrap_or_default();
+        let mut snapshot = Snapshot::default();
+        let mut pending = dirs.to_vec();
+        while let Some(dir) = pending.pop() {
+            snapshot.dirs.push(dir.clone());
+            for entry in fs::read_dir(base.join(&dir))? {
+                let entry = entry?;
+                let path = format!("{dir}/{}", entry.file_name().to_string_lossy());
+                if exclude(&path) {
+                    continue;
+                }
+                let file_type = entry.file_type()?;
+                if file_type.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+                if !file_type.is_file() {
+                    warn!("跳过不是普通文件的{}", entry.path().display());
+                    continue;
+                }
+                let meta = entry.metadata()?;
+                let (size, modified) = (meta.len(), modified_nanos(&meta));
+                let unchanged = previous.get(path.as_str()).filter(|file| {
+                    file.size == size
+                        && file.modified == modified
+                        && file.chunks.iter().all(|hash| store.touch(hash))
+                });
+                let chunks = match unchanged {
+                    Some(file) => file.chunks.clone(),
+                    None => {
+                        let mut chunks = Vec::new();
+                        let mut reader = File::open(entry.path())?;
+                        let mut buffer = vec![0; CHUNK_SIZE];
+                        loop {
+                            let len = read_full(&mut reader, &mut buffer)?;
+                            if len == 0 {
+                                break;
+                            }
+                            let (hash, added) = store.put(&buffer[..len])?;
+                            snapshot.added += added;
+                            chunks.push(hash);
+                        }
+                        chunks
+                    }
+                };
+                snapshot.files.push(SnapshotFile {
+                    path,
+                    size,
+                    modified,
+                    mode: file_mode(&meta),
+                    chunks,
+                });
+            }
+        }
+        snapshot.dirs.sort();
+        snapshot.files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(snapshot)
+    }
+
+    /// 把备份的文件还原到`dest`
+    pub fn checkout(&self, store: &Store, dest: &Path) -> Result<()> {
+        for dir in &self.dirs {
+            fs::create_dir_all(dest.join(safe_path(dir)?))?;
+        }
+        for file in &self.files {
+            let path = dest.join(safe_path(&file.path)?);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut output = File::create(&path)?;
+            for hash in &file.chunks {
+                output.write_all(&store.get(hash)?)?;
+            }
+            #[cfg(unix)]
+            if let Some(mode) = file.mode {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(&path, fs::Permissions::from_mode(mode))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 检查用到的块都存在并且没有损坏, 返回发现的问题
+    pub fn verify(&self, store: &Store) -> Vec<String> {
+        let mut problems = Vec::new();
+        // 多个文件共用的块只检查一次
+        let mut verified = HashMap::new();
+        for file in &self.files {
+            let mut size = 0;
+            for hash in &file.chunks {
+                if let Some(len) = verified.get(hash) {
+                    size += len;
+                    continue;
+                }
+                match store.get(hash) {
+                    Ok(data) => {
+                        verified.insert(hash, data.len() as u64);
+                        size += data.len() as u64;
+                    }
+                    Err(err) => {
+                        problems.push(format!("{}: {err}", file.path));
+                        size = file.size;
+                        break;
+                    }
+                }
+            }
+            if size != file.size {
+                problems.push(format!(
+                    "{}: 大小应为{}字节, 实际为{size}字节",
+                    file.path, file.size
+                ));
+            }
+        }
+        problems
+    }
+}
+
+/// 读满`buffer`, 返回读取的字节数, 文件结束时可能小于`buffer`的长度
+fn read_full(reader: &mut impl Read, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buffer.len() {
+        match reader.read(&mut buffer[len..])? {
+            0 => break,
+            read => len += read,
+        }
+    }
+    Ok(len)
+}
+
+/// 检查清单里的路径不会写到目标文件夹外面
+fn safe_path(path: &str) -> Result<&Path> {
+    let relative = Path::new(path);
+    if relative.is_absolute()
+        || relative
+            .components()
+            .any(|component| !matches!(component, std::path::Component::Normal(_)))
+    {
+        return Err(Error::Backup(format!("不安全的路径: {path}")));
+    }
+    Ok(relative)
+}
@@ -0,0 +1,244 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::{
+    fs,
+    path::Path,
+    process::ExitStatus,
+    thread,
+    time::{Duration, Instant},
+};
+
+use chrono::Local;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::server::ServerConfig;
+use crate::workspace::Workspace;
+
+/// 重启记录的文件名, 保存在服务器文件夹里
+pub const HISTORY_FILE: &str = "restart_history.json";
+
+/// 等待重启时检查是否要求关闭的间隔
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 最多保留的重启记录数量
+const MAX_HISTORY: usize = 100;
+
+/// 自动重启的策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestartPolicy {
+    /// 在`window`时间内最多重启的次数, 超过后不再重启
+    pub max_restarts: u32,
+    /// 统计重启次数的时间窗口
+    pub window: Duration,
+    /// 第一次崩溃后等待的时间, 之后每次崩溃翻倍
+    pub initial_backoff: Duration,
+    /// 最长的等待时间
+    pub max_backoff: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: 5,
+            window: Duration::from_secs(10 * 60),
+            initial_backoff: Duration::from_secs(5),
+            max_backoff: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+/// 服务器退出的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitKind {
+    /// 正常关闭, 例如在控制台输入了`stop`
+    Clean,
+    /// 崩溃或者被杀死
+    Crash,
+}
+
+impl ExitKind {
+    /// 退出码为0时是正常关闭, 其他情况(包括被信号杀死)都是崩溃
+    pub fn from_status(status: &ExitStatus) -> Self {
+        if status.success() {
+            ExitKind::Clean
+        } else {
+            ExitKind::Crash
+        }
+    }
+}
+
+/// 服务器的一次退出
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerExit {
+    pub status: ExitStatus,
+    /// 是否是通过`mcscs stop`/`mcscs kill`关闭的, 这种情况不管退出码是什么都不会重启
+    pub stop_requested: bool,
+}
+
+impl ServerExit {
+    pub fn kind(&self) -> ExitKind {
+        if self.stop_requested {
+            ExitKind::Clean
+        } else {
+            ExitKind::from_status(&self.status)
+        }
+    }
+}
+
+/// 一次运行的记录, 时间都是Unix时间戳(秒)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestartRecord {
+    pub started_at: i64,
+    pub exited_at: i64,
+    /// 被信号杀死时没有退出码
+    pub exit_code: Option<i32>,
+    pub kind: ExitKind,
+    /// 退出后是否自动重启
+    pub restarted: bool,
+}
+
+/// 服务器的重启记录, 保存在`servers/<name>/restart_history.json`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestartHistory {
+    pub records: Vec<RestartRecord>,
+}
+
+impl RestartHistory {
+    /// 读取重启记录, 文件不存在时返回空的记录
+    pub fn load(server_dir: &Path) -> Result<Self> {
+        let path = server_dir.join(HISTORY_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// 保存重启记录, 只保留最近的记录
+    pub fn save(&mut self, server_dir: &Path) -> Result<()> {
+        if self.records.len() > MAX_HISTORY {
+            self.records.drain(..self.records.len() - MAX_HISTORY);
+        }
+        let file = fs::File::create(server_dir.join(HISTORY_FILE))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    /// `now`之前`window`时间内的崩溃次数
+    pub fn recent_crashes(&self, window: Duration, now: i64) -> u32 {
+        let since = now.saturating_sub(window.as_secs() as i64);
+        self.records
+            .iter()
+            .filter(|record| record.kind == ExitKind::Crash && record.exited_at >= since)
+            .count() as u32
+    }
+}
+
+/// 时间窗口内第`crashes`次崩溃后是否应该重启, 已经重启了`max_restarts`次就不再重启
+///
+/// # 示例
+/// ```
+/// use mcscs::supervisor::{should_restart, RestartPolicy};
+/// let policy = RestartPolicy::default();
+/// assert!(should_restart(&policy, 5));
+/// assert!(!should_restart(&policy, 6));
+/// ```
+pub fn should_restart(policy: &RestartPolicy, crashes: u32) -> bool {
+    crashes <= policy.max_restarts
+}
+
+/// 第`crashes`次崩溃后等待的时间, 从`initial_backoff`开始翻倍, 不超过`max_backoff`
+///
+/// # 示例
+/// ```
+/// use std::time::Duration;
+/// use mcscs::supervisor::{backoff, RestartPolicy};
+/// let policy = RestartPolicy::default();
+/// assert_eq!(backoff(&policy, 1), Duration::from_secs(5));
+/// assert_eq!(backoff(&policy, 3), Duration::from_secs(20));
+/// ```
+pub fn backoff(policy: &RestartPolicy, crashes: u32) -> Duration {
+    let factor = 2u32.saturating_pow(crashes.saturating_sub(1));
+    policy
+        .initial_backoff
+        .saturating_mul(factor)
+        .min(policy.max_backoff)
+}
+
+/// 由`run`启动服务器并等待退出, 崩溃后按`policy`自动重启, 正常关闭时返回
+///
+/// 每次退出都会记录到服务器文件夹的`restart_history.json`.
+/// 等待重启时`stop_requested`返回`true`(例如执行了`mcscs stop`)则不再重启, 直接返回
+pub fn supervise(
+    server: &ServerConfig,
+    policy: &RestartPolicy,
+    mut run: impl FnMut() -> Result<ServerExit>,
+    stop_requested: impl Fn() -> bool,
+) -> Result<()> {
+    let server_dir = Workspace::current()?.server_dir(&server.name);
+    if !server_dir.join("eula.txt").exists() {
+        return Err(Error::Eula);
+    }
+    let mut history = RestartHistory::load(&server_dir).unwrap_or_else(|err| {
+        warn!("无法读取{}的重启记录, 将重新记录: {err}", server.name);
+        RestartHistory::default()
+    });
+    loop {
+        let started_at = Local::now().timestamp();
+        let exit = run()?;
+        let status = exit.status;
+        let exited_at = Local::now().timestamp();
+        let kind = exit.kind();
+        let mut record = RestartRecord {
+            started_at,
+            exited_at,
+            exit_code: status.code(),
+            kind,
+            restarted: false,
+        };
+        if kind == ExitKind::Clean {
+            info!("服务器{}已正常关闭", server.name);
+            history.records.push(record);
+            return history.save(&server_dir);
+        }
+        let crashes = history.recent_crashes(policy.window, exited_at) + 1;
+        let restart = should_restart(policy, crashes);
+        record.restarted = restart;
+        history.records.push(record);
+        history.save(&server_dir)?;
+        if !restart {
+            error!("服务器{}崩溃次数过多, 不再重启", server.name);
+            return Err(Error::Other(format!(
+                "服务器{}在{}秒内崩溃了{crashes}次, 已停止自动重启",
+                server.name,
+                policy.window.as_secs()
+            )));
+        }
+        let wait = backoff(policy, crashes);
+        warn!(
+            "服务器{}崩溃({status}), {}秒后重启",
+            server.name,
+            wait.as_secs()
+        );
+        let deadline = Instant::now() + wait;
+        loop {
+            if stop_requested() {
+                info!("服务器{}已取消自动重启", server.name);
+                return Ok(());
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            thread::sleep(STOP_POLL_INTERVAL.min(deadline - now));
+        }
+    }
+}
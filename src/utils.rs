@@ -7,24 +7,28 @@
  */
 use std::io;
 use std::io::Write;
+use std::{fs, path::Path};
 
 use console::Term;
 use dialoguer::theme::ColorfulTheme;
 use log::error;
 
+use crate::error::Result;
+
 /// 返回输入的内容
 ///
 /// # 使用
 /// ```
 /// use mcscs::utils::input;
-/// let input_value = input("请输入任意内容");
+/// let input_value = input("请输入任意内容").unwrap();
 /// println!("你输入了: {input_value}")
 /// ```
-pub fn input(description: &str) -> String {
-    dialoguer::Input::<String>::with_theme(&ColorfulTheme::default())
-        .with_prompt(description)
-        .interact_text()
-        .unwrap()
+pub fn input(description: &str) -> Result<String> {
+    Ok(
+        dialoguer::Input::<String>::with_theme(&ColorfulTheme::default())
+            .with_prompt(description)
+            .interact_text()?,
+    )
 }
 
 /// 清空控制台, 类似运行Windows系统上的cls/类Unix系统上的clear命令
@@ -44,3 +48,50 @@ pub fn pause() {
         error!("{e}");
     }
 }
+
+/// 复制整个文件夹, `to`不存在时会创建
+pub fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        copy_entry(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+/// 复制一个文件或者文件夹, 符号链接会复制成指向同一个位置的符号链接
+/// (例如几个服务器共用的插件文件夹), 不会复制它指向的文件
+pub fn copy_entry(from: &Path, to: &Path) -> Result<()> {
+    let file_type = fs::symlink_metadata(from)?.file_type();
+    if file_type.is_symlink() {
+        let target = fs::read_link(from)?;
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&target, to)?;
+        #[cfg(windows)]
+        if fs::metadata(from).is_ok_and(|metadata| metadata.is_dir()) {
+            std::os::windows::fs::symlink_dir(&target, to)?;
+        } else {
+            std::os::windows::fs::symlink_file(&target, to)?;
+        }
+    } else if file_type.is_dir() {
+        copy_dir(from, to)?;
+    } else {
+        fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+/// 移动文件夹, 不在同一个分区时先复制再删除原来的文件夹
+pub fn move_dir(from: &Path, to: &Path) -> Result<()> {
+    match fs::rename(from, to) {
+        Ok(()) => return Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {}
+        Err(err) => return Err(err.into()),
+    }
+    if let Err(err) = copy_dir(from, to) {
+        let _ = fs::remove_dir_all(to);
+        return Err(err);
+    }
+    fs::remove_dir_all(from)?;
+    Ok(())
+}
@@ -0,0 +1,159 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+use lazy_static::lazy_static;
+use log::trace;
+use serde_json::Value;
+
+use crate::error::Result;
+
+/// 指定工作目录的环境变量
+pub const HOME_ENV: &str = "MCSCS_HOME";
+
+lazy_static! {
+    static ref CURRENT: RwLock<Option<Workspace>> = RwLock::new(None);
+}
+
+/// MCSCS的工作目录, 所有模块都从这里获取文件夹路径
+///
+/// 工作目录按以下顺序决定:
+/// 1. `--home`参数
+/// 2. `MCSCS_HOME`环境变量
+/// 3. 配置文件(`$XDG_CONFIG_HOME/mcscs/config.json`或`%APPDATA%\mcscs\config.json`)里的`home`
+/// 4. 当前目录下的`MCSCS`文件夹
+///
+/// # 示例
+/// ```
+/// use mcscs::workspace::Workspace;
+/// let workspace = Workspace::new("/tmp/MCSCS");
+/// println!("{}", workspace.server_dir("server_name").display());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Workspace {
+    root: PathBuf,
+}
+
+impl Workspace {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// 按优先级决定工作目录, `home`为`--home`参数
+    pub fn resolve(home: Option<&Path>) -> Result<Self> {
+        if let Some(home) = home {
+            return Ok(Self::new(home));
+        }
+        if let Some(home) = env::var_os(HOME_ENV).filter(|home| !home.is_empty()) {
+            return Ok(Self::new(home));
+        }
+        if let Some(home) = Self::home_from_config_file()? {
+            return Ok(Self::new(home));
+        }
+        Ok(Self::new(env::current_dir()?.join("MCSCS")))
+    }
+
+    /// 返回配置文件的路径
+    pub fn config_file() -> Option<PathBuf> {
+        #[cfg(target_os = "windows")]
+        let config_dir = env::var_os("APPDATA").map(PathBuf::from);
+        #[cfg(not(target_os = "windows"))]
+        let config_dir = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+        config_dir.map(|dir| dir.join("mcscs").join("config.json"))
+    }
+
+    /// 从配置文件读取`home`
+    fn home_from_config_file() -> Result<Option<PathBuf>> {
+        let path = match Self::config_file() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(None),
+        };
+        let config = serde_json::from_str::<Value>(&fs::read_to_string(&path)?)?;
+        trace!("{} -> {config}", path.display());
+        Ok(config["home"].as_str().map(PathBuf::from))
+    }
+
+    /// 返回当前使用的工作目录, 如果还没有设置则按[`Workspace::resolve`]决定
+    ///
+    /// 无法决定工作目录时(例如配置文件无效)返回错误, 不会换成其他文件夹继续运行
+    pub fn current() -> Result<Self> {
+        if let Some(workspace) = CURRENT.read().unwrap().as_ref() {
+            return Ok(workspace.clone());
+        }
+        let workspace = Self::resolve(None)?;
+        Ok(CURRENT.write().unwrap().get_or_insert(workspace).clone())
+    }
+
+    /// 设置当前使用的工作目录
+    pub fn set_current(workspace: Self) {
+        trace!("workspace <- {}", workspace.root.display());
+        *CURRENT.write().unwrap() = Some(workspace);
+    }
+
+    /// 工作目录
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// `MCSCS/servers`
+    pub fn servers_dir(&self) -> PathBuf {
+        self.root.join("servers")
+    }
+
+    /// `MCSCS/servers/<name>`
+    pub fn server_dir(&self, name: &str) -> PathBuf {
+        self.servers_dir().join(name)
+    }
+
+    /// `MCSCS/configs`
+    pub fn configs_dir(&self) -> PathBuf {
+        self.root.join("configs")
+    }
+
+    /// `MCSCS/backups`
+    pub fn backups_dir(&self) -> PathBuf {
+        self.root.join("backups")
+    }
+
+    /// `MCSCS/backups/.store`, 增量备份的块
+    pub fn backup_store_dir(&self) -> PathBuf {
+        self.backups_dir().join(".store")
+    }
+
+    /// `MCSCS/backups/<name>`
+    pub fn backup_dir(&self, name: &str) -> PathBuf {
+        self.backups_dir().join(name)
+    }
+
+    /// `MCSCS/logs`
+    pub fn logs_dir(&self) -> PathBuf {
+        self.root.join("logs")
+    }
+
+    /// `MCSCS/downloads`
+    pub fn downloads_dir(&self) -> PathBuf {
+        self.root.join("downloads")
+    }
+
+    /// `MCSCS/temp`, 导入服务器时解压的临时文件
+    pub fn temp_dir(&self) -> PathBuf {
+        self.root.join("temp")
+    }
+
+    /// `MCSCS/aria2c`
+    pub fn aria2c_dir(&self) -> PathBuf {
+        self.root.join("aria2c")
+    }
+}
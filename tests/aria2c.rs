@@ -23,7 +23,7 @@ async fn test_get_aria2c_version() -> Result<(), Box<dyn Error>> {
 
 #[tokio::test]
 async fn test_install_aria2c() {
-    install_aria2c().await;
+    install_aria2c().await.expect("install_aria2c()");
 }
 
 #[test]
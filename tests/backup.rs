@@ -0,0 +1,184 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+#![cfg(unix)]
+
+mod common;
+
+use std::{fs, path::PathBuf, thread, time::Duration};
+
+use chrono::NaiveDateTime;
+use common::sh_server;
+use mcscs::{
+    archive::extract,
+    backup::{
+        create_backup, expired_backups, list_backups, prune_backups, world_dirs, Backup,
+        BackupFormat, BackupSettings, Retention, ID_FORMAT,
+    },
+    server::{save_servers_lists, ServerConfig},
+    session::{host, Session},
+    workspace::Workspace,
+};
+
+/// 用sh代替Java的"服务器", 收到save-all flush时输出保存完成, 收到stop时退出
+fn echo_server(name: &str) -> ServerConfig {
+    sh_server(
+        name,
+        r#"while read l; do echo "got $l"; [ "$l" = "save-all flush" ] && echo "Saved the game"; [ "$l" = stop ] && exit 0; done"#,
+    )
+}
+
+fn backup(id: &str) -> Backup {
+    Backup {
+        id: id.to_string(),
+        created: NaiveDateTime::parse_from_str(id, ID_FORMAT).unwrap(),
+        format: BackupFormat::TarZst,
+        path: PathBuf::from(format!("{id}.tar.zst")),
+        size: 0,
+    }
+}
+
+/// 测试按照每小时, 每天, 每周的保留规则选择要删除的备份
+#[test]
+fn test_expired_backups() {
+    let backups = [
+        "20240314-120000",
+        "20240314-113000",
+        "20240314-110000",
+        "20240313-230000",
+        "20240313-080000",
+        "20240312-230000",
+        "20240305-230000",
+        "20240227-230000",
+    ]
+    .map(backup);
+    let expired = |retention| {
+        expired_backups(&backups, &retention)
+            .into_iter()
+            .map(|backup| backup.id.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        expired(Retention {
+            hourly: 2,
+            daily: 3,
+            weekly: 2
+        }),
+        ["20240314-110000", "20240313-080000", "20240227-230000"]
+    );
+    // 最新的备份总是保留
+    assert_eq!(
+        expired(Retention {
+            hourly: 0,
+            daily: 0,
+            weekly: 0
+        })
+        .len(),
+        backups.len() - 1
+    );
+}
+
+/// 测试备份世界文件夹, 运行时暂停自动保存, 自动备份和删除旧的备份
+#[test]
+fn test_backup() {
+    let dir = tempfile::tempdir().expect("test_backup()");
+    let workspace = Workspace::new(dir.path());
+    Workspace::set_current(workspace.clone());
+    fs::create_dir_all(workspace.configs_dir()).unwrap();
+    let server = echo_server("survival");
+    let server_dir = workspace.server_dir("survival");
+    fs::create_dir_all(server_dir.join("lobby/region")).unwrap();
+    save_servers_lists("survival", &server).unwrap();
+    fs::create_dir_all(server_dir.join("lobby_nether")).unwrap();
+    fs::create_dir_all(server_dir.join("world")).unwrap();
+    fs::write(server_dir.join("server.properties"), "level-name=lobby\n").unwrap();
+    fs::write(server_dir.join("eula.txt"), "eula=true").unwrap();
+    fs::write(server_dir.join("lobby/level.dat"), "level").unwrap();
+    fs::write(server_dir.join("lobby/region/r.0.0.mca"), "region").unwrap();
+    fs::write(server_dir.join("lobby/session.lock"), "lock").unwrap();
+    assert_eq!(
+        world_dirs(&server_dir).expect("test_backup()"),
+        ["lobby", "lobby_nether"]
+    );
+
+    // 服务器没有运行时直接备份
+    let backup = create_backup("survival", BackupFormat::Zip).expect("test_backup()");
+    assert_eq!(
+        backup.path.parent(),
+        Some(&*workspace.backup_dir("survival"))
+    );
+    let backups = list_backups("survival").unwrap();
+    assert_eq!(backups.len(), 1);
+    assert_eq!(backups[0], backup);
+    let extracted = dir.path().join("extracted");
+    extract(&backup.path, &extracted).unwrap();
+    assert_eq!(
+        fs::read_to_string(extracted.join("lobby/region/r.0.0.mca")).unwrap(),
+        "region"
+    );
+    assert!(extracted.join("lobby_nether").is_dir());
+    assert!(!extracted.join("lobby/session.lock").exists());
+    assert!(!extracted.join("world").exists());
+
+    // 旧的备份按照保留规则删除, 不是备份的文件不受影响
+    let backup_dir = workspace.backup_dir("survival");
+    for old in [
+        "20200101-000000.tar.zst",
+        "20200101-000001.zip",
+        "notes.txt",
+    ] {
+        fs::write(backup_dir.join(old), "old").unwrap();
+    }
+    assert_eq!(list_backups("survival").unwrap().len(), 3);
+    let removed = prune_backups("survival", &Retention::default()).unwrap();
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].id, "20200101-000000");
+    assert!(backup_dir.join("notes.txt").exists());
+
+    // 服务器运行时暂停自动保存, 保存完成后再备份
+    let handle = thread::spawn(move || host(&server, None));
+    let session = loop {
+        if let Some(session) = Session::load("survival").unwrap() {
+            if session.server_pid != 0 {
+                break session;
+            }
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    thread::sleep(Duration::from_secs(1));
+    let running = create_backup("survival", BackupFormat::TarZst).expect("test_backup()");
+    let log = loop {
+        let log = fs::read_to_string(&session.log).unwrap();
+        if log.contains("got save-on\n") {
+            break log;
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    let save_off = log.find("got save-off\n").unwrap();
+    let save_all = log.find("Saved the game\n").unwrap();
+    let save_on = log.find("got save-on\n").unwrap();
+    assert!(save_off < save_all && save_all < save_on);
+
+    // 自动备份会生成新的备份, 并删除同一个小时里旧的备份
+    let settings = BackupSettings {
+        interval: Some(1),
+        ..Default::default()
+    };
+    settings.save("survival").unwrap();
+    assert_eq!(BackupSettings::load("survival").unwrap(), settings);
+    loop {
+        let backups = list_backups("survival").unwrap();
+        if backups.iter().all(|backup| backup.id != running.id) {
+            assert!(backups[0].created > running.created);
+            break;
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+    session.send_command("stop").unwrap();
+    handle.join().unwrap().expect("test_backup()");
+}
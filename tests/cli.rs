@@ -0,0 +1,240 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use clap::Parser;
+use mcscs::{
+    backup::BackupFormat,
+    cli::{BackupAction, Cli, Command, ConfigAction, PropertyAction},
+    output::OutputFormat,
+    pages::config::{get_config_value, set_config_value},
+    provider::ProviderKind,
+    server::ServerConfig,
+};
+
+const VALID_CONFIG: &str = r#"{
+    "schema_version": 1,
+    "name": "test",
+    "java": {"path": "/usr/bin/java", "version": "17.0.1"},
+    "jvm_args": ["-Dlog4j2.formatMsgNoLookups=true"],
+    "encoding": "utf-8",
+    "Xms": 1073741824,
+    "Xmx": 2147483648,
+    "info": {"core": "Paper", "mc_version": "1.20.1", "build_version": "build100"},
+    "server_args": ["--nogui"]
+}"#;
+
+/// 测试解析子命令
+#[test]
+fn test_parse_cli() {
+    let cli = Cli::try_parse_from([
+        "mcscs",
+        "--home",
+        "/tmp/MCSCS",
+        "create",
+        "--name",
+        "test",
+        "--core",
+        "Paper",
+        "--mc-version",
+        "1.20.1",
+        "--build",
+        "build100",
+        "--xms",
+        "1G",
+        "--xmx",
+        "2G",
+    ])
+    .expect("test_parse_cli()");
+    assert_eq!(cli.home.unwrap().to_str(), Some("/tmp/MCSCS"));
+    match cli.command {
+        Some(Command::Create(args)) => {
+            assert_eq!(args.name, "test");
+            assert_eq!(args.encoding, "utf-8");
+            assert_eq!(args.server_args, ["--nogui"]);
+            assert!(!args.accept_eula);
+            assert_eq!(args.provider, ProviderKind::FastMirror);
+        }
+        other => panic!("test_parse_cli(): {other:?}"),
+    }
+
+    let cli = Cli::try_parse_from(["mcscs", "cores", "--provider", "mojang", "--snapshots"])
+        .expect("test_parse_cli()");
+    match cli.command {
+        Some(Command::Cores {
+            provider,
+            snapshots,
+        }) => {
+            assert_eq!(provider, ProviderKind::Mojang);
+            assert!(snapshots);
+        }
+        other => panic!("test_parse_cli(): {other:?}"),
+    }
+
+    let cli = Cli::try_parse_from(["mcscs", "config", "test", "set", "jvm_args", "-Xss1M"])
+        .expect("test_parse_cli()");
+    match cli.command {
+        Some(Command::Config {
+            name,
+            action: ConfigAction::Set { key, value },
+        }) => {
+            assert_eq!(name, "test");
+            assert_eq!(key, "jvm_args");
+            assert_eq!(value, "-Xss1M");
+        }
+        other => panic!("test_parse_cli(): {other:?}"),
+    }
+
+    let cli = Cli::try_parse_from(["mcscs", "config", "test", "property", "set", "motd", "Hi"])
+        .expect("test_parse_cli()");
+    match cli.command {
+        Some(Command::Config {
+            action:
+                ConfigAction::Property {
+                    action: PropertyAction::Set { key, value },
+                },
+            ..
+        }) => assert_eq!((key.as_str(), value.as_str()), ("motd", "Hi")),
+        other => panic!("test_parse_cli(): {other:?}"),
+    }
+
+    let cli = Cli::try_parse_from(["mcscs", "backup", "test"]).expect("test_parse_cli()");
+    assert!(matches!(
+        cli.command,
+        Some(Command::Backup { action: None, .. })
+    ));
+    let cli = Cli::try_parse_from([
+        "mcscs",
+        "backup",
+        "test",
+        "settings",
+        "--format",
+        "incremental",
+        "--keep-daily",
+        "14",
+        "--interval",
+        "3600",
+    ])
+    .expect("test_parse_cli()");
+    match cli.command {
+        Some(Command::Backup {
+            action:
+                Some(BackupAction::Settings {
+                    format,
+                    keep_hourly,
+                    keep_daily,
+                    interval,
+                    ..
+                }),
+            ..
+        }) => {
+            assert_eq!(format, Some(BackupFormat::Incremental));
+            assert_eq!(keep_hourly, None);
+            assert_eq!(keep_daily, Some(14));
+            assert_eq!(interval, Some(3600));
+        }
+        other => panic!("test_parse_cli(): {other:?}"),
+    }
+
+    let cli = Cli::try_parse_from(["mcscs", "backup", "test", "verify", "20240314-120000"])
+        .expect("test_parse_cli()");
+    match cli.command {
+        Some(Command::Backup {
+            action: Some(BackupAction::Verify { id }),
+            ..
+        }) => assert_eq!(id.as_deref(), Some("20240314-120000")),
+        other => panic!("test_parse_cli(): {other:?}"),
+    }
+
+    let cli = Cli::try_parse_from(["mcscs", "outdated", "--exit-code", "--output", "json"])
+        .expect("test_parse_cli()");
+    assert!(matches!(
+        cli.command,
+        Some(Command::Outdated { exit_code: true })
+    ));
+    assert_eq!(cli.output, OutputFormat::Json);
+
+    let cli = Cli::try_parse_from(["mcscs", "update", "test", "--mc-version", "1.20.4", "--yes"])
+        .expect("test_parse_cli()");
+    match cli.command {
+        Some(Command::Update {
+            build,
+            mc_version,
+            rollback,
+            yes,
+            ..
+        }) => {
+            assert_eq!(build, None);
+            assert_eq!(mc_version.as_deref(), Some("1.20.4"));
+            assert!(!rollback && yes);
+        }
+        other => panic!("test_parse_cli(): {other:?}"),
+    }
+    assert!(
+        Cli::try_parse_from(["mcscs", "update", "test", "--rollback", "--build", "b1"]).is_err()
+    );
+
+    let cli =
+        Cli::try_parse_from(["mcscs", "rename", "survival", "lobby"]).expect("test_parse_cli()");
+    match cli.command {
+        Some(Command::Rename { name, new_name }) => {
+            assert_eq!((name.as_str(), new_name.as_str()), ("survival", "lobby"))
+        }
+        other => panic!("test_parse_cli(): {other:?}"),
+    }
+
+    let cli = Cli::try_parse_from(["mcscs", "clone", "survival", "test", "--no-worlds"])
+        .expect("test_parse_cli()");
+    match cli.command {
+        Some(Command::Clone {
+            source,
+            name,
+            no_worlds,
+        }) => {
+            assert_eq!((source.as_str(), name.as_str()), ("survival", "test"));
+            assert!(no_worlds);
+        }
+        other => panic!("test_parse_cli(): {other:?}"),
+    }
+
+    let cli = Cli::try_parse_from([
+        "mcscs",
+        "restore",
+        "test",
+        "20240314-120000",
+        "--to",
+        "copy",
+    ])
+    .expect("test_parse_cli()");
+    match cli.command {
+        Some(Command::Restore { id, stop, to, .. }) => {
+            assert_eq!(id, "20240314-120000");
+            assert!(!stop);
+            assert_eq!(to.as_deref(), Some("copy"));
+        }
+        other => panic!("test_parse_cli(): {other:?}"),
+    }
+    assert!(Cli::try_parse_from(["mcscs", "restore", "test", "1", "--stop", "--to", "a"]).is_err());
+
+    // 没有子命令时进入交互式菜单
+    assert!(Cli::try_parse_from(["mcscs"]).unwrap().command.is_none());
+    assert!(Cli::try_parse_from(["mcscs", "start"]).is_err());
+}
+
+/// 测试读取和修改配置项
+#[test]
+fn test_config_value() {
+    let mut server = ServerConfig::from_json(VALID_CONFIG).expect("test_config_value()");
+    assert_eq!(get_config_value(&server, "xmx").unwrap(), "2147483648");
+    set_config_value(&mut server, "Xmx", "4G").unwrap();
+    assert_eq!(server.xmx, 4 * 1024 * 1024 * 1024);
+    set_config_value(&mut server, "server_args", "--nogui --port 25566").unwrap();
+    assert_eq!(server.server_args, ["--nogui", "--port", "25566"]);
+    // Xmx不能小于Xms
+    assert!(set_config_value(&mut server, "Xmx", "512M").is_err());
+    assert!(set_config_value(&mut server, "unknown", "1").is_err());
+}
@@ -0,0 +1,97 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+mod common;
+
+use std::fs;
+
+use common::FakeServer;
+use mcscs::{
+    pages::clone::clone_server,
+    properties::Properties,
+    server::{load_servers_lists, save_servers_lists, ServerConfig},
+    workspace::Workspace,
+};
+
+const CONFIG: &str = r#"{
+    "schema_version": 1,
+    "name": "survival",
+    "java": {"path": "/usr/bin/java", "version": "17.0.9"},
+    "jvm_args": ["-Dlog4j2.formatMsgNoLookups=true"],
+    "encoding": "utf-8",
+    "Xms": 1073741824,
+    "Xmx": 4294967296,
+    "info": {"core": "Paper", "mc_version": "1.20.1", "build_version": "build196"},
+    "server_args": ["--nogui"]
+}"#;
+
+/// 测试复制服务器: 修改名称, 分配新的端口, 不复制运行时的文件, 可以不复制世界
+#[test]
+fn test_clone_server() {
+    let dir = tempfile::tempdir().expect("test_clone_server()");
+    let workspace = Workspace::new(dir.path());
+    Workspace::set_current(workspace.clone());
+    let server_dir = workspace.server_dir("survival");
+    fs::create_dir_all(server_dir.join("world")).unwrap();
+    fs::create_dir_all(server_dir.join("plugins")).unwrap();
+    fs::create_dir_all(server_dir.join("sessions")).unwrap();
+    let server = ServerConfig::from_json(CONFIG).unwrap();
+    save_servers_lists("survival", &server).unwrap();
+    fs::write(
+        server_dir.join("server.properties"),
+        "# comment\nserver-port=25565\nenable-rcon=true\nrcon.port=25575\n",
+    )
+    .unwrap();
+    fs::write(server_dir.join("server.jar"), "jar").unwrap();
+    fs::write(server_dir.join("plugins/plugin.jar"), "plugin").unwrap();
+    fs::write(server_dir.join("world/level.dat"), "level").unwrap();
+    fs::write(server_dir.join("sessions/1.log"), "log").unwrap();
+    fs::write(server_dir.join("restart_history.json"), "[]").unwrap();
+    fs::create_dir(server_dir.join(".core-backup")).unwrap();
+    fs::write(server_dir.join(".core-backup/server.jar"), "old jar").unwrap();
+
+    let copied = clone_server("survival", "test", true).expect("test_clone_server()");
+    assert_eq!(copied.name, "test");
+    assert_eq!(copied.info, server.info);
+    let servers = load_servers_lists(None).unwrap();
+    assert_eq!(servers.len(), 2);
+    assert_eq!(servers["test"], copied);
+    assert_eq!(servers["survival"], server);
+    let test_dir = workspace.server_dir("test");
+    assert_eq!(
+        fs::read_to_string(test_dir.join("world/level.dat")).unwrap(),
+        "level"
+    );
+    assert!(test_dir.join("server.jar").exists());
+    assert!(test_dir.join("plugins/plugin.jar").exists());
+    assert!(!test_dir.join("sessions").exists());
+    assert!(!test_dir.join("restart_history.json").exists());
+    assert!(!test_dir.join(".core-backup").exists());
+    let properties = Properties::load(&test_dir.join("server.properties")).unwrap();
+    assert!(properties.to_string().starts_with("# comment\n"));
+    let ports = [
+        properties.get("server-port").unwrap(),
+        properties.get("rcon.port").unwrap(),
+    ];
+    assert!(!ports.contains(&"25565") && !ports.contains(&"25575"));
+    assert_ne!(ports[0], ports[1]);
+    assert!(clone_server("survival", "test", true).is_err());
+    assert!(clone_server("missing", "other", true).is_err());
+
+    // 服务器运行时只能复制世界以外的文件
+    let _server = FakeServer::start(&server_dir);
+    assert!(clone_server("survival", "running", true).is_err());
+    assert!(!workspace.server_dir("running").exists());
+    let empty = clone_server("survival", "empty", false).expect("test_clone_server()");
+    let empty_dir = workspace.server_dir(&empty.name);
+    assert!(!empty_dir.join("world").exists());
+    assert!(!empty_dir.join("server.pid").exists());
+    assert!(empty_dir.join("plugins/plugin.jar").exists());
+    let empty_properties = Properties::load(&empty_dir.join("server.properties")).unwrap();
+    assert!(!ports.contains(&empty_properties.get("server-port").unwrap()));
+}
@@ -0,0 +1,67 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+// 每个测试只使用其中的一部分
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    path::Path,
+    process::{Child, Command},
+};
+
+use mcscs::{
+    java::Java,
+    provider::ProviderKind,
+    server::{CoreInfo, ServerConfig},
+};
+
+/// 用`/bin/sh -c <script>`代替Java运行的服务器配置, 服务器的输入输出就是脚本的输入输出
+pub fn sh_server(name: &str, script: &str) -> ServerConfig {
+    ServerConfig {
+        schema_version: 1,
+        name: name.to_string(),
+        java: Java {
+            path: "/bin/sh".into(),
+            version: "unknown".to_string(),
+        },
+        jvm_args: vec!["-c".to_string(), script.to_string()],
+        encoding: "utf-8".to_string(),
+        xms: 1024,
+        xmx: 1024,
+        info: CoreInfo {
+            core: "Test".to_string(),
+            mc_version: "1.20.1".to_string(),
+            build_version: "build1".to_string(),
+            provider: ProviderKind::FastMirror,
+        },
+        server_args: Vec::new(),
+    }
+}
+
+/// 在服务器文件夹里运行的"服务器"进程, 命令行里有server.jar, 并写入server.pid. 离开作用域时结束进程
+pub struct FakeServer(Child);
+
+impl FakeServer {
+    pub fn start(server_dir: &Path) -> Self {
+        let child = Command::new("/bin/sh")
+            .args(["-c", "sleep 60; :", "server.jar"])
+            .current_dir(server_dir)
+            .spawn()
+            .expect("FakeServer::start()");
+        fs::write(server_dir.join("server.pid"), child.id().to_string()).unwrap();
+        FakeServer(child)
+    }
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
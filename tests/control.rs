@@ -0,0 +1,131 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+#![cfg(unix)]
+
+mod common;
+
+use std::{fs, process, thread, time::Duration};
+
+use common::sh_server;
+use mcscs::{
+    control::{is_running, kill_server, stop_server, StopOutcome},
+    server::ServerConfig,
+    session::{host, read_pid, Session},
+    supervisor::{ExitKind, RestartHistory, RestartPolicy},
+    workspace::Workspace,
+};
+
+/// 在后台线程运行宿主, 等待服务器启动
+fn start(
+    workspace: &Workspace,
+    server: ServerConfig,
+    policy: Option<RestartPolicy>,
+) -> thread::JoinHandle<mcscs::Result<()>> {
+    let server_dir = workspace.server_dir(&server.name);
+    fs::create_dir_all(&server_dir).unwrap();
+    fs::write(server_dir.join("eula.txt"), "eula=true").unwrap();
+    let name = server.name.clone();
+    let handle = thread::spawn(move || host(&server, policy.as_ref()));
+    while Session::load(&name)
+        .unwrap()
+        .is_none_or(|session| session.server_pid == 0)
+    {
+        thread::sleep(Duration::from_millis(50));
+    }
+    handle
+}
+
+/// 测试关闭和强制结束服务器
+#[test]
+fn test_stop_server() {
+    let dir = tempfile::tempdir().expect("test_stop_server()");
+    let workspace = Workspace::new(dir.path());
+    Workspace::set_current(workspace.clone());
+
+    // 收到stop后正常退出
+    let handle = start(
+        &workspace,
+        sh_server(
+            "graceful",
+            r#"while read l; do [ "$l" = stop ] && exit 0; done"#,
+        ),
+        None,
+    );
+    assert!(read_pid(&workspace.server_dir("graceful")).is_some());
+    assert_eq!(
+        stop_server("graceful", Duration::from_secs(5)).expect("test_stop_server()"),
+        StopOutcome::Graceful
+    );
+    handle.join().unwrap().unwrap();
+    assert!(!is_running("graceful").unwrap());
+    assert!(read_pid(&workspace.server_dir("graceful")).is_none());
+
+    // 不响应stop, 超时后发送SIGTERM, 有守护进程时也不会重启
+    let handle = start(
+        &workspace,
+        sh_server("stuck", "while true; do sleep 0.1; done"),
+        Some(RestartPolicy::default()),
+    );
+    assert_eq!(
+        stop_server("stuck", Duration::from_millis(500)).expect("test_stop_server()"),
+        StopOutcome::Terminated
+    );
+    handle.join().unwrap().unwrap();
+    let history = RestartHistory::load(&workspace.server_dir("stuck")).unwrap();
+    assert_eq!(history.records.len(), 1);
+    assert_eq!(history.records[0].kind, ExitKind::Clean);
+
+    // 强制结束
+    let handle = start(
+        &workspace,
+        sh_server("kill", "while true; do sleep 0.1; done"),
+        None,
+    );
+    kill_server("kill").expect("test_stop_server()");
+    handle.join().unwrap().unwrap();
+    assert!(!is_running("kill").unwrap());
+    assert!(stop_server("kill", Duration::from_secs(1)).is_err());
+
+    // server.pid里的PID被其他进程使用时不会结束它, 并删除过期的server.pid
+    let server_dir = workspace.server_dir("kill");
+    fs::write(server_dir.join("server.pid"), process::id().to_string()).unwrap();
+    assert!(kill_server("kill").is_err());
+    assert!(read_pid(&server_dir).is_none());
+    assert!(!is_running("kill").unwrap());
+
+    // 崩溃后等待自动重启时仍然算作正在运行, 关闭或者强制结束会取消重启
+    let policy = RestartPolicy {
+        initial_backoff: Duration::from_secs(60),
+        ..RestartPolicy::default()
+    };
+    for name in ["backoff-stop", "backoff-kill"] {
+        let handle = start(
+            &workspace,
+            sh_server(name, "sleep 0.2; exit 1"),
+            Some(policy),
+        );
+        while Session::load(name).unwrap().unwrap().server_pid != 0 {
+            thread::sleep(Duration::from_millis(50));
+        }
+        assert!(is_running(name).unwrap());
+        if name == "backoff-stop" {
+            assert_eq!(
+                stop_server(name, Duration::from_secs(5)).expect("test_stop_server()"),
+                StopOutcome::Graceful
+            );
+        } else {
+            kill_server(name).expect("test_stop_server()");
+        }
+        handle.join().unwrap().unwrap();
+        assert!(!is_running(name).unwrap());
+        let history = RestartHistory::load(&workspace.server_dir(name)).unwrap();
+        assert_eq!(history.records.len(), 1);
+        assert_eq!(history.records[0].kind, ExitKind::Crash);
+    }
+}
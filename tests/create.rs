@@ -0,0 +1,67 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::fs;
+
+use mcscs::{
+    pages::create::{free_server_port, write_world_settings, WorldSettings},
+    properties::{Properties, PROPERTIES_FILE},
+    workspace::Workspace,
+};
+
+/// 测试创建服务器时写入server.properties
+#[test]
+fn test_world_settings() {
+    let dir = tempfile::tempdir().expect("test_world_settings()");
+    let workspace = Workspace::new(dir.path());
+    Workspace::set_current(workspace.clone());
+    for name in ["a", "b"] {
+        fs::create_dir_all(workspace.server_dir(name)).unwrap();
+        fs::write(
+            workspace.server_dir(name).join("config.json"),
+            format!(
+                r#"{{"schema_version": 1, "name": "{name}", "java": {{"path": "java", "version": "17"}},
+                "jvm_args": [], "encoding": "utf-8", "Xms": 1024, "Xmx": 1024,
+                "info": {{"core": "Paper", "mc_version": "1.20.1", "build_version": "build1"}},
+                "server_args": []}}"#
+            ),
+        )
+        .unwrap();
+    }
+
+    // a还没有启动过, 会使用默认的25565
+    let port = free_server_port("b").expect("test_world_settings()");
+    assert!(port > 25565);
+    let settings = WorldSettings {
+        port: Some(port),
+        level_seed: Some("-123".to_string()),
+        level_type: Some("flat".to_string()),
+        gamemode: Some("Creative".to_string()),
+        online_mode: Some(false),
+        max_players: Some(50),
+        ..Default::default()
+    };
+    write_world_settings("b", &settings).expect("test_world_settings()");
+    let properties = Properties::load(&workspace.server_dir("b").join(PROPERTIES_FILE)).unwrap();
+    assert_eq!(
+        properties.get("server-port"),
+        Some(port.to_string().as_str())
+    );
+    assert_eq!(properties.get("level-seed"), Some("-123"));
+    assert_eq!(properties.get("gamemode"), Some("creative"));
+    assert_eq!(properties.get("online-mode"), Some("false"));
+    assert_eq!(properties.get("max-players"), Some("50"));
+    assert_eq!(properties.get("difficulty"), None);
+    assert_ne!(free_server_port("a").unwrap(), port);
+
+    let invalid = WorldSettings {
+        difficulty: Some("impossible".to_string()),
+        ..Default::default()
+    };
+    assert!(write_world_settings("a", &invalid).is_err());
+}
@@ -15,7 +15,7 @@ use mcscs::{
 };
 
 async fn get_new_fastmirror_info(core: &str) -> (String, String, String) {
-    let fastmirror = get_fastmirror_value().await;
+    let fastmirror = get_fastmirror_value().await.unwrap();
     let mc_version = if let Some(fastmirror) = fastmirror[core]["mc_versions"]
         .as_array()
         .and_then(|arr| arr.first())
@@ -24,7 +24,9 @@ async fn get_new_fastmirror_info(core: &str) -> (String, String, String) {
     } else {
         "unknown".to_string()
     };
-    let fastmirror = get_fastmirror_builds_value(core, &mc_version).await;
+    let fastmirror = get_fastmirror_builds_value(core, &mc_version)
+        .await
+        .unwrap();
     let build_version = fastmirror[0]["core_version"].as_str().unwrap().to_string();
     let sha1 = fastmirror[0]["sha1"].as_str().unwrap().to_string();
     (mc_version, build_version, sha1)
@@ -52,7 +54,7 @@ async fn test_check_sha1() {
     let file_path = download_server_core("Mohist", &mc_version, &build_version)
         .await
         .unwrap();
-    let file_sha1 = get_file_sha1(&file_path);
+    let file_sha1 = get_file_sha1(&file_path).unwrap();
     println!("文件路径 = {}", file_path.display());
     println!("FastMirror SHA1 = {fastmirror_sha1}");
     println!("File SHA1 = {file_sha1}");
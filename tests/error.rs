@@ -0,0 +1,47 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::fs;
+
+use mcscs::{server::load_servers_lists, workspace::Workspace, Error};
+
+/// 测试读取不存在或者无效的服务器配置时返回错误而不是panic
+#[test]
+fn test_load_servers_error() {
+    let dir = tempfile::tempdir().expect("test_load_servers_error()");
+    let workspace = Workspace::new(dir.path());
+    fs::create_dir_all(workspace.server_dir("broken")).unwrap();
+    fs::write(
+        workspace.server_dir("broken").join("config.json"),
+        r#"{"schema_version": 1, "name": "broken"}"#,
+    )
+    .unwrap();
+    Workspace::set_current(workspace);
+
+    match load_servers_lists(Some("missing")) {
+        Err(Error::ServerNotFound(name)) => assert_eq!(name, "missing"),
+        other => panic!("test_load_servers_error(): {other:?}"),
+    }
+    match load_servers_lists(Some("broken")) {
+        Err(Error::Config(err)) => assert_eq!(err.field, "java"),
+        other => panic!("test_load_servers_error(): {other:?}"),
+    }
+    // 读取全部服务器时跳过无效的配置
+    assert!(load_servers_lists(None).unwrap().is_empty());
+}
+
+/// 测试错误信息
+#[test]
+fn test_error_display() {
+    let err = Error::Checksum {
+        expected: "aaa".to_string(),
+        actual: "bbb".to_string(),
+    };
+    assert_eq!(err.to_string(), "SHA1校验失败: 预期aaa, 实际bbb");
+    assert_eq!(Error::Cancelled.to_string(), "操作已取消");
+}
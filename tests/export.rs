@@ -0,0 +1,120 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::{fs, path::Path};
+
+use mcscs::{
+    archive::{extract, ArchiveFormat},
+    java::{save_java_lists, Java},
+    pages::{
+        export::{export_server, ExportManifest, ExportOptions},
+        import::{import_server, ImportOptions},
+    },
+    server::{load_servers_lists, save_servers_lists, ServerConfig},
+    workspace::Workspace,
+};
+
+const CONFIG: &str = r#"{
+    "schema_version": 1,
+    "name": "survival",
+    "java": {"path": "/home/a/jdk-17.0.9/bin/java", "version": "17.0.9"},
+    "jvm_args": ["-Dlog4j2.formatMsgNoLookups=true", "-XX:+UseG1GC"],
+    "encoding": "utf-8",
+    "Xms": 1073741824,
+    "Xmx": 8589934592,
+    "info": {"core": "Paper", "mc_version": "1.20.1", "build_version": "build196"},
+    "server_args": ["--nogui"]
+}"#;
+
+fn write(path: &Path, data: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, data).unwrap();
+}
+
+/// 测试导出后在另一台电脑上导入
+#[test]
+fn test_export_import() {
+    let dir = tempfile::tempdir().expect("test_export_import()");
+    let workspace = Workspace::new(dir.path().join("MCSCS"));
+    Workspace::set_current(workspace.clone());
+    fs::create_dir_all(workspace.configs_dir()).unwrap();
+    let server = ServerConfig::from_json(CONFIG).unwrap();
+    let server_dir = workspace.server_dir("survival");
+    fs::create_dir_all(&server_dir).unwrap();
+    save_servers_lists("survival", &server).unwrap();
+    write(&server_dir.join("server.jar"), "jar");
+    write(&server_dir.join("world/level.dat"), "level");
+    write(&server_dir.join("world/session.lock"), "lock");
+    write(&server_dir.join("logs/latest.log"), "log");
+    write(&server_dir.join("crash-reports/crash.txt"), "crash");
+    write(&server_dir.join("cache/mojang_1.20.1.jar"), "cache");
+    // 上次运行留下的pid文件
+    write(&server_dir.join("server.pid"), "4194304");
+    write(&server_dir.join(".core-backup/server.jar"), "old jar");
+
+    // 目标电脑上的Java路径不同
+    save_java_lists(&[
+        Java {
+            path: "/usr/lib/jvm/java-21/bin/java".into(),
+            version: "21.0.2".into(),
+        },
+        Java {
+            path: "/usr/lib/jvm/java-17/bin/java".into(),
+            version: "17.0.10".into(),
+        },
+    ])
+    .unwrap();
+
+    for format in [ArchiveFormat::TarZst, ArchiveFormat::Zip] {
+        let archive = dir.path().join(format!("survival.{}", format.extension()));
+        let options = ExportOptions {
+            format,
+            exclude_logs: true,
+            exclude_caches: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            export_server("survival", &archive, &options).expect("test_export_import()"),
+            3
+        );
+        assert_eq!(ArchiveFormat::detect(&archive).unwrap(), format);
+
+        // 压缩包里是服务器文件和清单
+        let extracted = dir.path().join(format!("extracted-{}", format.extension()));
+        extract(&archive, &extracted).expect("test_export_import()");
+        assert!(extracted.join("world/level.dat").exists());
+        assert!(extracted.join("crash-reports/crash.txt").exists());
+        for excluded in [
+            "config.json",
+            "server.pid",
+            ".core-backup",
+            "world/session.lock",
+            "logs",
+            "cache",
+        ] {
+            assert!(!extracted.join(excluded).exists(), "{excluded}");
+        }
+        let manifest = ExportManifest::load(&extracted).unwrap().unwrap();
+        assert_eq!(manifest.java_major, 17);
+        assert!(manifest.config.get("java").is_none());
+
+        // 导入时重新选择Java, 其他配置不变
+        let name = format!("imported-{}", format.extension());
+        let imported = import_server(&archive, &name, &ImportOptions::default())
+            .expect("test_export_import()");
+        assert_eq!(imported.name, name);
+        assert_eq!(imported.java.version, "17.0.10");
+        assert_eq!(imported.xmx, server.xmx);
+        assert_eq!(imported.jvm_args, server.jvm_args);
+        assert_eq!(imported.info, server.info);
+        let imported_dir = workspace.server_dir(&name);
+        assert!(imported_dir.join("server.jar").exists());
+        assert!(!imported_dir.join("mcscs.json").exists());
+        assert_eq!(load_servers_lists(Some(&name)).unwrap()[&name], imported);
+    }
+}
@@ -0,0 +1,164 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::Path,
+};
+
+use mcscs::{
+    java::{save_java_lists, Java},
+    pages::import::{detect_core, import_server, ImportOptions},
+    server::load_servers_lists,
+    workspace::Workspace,
+};
+use zip::{write::FileOptions, ZipWriter};
+
+/// 生成一个只有`MANIFEST.MF`和`version.json`的服务器核心
+fn write_jar(path: &Path, manifest: &str, version_json: Option<&str>) {
+    let mut jar = ZipWriter::new(File::create(path).unwrap());
+    jar.start_file("META-INF/MANIFEST.MF", FileOptions::default())
+        .unwrap();
+    jar.write_all(manifest.as_bytes()).unwrap();
+    if let Some(version_json) = version_json {
+        jar.start_file("version.json", FileOptions::default())
+            .unwrap();
+        jar.write_all(version_json.as_bytes()).unwrap();
+    }
+    jar.finish().unwrap();
+}
+
+/// 测试从核心检测核心名称和版本
+#[test]
+fn test_detect_core() {
+    let dir = tempfile::tempdir().expect("test_detect_core()");
+    let paper = dir.path().join("paper-1.20.1-196.jar");
+    write_jar(
+        &paper,
+        "Manifest-Version: 1.0\nMain-Class: io.papermc.paperclip.Main\n",
+        Some(r#"{"id": "1.20.1", "name": "1.20.1", "java_version": 17}"#),
+    );
+    let detected = detect_core(&paper).expect("test_detect_core()");
+    assert_eq!(detected.info.core, "Paper");
+    assert_eq!(detected.info.mc_version, "1.20.1");
+    assert_eq!(detected.info.build_version, "build196");
+    assert_eq!(detected.required_java(), 17);
+
+    let spigot = dir.path().join("server.jar");
+    write_jar(
+        &spigot,
+        "Main-Class: org.bukkit.craftbukkit.Main\nImplementation-Version: git-Spigot-79a30d7-f4830a1 (M\n C: 1.12.2)\n",
+        None,
+    );
+    let detected = detect_core(&spigot).expect("test_detect_core()");
+    assert_eq!(detected.info.core, "CraftBukkit");
+    assert_eq!(detected.info.mc_version, "1.12.2");
+    assert_eq!(detected.info.build_version, "unknown");
+    assert_eq!(detected.java_major, None);
+    assert_eq!(detected.required_java(), 8);
+}
+
+/// 测试导入文件夹和zip压缩包
+#[test]
+fn test_import_server() {
+    let dir = tempfile::tempdir().expect("test_import_server()");
+    let workspace = Workspace::new(dir.path().join("MCSCS"));
+    Workspace::set_current(workspace.clone());
+    fs::create_dir_all(workspace.servers_dir()).unwrap();
+    fs::create_dir_all(workspace.configs_dir()).unwrap();
+    save_java_lists(&[
+        Java {
+            path: "/opt/java8/bin/java".into(),
+            version: "1.8.0_402".into(),
+        },
+        Java {
+            path: "/opt/java21/bin/java".into(),
+            version: "21.0.1".into(),
+        },
+        Java {
+            path: "/opt/java17/bin/java".into(),
+            version: "17.0.9".into(),
+        },
+    ])
+    .unwrap();
+
+    // 文件夹: 复制并把核心重命名为server.jar
+    let source = dir.path().join("old-server");
+    fs::create_dir_all(source.join("world")).unwrap();
+    fs::write(source.join("world").join("level.dat"), "level").unwrap();
+    fs::write(source.join("eula.txt"), "eula=true\n").unwrap();
+    // 共用的插件文件夹: 复制符号链接而不是里面的文件
+    let shared = dir.path().join("shared-plugins");
+    fs::create_dir_all(&shared).unwrap();
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&shared, source.join("plugins")).unwrap();
+    write_jar(
+        &source.join("paper-1.20.1-196.jar"),
+        "Main-Class: io.papermc.paperclip.Main\n",
+        Some(r#"{"id": "1.20.1", "java_version": 17}"#),
+    );
+    let server =
+        import_server(&source, "copied", &ImportOptions::default()).expect("test_import_server()");
+    assert_eq!(server.java.version, "17.0.9");
+    assert_eq!(server.info.build_version, "build196");
+    let server_dir = workspace.server_dir("copied");
+    assert!(server_dir.join("server.jar").exists());
+    assert!(!server_dir.join("paper-1.20.1-196.jar").exists());
+    assert_eq!(
+        fs::read_to_string(server_dir.join("world").join("level.dat")).unwrap(),
+        "level"
+    );
+    assert!(source.join("paper-1.20.1-196.jar").exists());
+    #[cfg(unix)]
+    assert_eq!(fs::read_link(server_dir.join("plugins")).unwrap(), shared);
+    assert_eq!(server.xms, 1024 * 1024 * 1024);
+    assert_eq!(
+        load_servers_lists(Some("copied")).unwrap()["copied"],
+        server
+    );
+    assert!(import_server(&source, "copied", &ImportOptions::default()).is_err());
+
+    // 有多个jar文件时需要指定核心
+    write_jar(&source.join("plugin.jar"), "Main-Class: a.B\n", None);
+    assert!(import_server(&source, "multiple", &ImportOptions::default()).is_err());
+    assert!(!workspace.server_dir("multiple").exists());
+
+    // zip压缩包: 只有一个文件夹时使用文件夹里的文件
+    let archive = dir.path().join("server.zip");
+    let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+    zip.start_file("vanilla/eula.txt", FileOptions::default())
+        .unwrap();
+    zip.write_all(b"eula=true\n").unwrap();
+    zip.start_file(
+        "vanilla/minecraft_server.1.12.2.jar",
+        FileOptions::default(),
+    )
+    .unwrap();
+    let jar = dir.path().join("vanilla.jar");
+    write_jar(
+        &jar,
+        "Main-Class: net.minecraft.server.MinecraftServer\n",
+        None,
+    );
+    zip.write_all(&fs::read(&jar).unwrap()).unwrap();
+    zip.finish().unwrap();
+    let server =
+        import_server(&archive, "zipped", &ImportOptions::default()).expect("test_import_server()");
+    assert_eq!(server.info.core, "Vanilla");
+    assert_eq!(server.info.mc_version, "1.12.2");
+    assert_eq!(server.java.version, "1.8.0_402");
+    assert!(workspace.server_dir("zipped").join("server.jar").exists());
+    assert!(workspace.server_dir("zipped").join("eula.txt").exists());
+    assert!(!workspace.temp_dir().join("import-zipped").exists());
+    assert_eq!(
+        fs::read_dir(workspace.servers_dir()).unwrap().count(),
+        2,
+        "解压的临时文件不在servers文件夹里"
+    );
+}
@@ -12,5 +12,8 @@ use mcscs::{java::detect_java, pages::init};
 #[tokio::test]
 async fn test_detect_java() {
     init::main().await.expect("main()");
-    println!("{}", serde_json::to_string_pretty(&detect_java()).unwrap())
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&detect_java().unwrap()).unwrap()
+    )
 }
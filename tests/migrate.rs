@@ -0,0 +1,112 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::fs;
+
+use serde_json::{json, Value};
+
+use mcscs::migrate::{import_legacy_servers, load_and_upgrade, upgrade, SCHEMA_VERSION};
+
+fn legacy_config(name: &str) -> Value {
+    json!({
+        "name": name,
+        "java": {"path": "/usr/bin/java", "version": "17.0.9"},
+        "jvm_args": "-Dlog4j2.formatMsgNoLookups=true -XX:+UseG1GC",
+        "encoding": "utf-8",
+        "Xms": "1G",
+        "Xmx": 2147483648u64,
+        "info": {"core": "Mohist", "mc_version": "1.20.1", "build_version": "build593"},
+        "server_args": ["--nogui"]
+    })
+}
+
+/// 测试升级版本0的配置
+#[test]
+fn test_upgrade_v0() {
+    let mut config = legacy_config("test");
+    assert_eq!(upgrade(&mut config).expect("test_upgrade_v0()"), 0);
+    assert_eq!(config["schema_version"], SCHEMA_VERSION);
+    assert_eq!(config["Xms"], 1073741824);
+    assert_eq!(
+        config["jvm_args"],
+        json!(["-Dlog4j2.formatMsgNoLookups=true", "-XX:+UseG1GC"])
+    );
+}
+
+/// 测试拒绝比当前程序更新的配置
+#[test]
+fn test_upgrade_newer_version() {
+    let mut config = json!({"schema_version": SCHEMA_VERSION + 1});
+    let err = upgrade(&mut config).expect_err("test_upgrade_newer_version()");
+    assert_eq!(err.field, "schema_version");
+}
+
+/// 测试拒绝超出范围的内存大小, 不会溢出
+#[test]
+fn test_upgrade_memory_overflow() {
+    let mut config = legacy_config("test");
+    config["Xmx"] = json!("99999999999T");
+    let err = upgrade(&mut config).expect_err("test_upgrade_memory_overflow()");
+    assert_eq!(err.field, "Xmx");
+}
+
+/// 测试读取旧配置时会备份并写回升级后的配置
+#[test]
+fn test_load_and_upgrade() {
+    let dir = tempfile::tempdir().expect("test_load_and_upgrade()");
+    let path = dir.path().join("config.json");
+    let original = serde_json::to_string_pretty(&legacy_config("test")).unwrap();
+    fs::write(&path, &original).unwrap();
+
+    let config = load_and_upgrade(&path).expect("test_load_and_upgrade()");
+    assert_eq!(config.schema_version, SCHEMA_VERSION);
+    assert_eq!(
+        fs::read_to_string(dir.path().join("config.json.v0.bak")).unwrap(),
+        original
+    );
+    let saved = serde_json::from_str::<Value>(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(saved["schema_version"], SCHEMA_VERSION);
+}
+
+/// 测试从旧版本的servers.json导入服务器
+#[test]
+fn test_import_legacy_servers() {
+    let dir = tempfile::tempdir().expect("test_import_legacy_servers()");
+    let configs_dir = dir.path().join("configs");
+    let servers_dir = dir.path().join("servers");
+    fs::create_dir_all(&configs_dir).unwrap();
+    fs::create_dir_all(&servers_dir).unwrap();
+    fs::write(
+        configs_dir.join("servers.json"),
+        json!({"lobby": legacy_config("lobby"), "broken": {"name": "broken"}}).to_string(),
+    )
+    .unwrap();
+
+    // 有服务器导入失败时保留servers.json
+    let failed =
+        import_legacy_servers(&configs_dir, &servers_dir).expect("test_import_legacy_servers()");
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].0, "broken");
+    assert!(servers_dir.join("lobby").join("config.json").exists());
+    assert!(!servers_dir.join("broken").exists());
+    assert!(configs_dir.join("servers.json").exists());
+    assert!(!configs_dir.join("servers.json.bak").exists());
+
+    // 修复后重新导入, 已经导入的服务器不会被覆盖
+    fs::write(
+        configs_dir.join("servers.json"),
+        json!({"lobby": legacy_config("lobby"), "broken": legacy_config("broken")}).to_string(),
+    )
+    .unwrap();
+    let failed =
+        import_legacy_servers(&configs_dir, &servers_dir).expect("test_import_legacy_servers()");
+    assert!(failed.is_empty());
+    assert!(servers_dir.join("broken").join("config.json").exists());
+    assert!(!configs_dir.join("servers.json").exists());
+    assert!(configs_dir.join("servers.json.bak").exists());
+}
@@ -0,0 +1,122 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use mcscs::mojang::{VersionInfo, VersionManifest, VersionType, VANILLA};
+use mcscs::output::OutdatedEntry;
+use mcscs::provider::{Build, ProviderKind};
+use mcscs::server::{CoreInfo, ServerConfig};
+
+const MANIFEST: &str = r#"{
+    "latest": {"release": "1.20.4", "snapshot": "24w14a"},
+    "versions": [
+        {"id": "24w14a", "type": "snapshot", "url": "https://piston-meta.mojang.com/v1/packages/a/24w14a.json", "time": "2024-04-03T12:50:04+00:00", "releaseTime": "2024-04-03T12:39:08+00:00", "sha1": "a", "complianceLevel": 1},
+        {"id": "1.20.4", "type": "release", "url": "https://piston-meta.mojang.com/v1/packages/b/1.20.4.json", "time": "2024-03-20T07:26:04+00:00", "releaseTime": "2023-12-07T12:56:20+00:00", "sha1": "b", "complianceLevel": 1},
+        {"id": "b1.7.3", "type": "old_beta", "url": "https://piston-meta.mojang.com/v1/packages/c/b1.7.3.json", "time": "2022-03-10T09:51:38+00:00", "releaseTime": "2011-07-07T22:00:00+00:00", "sha1": "c", "complianceLevel": 0},
+        {"id": "rd-132211", "type": "old_alpha", "url": "https://piston-meta.mojang.com/v1/packages/d/rd-132211.json", "time": "2022-03-10T09:51:38+00:00", "releaseTime": "2009-05-13T20:11:00+00:00", "sha1": "d", "complianceLevel": 0}
+    ]
+}"#;
+
+/// 测试读取版本清单和版本信息: 按类型过滤版本, 读取服务器核心和需要的Java版本
+#[test]
+fn test_version_manifest() {
+    let manifest: VersionManifest =
+        serde_json::from_str(MANIFEST).expect("test_version_manifest()");
+    assert_eq!(manifest.latest.release, "1.20.4");
+    assert_eq!(manifest.versions(false), ["1.20.4"]);
+    assert_eq!(manifest.versions(true), ["24w14a", "1.20.4"]);
+    let version = manifest.version("24w14a").expect("test_version_manifest()");
+    assert_eq!(version.version_type, VersionType::Snapshot);
+    assert_eq!(version.version_type.to_string(), "snapshot");
+    assert!(manifest.version("1.20.5").is_err());
+
+    let info: VersionInfo = serde_json::from_str(
+        r#"{
+            "id": "1.20.4",
+            "type": "release",
+            "mainClass": "net.minecraft.client.main.Main",
+            "javaVersion": {"component": "java-runtime-gamma", "majorVersion": 17},
+            "downloads": {
+                "client": {"sha1": "fd19469fed4a4b4c15b2d5133985f0e3e7816a8a", "size": 24445539, "url": "https://piston-data.mojang.com/v1/objects/fd19469fed4a4b4c15b2d5133985f0e3e7816a8a/client.jar"},
+                "server": {"sha1": "8dd1a28015f51b1803213892b50b7b4fc76e594d", "size": 49150256, "url": "https://piston-data.mojang.com/v1/objects/8dd1a28015f51b1803213892b50b7b4fc76e594d/server.jar"}
+            }
+        }"#,
+    )
+    .expect("test_version_manifest()");
+    let server = info.server().expect("test_version_manifest()");
+    assert_eq!(server.sha1, "8dd1a28015f51b1803213892b50b7b4fc76e594d");
+    assert!(server.url.ends_with("/server.jar"));
+    assert_eq!(info.java_version.unwrap().major_version, 17);
+
+    // 很早的版本没有服务器核心, 也没有记录需要的Java
+    let info: VersionInfo = serde_json::from_str(
+        r#"{"id": "b1.7.3", "type": "old_beta", "downloads": {"client": {"sha1": "43db9b498cb67058d2e12d394e6507722e71bb45", "size": 1465375, "url": "https://launcher.mojang.com/v1/objects/43db9b498cb67058d2e12d394e6507722e71bb45/client.jar"}}}"#,
+    )
+    .expect("test_version_manifest()");
+    assert!(info.server().is_err());
+    assert_eq!(info.java_version, None);
+}
+
+/// 测试从Mojang创建的服务器: 构建版本是版本类型, 只和Mojang的构建版本比较
+#[test]
+fn test_mojang_server_outdated() {
+    let manifest: VersionManifest =
+        serde_json::from_str(MANIFEST).expect("test_mojang_server_outdated()");
+    let mut server = ServerConfig::from_json(
+        r#"{"schema_version": 1, "name": "vanilla", "java": {"path": "java", "version": "17"}, "jvm_args": [], "encoding": "utf-8", "Xms": 1024, "Xmx": 1024, "info": {"core": "Vanilla", "mc_version": "1.20.4", "build_version": "release", "provider": "mojang"}, "server_args": []}"#,
+    )
+    .expect("test_mojang_server_outdated()");
+    assert_eq!(
+        server.info,
+        CoreInfo {
+            core: VANILLA.to_string(),
+            mc_version: "1.20.4".to_string(),
+            build_version: "release".to_string(),
+            provider: ProviderKind::Mojang,
+        }
+    );
+
+    // 和Mojang::builds一样, 每个Minecraft版本只有一个构建版本
+    let version = manifest.version("1.20.4").unwrap();
+    let builds = [Build {
+        version: version.version_type.to_string(),
+        update_time: version.release_time.clone(),
+    }];
+    let entry = OutdatedEntry::check(
+        &server,
+        ProviderKind::Mojang,
+        &manifest.versions(false),
+        &builds,
+    );
+    assert_eq!(entry.latest_build.as_deref(), Some("release"));
+    assert!(!entry.outdated);
+    assert!(entry.newer_mc_versions.is_empty());
+
+    // Mojang不可用时换成FastMirror, 它的构建版本无法和release比较
+    let fastmirror = [Build {
+        version: "build1".to_string(),
+        update_time: "2024-03-01T00:00:00".to_string(),
+    }];
+    let entry = OutdatedEntry::check(
+        &server,
+        ProviderKind::FastMirror,
+        &["1.20.4".to_string()],
+        &fastmirror,
+    );
+    assert_eq!(entry.latest_build, None);
+    assert!(!entry.outdated);
+
+    // 旧的版本可以更新到更新的Minecraft版本
+    server.info.mc_version = "1.20.1".to_string();
+    let entry = OutdatedEntry::check(
+        &server,
+        ProviderKind::Mojang,
+        &manifest.versions(false),
+        &builds,
+    );
+    assert_eq!(entry.newer_mc_versions, ["1.20.4"]);
+}
@@ -0,0 +1,105 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use mcscs::{
+    output::{check_outdated, format_bytes, format_table, CoreEntry, OutdatedEntry, ServerEntry},
+    pages::create::to_bytes,
+    server::ServerConfig,
+};
+use serde_json::json;
+
+/// 测试内存大小的格式化
+#[test]
+fn test_format_bytes() {
+    for size in ["512M", "1G", "3G", "2T", "1023"] {
+        assert_eq!(format_bytes(to_bytes(size)), size);
+    }
+}
+
+/// 测试表格对齐
+#[test]
+fn test_format_table() {
+    let table = format_table(
+        &["名称", "版本"],
+        &[
+            vec!["test".to_string(), "1.20.1".to_string()],
+            vec!["生存服务器".to_string(), "1.8.9".to_string()],
+        ],
+    );
+    assert_eq!(
+        table,
+        "名称        版本\ntest        1.20.1\n生存服务器  1.8.9\n"
+    );
+}
+
+/// 测试把FastMirror的返回值转换为核心列表
+#[test]
+fn test_core_entry() {
+    let fastmirror = json!({
+        "Mohist": {
+            "name": "Mohist",
+            "tag": "mod",
+            "homepage": "https://mohistmc.com",
+            "recommend": false,
+            "mc_versions": ["1.20.1", "1.12.2"]
+        }
+    });
+    let cores = CoreEntry::from_fastmirror(&fastmirror).expect("test_core_entry()");
+    assert_eq!(cores.len(), 1);
+    assert_eq!(cores[0].tag, "mod");
+    assert_eq!(
+        serde_json::to_value(&cores).unwrap(),
+        json!([fastmirror["Mohist"]])
+    );
+    assert!(CoreEntry::from_fastmirror(&json!({"Broken": {"name": "Broken"}})).is_err());
+}
+
+/// 测试服务器列表里的状态, 无法检查是否正在运行时显示为未知
+#[test]
+fn test_server_entry_state() {
+    let config = ServerConfig::from_json(r#"{"schema_version": 1, "name": "test", "java": {"path": "java", "version": "17"}, "jvm_args": [], "encoding": "utf-8", "Xms": 1024, "Xmx": 1024, "info": {"core": "Paper", "mc_version": "1.20.1", "build_version": "build1"}, "server_args": []}"#)
+        .expect("test_server_entry_state()");
+    let entry = |running| ServerEntry {
+        config: config.clone(),
+        running,
+        status: None,
+    };
+    assert_eq!(entry(Some(true)).state(), "启动中");
+    assert_eq!(entry(Some(false)).state(), "离线");
+    let unknown = entry(None);
+    assert_eq!(unknown.state(), "未知");
+    assert_eq!(
+        serde_json::to_value(&unknown).unwrap()["running"],
+        json!(null)
+    );
+}
+
+/// 测试`outdated --exit-code`: 有新的构建版本和无法检查的服务器都会返回错误, 错误信息不同
+#[test]
+fn test_check_outdated() {
+    let entry = |latest_build: Option<&str>, outdated| OutdatedEntry {
+        name: "test".to_string(),
+        core: "Paper".to_string(),
+        mc_version: "1.20.1".to_string(),
+        build_version: "build1".to_string(),
+        latest_build: latest_build.map(String::from),
+        outdated,
+        newer_mc_versions: Vec::new(),
+    };
+    assert!(check_outdated(&[]).is_ok());
+    assert!(check_outdated(&[entry(Some("build1"), false)]).is_ok());
+
+    let err = check_outdated(&[entry(Some("build2"), true)]).unwrap_err();
+    assert!(err.to_string().contains("1个服务器有新的构建版本"), "{err}");
+    assert!(!err.to_string().contains("无法检查"), "{err}");
+
+    // 来源不可用时不能当作已是最新
+    let err = check_outdated(&[entry(Some("build1"), false), entry(None, false)]).unwrap_err();
+    assert!(err.to_string().contains("1个服务器无法检查"), "{err}");
+    assert!(!err.to_string().contains("mcscs update"), "{err}");
+}
@@ -6,31 +6,29 @@
  * Vestibulum commodo. Ut rhoncus gravida arcu.
  */
 
-use std::error::Error;
-
 use mcscs::pages::{config, delete, init, start};
+use mcscs::Result;
 
 #[doc = "测试创建服务器"]
 mod test_create {
-    use std::error::Error;
-
     use serde_json::json;
 
     use mcscs::pages::{
         create::{self, java, jvm_args, name, xms, xmx},
         init,
     };
+    use mcscs::Result;
 
     #[tokio::test]
     #[doc = "测试创建服务器页面"]
-    async fn test_create_pages() -> Result<(), Box<dyn Error>> {
+    async fn test_create_pages() -> Result<()> {
         create::main().await
     }
 
     #[test]
     #[doc = "测试用户输入服务器昵称"]
     fn test_server_name() {
-        println!("{}", name())
+        println!("{}", name().unwrap())
     }
 
     #[test]
@@ -38,7 +36,7 @@ mod test_create {
     fn test_java() {
         println!(
             "{}",
-            serde_json::to_string_pretty(&java()).unwrap_or("unknown".to_string())
+            serde_json::to_string_pretty(&java(None).ok()).unwrap_or("unknown".to_string())
         )
     }
 
@@ -48,8 +46,8 @@ mod test_create {
         println!("1GB = 1000MB");
         println!("1MB = 1000KB");
         println!("1KB = 1000Bytes");
-        let xms = xms(None);
-        let xmx = xmx(xms);
+        let xms = xms(None).unwrap();
+        let xmx = xmx(xms).unwrap();
         println!(
             "{}",
             serde_json::to_string_pretty(&json!({"Xms": xms, "Xmx": xmx}))
@@ -62,16 +60,13 @@ mod test_create {
     fn test_jvm_args() {
         println!(
             "{}",
-            serde_json::to_string_pretty(&jvm_args(None)).unwrap_or("unknown".to_string())
+            serde_json::to_string_pretty(&jvm_args(None).unwrap()).unwrap_or("unknown".to_string())
         )
     }
 
     #[doc = "测试用户选择核心"]
     mod test_download {
-        use mcscs::{
-            fastmirror::download_server_core,
-            pages::create::{build_version, core, mc_version},
-        };
+        use mcscs::pages::create::{build_version, core, mc_version};
 
         use super::*;
 
@@ -82,10 +77,13 @@ mod test_create {
                 eprintln!("初始化失败: {err}");
                 return;
             }
-            let core = core().await;
-            let mc_version = mc_version(&core).await;
-            let build_version = build_version(&core, &mc_version).await;
-            match download_server_core(&core, &mc_version, &build_version).await {
+            let (kind, core) = core().await.unwrap();
+            let provider = kind.provider();
+            let mc_version = mc_version(provider.as_ref(), &core).await.unwrap();
+            let build_version = build_version(provider.as_ref(), &core, &mc_version)
+                .await
+                .unwrap();
+            match provider.download(&core, &mc_version, &build_version).await {
                 Ok(file_path) => {
                     println!("下载成功: {}", file_path.display());
                 }
@@ -110,19 +108,19 @@ mod test_create {
 
 #[test]
 #[doc = "测试启动服务器页面"]
-fn test_start_pages() -> Result<(), Box<dyn Error>> {
+fn test_start_pages() -> Result<()> {
     start::main()
 }
 
 #[test]
 #[doc = "测试删除服务器页面"]
-fn test_delete_pages() -> Result<(), Box<dyn Error>> {
+fn test_delete_pages() -> Result<()> {
     delete::main()
 }
 
 #[test]
 #[doc = "测试配置服务器页面"]
-fn test_config_pages() -> Result<(), Box<dyn Error>> {
+fn test_config_pages() -> Result<()> {
     config::main()
 }
 
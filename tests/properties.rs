@@ -0,0 +1,120 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::fs;
+
+use mcscs::{
+    pages::config::{load_properties, set_property},
+    properties::{validate_property, Properties},
+    workspace::Workspace,
+};
+
+const SERVER_PROPERTIES: &str = r#"#Minecraft server properties
+#Thu Mar 14 12:00:00 CST 2024
+enable-rcon=false
+generator-settings={}
+level-name=world
+motd=A Minecraft Server
+server-port=25565
+
+! 另一种注释
+spawn-protection = 16
+level-seed:abc\:def
+long-value=first \
+    second
+"#;
+
+/// 测试没有修改时原样写回
+#[test]
+fn test_roundtrip() {
+    let properties = Properties::parse(SERVER_PROPERTIES);
+    assert_eq!(properties.to_string(), SERVER_PROPERTIES);
+}
+
+/// 测试解析各种写法
+#[test]
+fn test_parse_properties() {
+    let properties = Properties::parse(SERVER_PROPERTIES);
+    assert_eq!(properties.get("generator-settings"), Some("{}"));
+    assert_eq!(properties.get("motd"), Some("A Minecraft Server"));
+    assert_eq!(properties.get("spawn-protection"), Some("16"));
+    assert_eq!(properties.get("level-seed"), Some("abc:def"));
+    assert_eq!(properties.get("long-value"), Some("first second"));
+    assert_eq!(properties.get("missing"), None);
+    let keys = properties.iter().map(|(key, _)| key).collect::<Vec<&str>>();
+    assert_eq!(keys[0], "enable-rcon");
+    assert_eq!(keys.len(), 8);
+}
+
+/// 测试修改时保留注释和顺序, 只改写修改过的行
+#[test]
+fn test_set_properties() {
+    let mut properties = Properties::parse(SERVER_PROPERTIES);
+    properties.set("enable-rcon", "true");
+    properties.set("motd", "欢迎: 生存服");
+    properties.set("rcon.port", "25575");
+    // 值没有变化时不改写
+    properties.set("spawn-protection", "16");
+    assert_eq!(properties.remove("level-seed"), Some("abc:def".to_string()));
+    let data = properties.to_string();
+    assert!(data.starts_with("#Minecraft server properties\n#Thu Mar 14"));
+    assert!(data.contains("\nenable-rcon=true\ngenerator-settings={}\n"));
+    assert!(data.contains("\nmotd=\\u6B22\\u8FCE\\: \\u751F\\u5B58\\u670D\n"));
+    assert!(data.contains("\nspawn-protection = 16\n"));
+    assert!(!data.contains("level-seed"));
+    assert!(data.ends_with("second\nrcon.port=25575\n"));
+
+    // 写入后再读取得到相同的值
+    let reparsed = Properties::parse(&data);
+    assert_eq!(reparsed.get("motd"), Some("欢迎: 生存服"));
+    assert_eq!(reparsed.get("rcon.port"), Some("25575"));
+}
+
+/// 测试常用配置项的检查
+#[test]
+fn test_validate_property() {
+    assert_eq!(validate_property("server-port", " 25566").unwrap(), "25566");
+    assert!(validate_property("server-port", "0").is_err());
+    assert!(validate_property("server-port", "abc").is_err());
+    assert_eq!(validate_property("white-list", "False").unwrap(), "false");
+    assert!(validate_property("online-mode", "yes").is_err());
+    assert_eq!(
+        validate_property("gamemode", "CREATIVE").unwrap(),
+        "creative"
+    );
+    // 旧版本使用序号
+    assert_eq!(validate_property("difficulty", "2").unwrap(), "2");
+    assert!(validate_property("difficulty", "4").is_err());
+    assert!(validate_property("view-distance", "2").is_err());
+    assert_eq!(validate_property("view-distance", "12").unwrap(), "12");
+    assert!(validate_property("max-players", "-1").is_err());
+    assert!(validate_property("level-name", " ").is_err());
+    assert_eq!(validate_property("motd", "§aHello").unwrap(), "§aHello");
+}
+
+/// 测试修改服务器的server.properties
+#[test]
+fn test_set_property() {
+    let dir = tempfile::tempdir().expect("test_set_property()");
+    let workspace = Workspace::new(dir.path());
+    Workspace::set_current(workspace.clone());
+    let server_dir = workspace.server_dir("test");
+    fs::create_dir_all(&server_dir).unwrap();
+    fs::write(server_dir.join("server.properties"), SERVER_PROPERTIES).unwrap();
+
+    set_property("test", "server-port", "25570").expect("test_set_property()");
+    set_property("test", "online-mode", "FALSE").expect("test_set_property()");
+    assert!(set_property("test", "max-players", "many").is_err());
+    let properties = load_properties("test").unwrap();
+    assert_eq!(properties.get("server-port"), Some("25570"));
+    assert_eq!(properties.get("online-mode"), Some("false"));
+    assert_eq!(properties.get("max-players"), None);
+    let data = fs::read_to_string(server_dir.join("server.properties")).unwrap();
+    assert!(data.starts_with("#Minecraft server properties\n"));
+    assert!(data.contains("\n! 另一种注释\n"));
+}
@@ -0,0 +1,118 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::{fs, net::UdpSocket, thread};
+
+use mcscs::{
+    query::{connect_server, QueryClient, TYPE_HANDSHAKE, TYPE_STAT},
+    status::DEFAULT_TIMEOUT,
+    workspace::Workspace,
+};
+
+const TOKEN: i32 = 9513307;
+
+/// 在本地启动一个测试服务器, 返回端口
+fn serve() -> u16 {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let port = socket.local_addr().unwrap().port();
+    thread::spawn(move || {
+        let mut buf = [0u8; 1500];
+        loop {
+            let (len, addr) = socket.recv_from(&mut buf).unwrap();
+            let request = &buf[..len];
+            assert_eq!(&request[..2], &[0xfe, 0xfd]);
+            let kind = request[2];
+            let session = &request[3..7];
+            let mut response = vec![kind];
+            response.extend_from_slice(session);
+            match (kind, len) {
+                (TYPE_HANDSHAKE, 7) => response.extend_from_slice(b"9513307\0"),
+                (TYPE_STAT, 11) => {
+                    assert_eq!(&request[7..11], &TOKEN.to_be_bytes());
+                    response
+                        .extend_from_slice(b"\xc2\xa7aA Minecraft Server\0SMP\0world\x002\x0020\0");
+                    response.extend_from_slice(&25565u16.to_le_bytes());
+                    response.extend_from_slice(b"127.0.0.1\0");
+                }
+                (TYPE_STAT, 15) => {
+                    assert_eq!(&request[7..11], &TOKEN.to_be_bytes());
+                    response.extend_from_slice(b"splitnum\0\x80\0");
+                    for (key, value) in [
+                        ("hostname", "A Minecraft Server"),
+                        ("gametype", "SMP"),
+                        ("game_id", "MINECRAFT"),
+                        ("version", "1.20.1"),
+                        (
+                            "plugins",
+                            "Paper on 1.20.1: WorldEdit 7.2.15; LuckPerms 5.4.102",
+                        ),
+                        ("map", "world"),
+                        ("numplayers", "3"),
+                        ("maxplayers", "20"),
+                        ("hostport", "25565"),
+                        ("hostip", "127.0.0.1"),
+                    ] {
+                        response.extend_from_slice(key.as_bytes());
+                        response.push(0);
+                        response.extend_from_slice(value.as_bytes());
+                        response.push(0);
+                    }
+                    response.push(0);
+                    response.extend_from_slice(b"\x01player_\0\0Steve\0Alex\0Notch\0\0");
+                }
+                _ => continue,
+            }
+            socket.send_to(&response, addr).unwrap();
+        }
+    });
+    port
+}
+
+/// 测试握手, 基本状态和完整状态
+#[test]
+fn test_query() {
+    let port = serve();
+    let client = QueryClient::connect(("127.0.0.1", port), DEFAULT_TIMEOUT).expect("test_query()");
+    assert_eq!(client.handshake().expect("test_query()"), TOKEN);
+
+    let basic = client.basic_stat().expect("test_query()");
+    assert_eq!(basic.motd, "A Minecraft Server");
+    assert_eq!(basic.map, "world");
+    assert_eq!((basic.online, basic.max), (2, 20));
+    assert_eq!(basic.host_port, 25565);
+
+    let full = client.full_stat().expect("test_query()");
+    assert_eq!(full.version, "1.20.1");
+    assert_eq!(full.server_mod, "Paper on 1.20.1");
+    assert_eq!(full.plugins, ["WorldEdit 7.2.15", "LuckPerms 5.4.102"]);
+    assert_eq!((full.online, full.max), (3, 20));
+    assert_eq!(full.players, ["Steve", "Alex", "Notch"]);
+}
+
+/// 测试从server.properties读取query.port
+#[test]
+fn test_connect_server() {
+    let dir = tempfile::tempdir().expect("test_connect_server()");
+    let workspace = Workspace::new(dir.path());
+    Workspace::set_current(workspace.clone());
+    let server_dir = workspace.server_dir("test");
+    fs::create_dir_all(&server_dir).unwrap();
+    assert!(connect_server("test", DEFAULT_TIMEOUT).is_err());
+
+    let port = serve();
+    fs::write(
+        server_dir.join("server.properties"),
+        format!("enable-query=true\nserver-port=25570\nquery.port={port}\n"),
+    )
+    .unwrap();
+    let client = connect_server("test", DEFAULT_TIMEOUT).expect("test_connect_server()");
+    assert_eq!(
+        client.full_stat().expect("test_connect_server()").map,
+        "world"
+    );
+}
@@ -0,0 +1,135 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::{
+    fs,
+    io::Write,
+    net::{TcpListener, TcpStream},
+    thread,
+};
+
+use mcscs::{
+    properties::{Properties, PROPERTIES_FILE},
+    rcon::{
+        connect_server, enable_rcon, Packet, RconClient, TYPE_COMMAND, TYPE_LOGIN, TYPE_RESPONSE,
+    },
+    workspace::Workspace,
+    Error,
+};
+
+/// 和Minecraft一样的RCON服务器, 长的返回会分成多个数据包
+fn serve(listener: TcpListener, password: &'static str) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream: TcpStream = stream.unwrap();
+            thread::spawn(move || {
+                while let Ok(packet) = Packet::read(&mut stream) {
+                    let replies = match packet.kind {
+                        TYPE_LOGIN if packet.body == password => {
+                            vec![Packet::new(packet.id, TYPE_COMMAND, "")]
+                        }
+                        TYPE_LOGIN => vec![Packet::new(-1, TYPE_COMMAND, "")],
+                        TYPE_COMMAND if packet.body == "help" => (0..3)
+                            .map(|i| Packet::new(packet.id, TYPE_RESPONSE, "x".repeat(4096 - i)))
+                            .collect(),
+                        TYPE_COMMAND => vec![Packet::new(
+                            packet.id,
+                            TYPE_RESPONSE,
+                            format!("ran {}", packet.body),
+                        )],
+                        kind => vec![Packet::new(
+                            packet.id,
+                            TYPE_RESPONSE,
+                            format!("Unknown request {kind:x}"),
+                        )],
+                    };
+                    for reply in replies {
+                        stream.write_all(&reply.encode()).unwrap();
+                    }
+                }
+            });
+        }
+    })
+}
+
+/// 测试数据包的编码和解码
+#[test]
+fn test_packet() {
+    let packet = Packet::new(42, TYPE_COMMAND, "say 你好");
+    let bytes = packet.encode();
+    assert_eq!(Packet::read(&mut bytes.as_slice()).unwrap(), packet);
+    // 长度无效
+    let mut bytes = bytes;
+    bytes[..4].copy_from_slice(&100000i32.to_le_bytes());
+    assert!(matches!(
+        Packet::read(&mut bytes.as_slice()),
+        Err(Error::Rcon(_))
+    ));
+}
+
+/// 测试登录和执行命令
+#[test]
+fn test_rcon_client() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    serve(listener, "secret");
+
+    assert!(matches!(
+        RconClient::connect(addr, "wrong"),
+        Err(Error::Rcon(_))
+    ));
+    let mut client = RconClient::connect(addr, "secret").expect("test_rcon_client()");
+    assert_eq!(client.command("list").unwrap(), "ran list");
+    // 分成多个数据包的返回
+    assert_eq!(client.command("help").unwrap().len(), 4096 * 3 - 3);
+    assert_eq!(
+        client.command("whitelist add Steve").unwrap(),
+        "ran whitelist add Steve"
+    );
+}
+
+/// 测试启用RCON时为每个服务器选择不同的端口, 并通过server.properties连接
+#[test]
+fn test_enable_rcon() {
+    let dir = tempfile::tempdir().expect("test_enable_rcon()");
+    let workspace = Workspace::new(dir.path());
+    Workspace::set_current(workspace.clone());
+    for name in ["a", "b"] {
+        fs::create_dir_all(workspace.server_dir(name)).unwrap();
+        fs::write(
+            workspace.server_dir(name).join("config.json"),
+            format!(
+                r#"{{"schema_version": 1, "name": "{name}", "java": {{"path": "java", "version": "17"}},
+                "jvm_args": [], "encoding": "utf-8", "Xms": 1024, "Xmx": 1024,
+                "info": {{"core": "Paper", "mc_version": "1.20.1", "build_version": "build1"}},
+                "server_args": []}}"#
+            ),
+        )
+        .unwrap();
+    }
+    assert!(matches!(connect_server("a"), Err(Error::Rcon(_))));
+
+    let (port_a, password_a) = enable_rcon("a").expect("test_enable_rcon()");
+    let (port_b, _) = enable_rcon("b").expect("test_enable_rcon()");
+    assert_ne!(port_a, port_b);
+    assert_eq!(password_a.len(), 20);
+    // 再次启用时不修改已有的端口和密码
+    assert_eq!(enable_rcon("a").unwrap(), (port_a, password_a.clone()));
+
+    // 用一个测试服务器代替Minecraft
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let path = workspace.server_dir("a").join(PROPERTIES_FILE);
+    let mut properties = Properties::load(&path).unwrap();
+    properties.set("rcon.port", &port.to_string());
+    properties.set("rcon.password", "secret");
+    properties.save(&path).unwrap();
+    serve(listener, "secret");
+    let mut client = connect_server("a").expect("test_enable_rcon()");
+    assert_eq!(client.command("save-all").unwrap(), "ran save-all");
+}
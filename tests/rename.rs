@@ -0,0 +1,82 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+mod common;
+
+use std::fs;
+
+use common::FakeServer;
+use mcscs::{
+    backup::{create_backup, list_backups, BackupFormat, BackupSettings},
+    pages::config::rename_server,
+    server::{load_servers_lists, save_servers_lists, ServerConfig},
+    workspace::Workspace,
+};
+
+const CONFIG: &str = r#"{
+    "schema_version": 1,
+    "name": "survival",
+    "java": {"path": "/usr/bin/java", "version": "17.0.9"},
+    "jvm_args": ["-Dlog4j2.formatMsgNoLookups=true"],
+    "encoding": "utf-8",
+    "Xms": 1073741824,
+    "Xmx": 4294967296,
+    "info": {"core": "Paper", "mc_version": "1.20.1", "build_version": "build196"},
+    "server_args": ["--nogui"]
+}"#;
+
+/// 测试重命名服务器: 移动服务器文件夹和备份, 修改配置里的名称, 运行时拒绝重命名
+#[test]
+fn test_rename_server() {
+    let dir = tempfile::tempdir().expect("test_rename_server()");
+    let workspace = Workspace::new(dir.path());
+    Workspace::set_current(workspace.clone());
+    let server_dir = workspace.server_dir("survival");
+    fs::create_dir_all(server_dir.join("world")).unwrap();
+    save_servers_lists("survival", &ServerConfig::from_json(CONFIG).unwrap()).unwrap();
+    fs::write(server_dir.join("world/level.dat"), "level").unwrap();
+    let backup = create_backup("survival", BackupFormat::TarZst).expect("test_rename_server()");
+    let settings = BackupSettings {
+        interval: Some(3600),
+        ..Default::default()
+    };
+    settings.save("survival").unwrap();
+
+    // 服务器正在运行时拒绝重命名, 什么都不会改变
+    let _server = FakeServer::start(&server_dir);
+    assert!(rename_server("survival", "lobby").is_err());
+    assert!(server_dir.join("config.json").exists());
+    assert!(!workspace.server_dir("lobby").exists());
+
+    // 已经退出的服务器留下的PID文件会被删除
+    fs::write(server_dir.join("server.pid"), "4194304").unwrap();
+    let renamed = rename_server("survival", "lobby").expect("test_rename_server()");
+    assert_eq!(renamed.name, "lobby");
+    let servers = load_servers_lists(None).unwrap();
+    assert_eq!(servers.keys().collect::<Vec<_>>(), ["lobby"]);
+    assert_eq!(servers["lobby"], renamed);
+    let lobby_dir = workspace.server_dir("lobby");
+    assert!(!server_dir.exists());
+    assert!(!lobby_dir.join("server.pid").exists());
+    assert_eq!(
+        fs::read_to_string(lobby_dir.join("world/level.dat")).unwrap(),
+        "level"
+    );
+    assert!(!workspace.backup_dir("survival").exists());
+    let backups = list_backups("lobby").unwrap();
+    assert_eq!(backups.len(), 1);
+    assert_eq!(backups[0].id, backup.id);
+    assert_eq!(BackupSettings::load("lobby").unwrap(), settings);
+
+    // 新的名称已被使用时拒绝重命名
+    fs::create_dir_all(&server_dir).unwrap();
+    save_servers_lists("survival", &ServerConfig::from_json(CONFIG).unwrap()).unwrap();
+    assert!(rename_server("survival", "lobby").is_err());
+    assert!(rename_server("missing", "other").is_err());
+    assert!(rename_server("survival", " ").is_err());
+}
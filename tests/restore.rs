@@ -0,0 +1,116 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+mod common;
+
+use std::fs;
+
+use common::FakeServer;
+use mcscs::{
+    backup::{create_backup, list_backups, restore_backup, restore_to_new_server, BackupFormat},
+    properties::Properties,
+    server::{load_servers_lists, save_servers_lists, ServerConfig},
+    workspace::Workspace,
+};
+
+const CONFIG: &str = r#"{
+    "schema_version": 1,
+    "name": "survival",
+    "java": {"path": "/usr/bin/java", "version": "17.0.9"},
+    "jvm_args": ["-Dlog4j2.formatMsgNoLookups=true"],
+    "encoding": "utf-8",
+    "Xms": 1073741824,
+    "Xmx": 4294967296,
+    "info": {"core": "Paper", "mc_version": "1.20.1", "build_version": "build196"},
+    "server_args": ["--nogui"]
+}"#;
+
+/// 测试恢复备份: 恢复前自动备份, 替换世界文件夹, 运行时拒绝恢复, 恢复为新的服务器
+#[test]
+fn test_restore_backup() {
+    let dir = tempfile::tempdir().expect("test_restore_backup()");
+    let workspace = Workspace::new(dir.path());
+    Workspace::set_current(workspace.clone());
+    let server_dir = workspace.server_dir("survival");
+    fs::create_dir_all(server_dir.join("world")).unwrap();
+    fs::create_dir_all(server_dir.join("plugins")).unwrap();
+    save_servers_lists("survival", &ServerConfig::from_json(CONFIG).unwrap()).unwrap();
+    fs::write(
+        server_dir.join("server.properties"),
+        "server-port=25565\nenable-rcon=true\nrcon.port=25575\n",
+    )
+    .unwrap();
+    fs::write(server_dir.join("plugins/plugin.jar"), "plugin").unwrap();
+    fs::write(server_dir.join("world/level.dat"), "v1").unwrap();
+    let backup = create_backup("survival", BackupFormat::TarZst).expect("test_restore_backup()");
+
+    // 备份之后生成了下界
+    fs::write(server_dir.join("world/level.dat"), "v2").unwrap();
+    fs::create_dir_all(server_dir.join("world_nether")).unwrap();
+    let snapshot = restore_backup("survival", &backup.id, false)
+        .expect("test_restore_backup()")
+        .unwrap();
+    assert_ne!(snapshot.id, backup.id);
+    assert_eq!(
+        fs::read_to_string(server_dir.join("world/level.dat")).unwrap(),
+        "v1"
+    );
+    assert!(!server_dir.join("world_nether").exists());
+    assert_eq!(list_backups("survival").unwrap().len(), 2);
+    let leftovers = fs::read_dir(&server_dir)
+        .unwrap()
+        .filter(|entry| {
+            entry
+                .as_ref()
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .starts_with(".restore-")
+        })
+        .count();
+    assert_eq!(leftovers, 0);
+
+    // 恢复前的备份可以撤销这次恢复
+    restore_backup("survival", &snapshot.id, false).expect("test_restore_backup()");
+    assert_eq!(
+        fs::read_to_string(server_dir.join("world/level.dat")).unwrap(),
+        "v2"
+    );
+    assert!(server_dir.join("world_nether").is_dir());
+    assert!(restore_backup("survival", "20000101-000000", false).is_err());
+
+    // 服务器正在运行时拒绝恢复
+    let _server = FakeServer::start(&server_dir);
+    assert!(restore_backup("survival", &backup.id, false).is_err());
+    assert_eq!(
+        fs::read_to_string(server_dir.join("world/level.dat")).unwrap(),
+        "v2"
+    );
+
+    // 恢复为新的服务器, 不复制运行时的文件, 使用其他端口
+    let restored =
+        restore_to_new_server("survival", &backup.id, "restored").expect("test_restore_backup()");
+    assert_eq!(restored.name, "restored");
+    assert_eq!(
+        load_servers_lists(Some("restored")).unwrap()["restored"],
+        restored
+    );
+    let restored_dir = workspace.server_dir("restored");
+    assert_eq!(
+        fs::read_to_string(restored_dir.join("world/level.dat")).unwrap(),
+        "v1"
+    );
+    assert!(restored_dir.join("plugins/plugin.jar").exists());
+    assert!(!restored_dir.join("world_nether").exists());
+    assert!(!restored_dir.join("server.pid").exists());
+    let properties = Properties::load(&restored_dir.join("server.properties")).unwrap();
+    assert_ne!(properties.get("server-port"), Some("25565"));
+    assert_ne!(properties.get("rcon.port"), Some("25575"));
+    assert_ne!(properties.get("server-port"), properties.get("rcon.port"));
+    assert!(restore_to_new_server("survival", &backup.id, "restored").is_err());
+}
@@ -7,20 +7,84 @@
  */
 
 use mcscs::{
-    pages::{choose_server, init},
-    server::load_servers_lists,
+    pages::init,
+    provider::ProviderKind,
+    select::select_server,
+    server::{load_servers_lists, validate_name, ServerConfig},
 };
 
 #[tokio::test]
 async fn test_get_server_config() {
     init::main().await.expect("main()");
-    let data = load_servers_lists(Some(
-        choose_server()["name"]
-            .as_str()
-            .expect("test_get_server_config()"),
-    ));
+    let server = select_server()
+        .expect("test_get_server_config()")
+        .expect("test_get_server_config()");
+    let data = load_servers_lists(Some(&server.name)).expect("test_get_server_config()");
     println!(
         "data: {}",
         serde_json::to_string_pretty(&data).expect("test_get_server_config()")
     );
 }
+
+const VALID_CONFIG: &str = r#"{
+    "name": "test",
+    "java": {"path": "/usr/bin/java", "version": "17.0.9"},
+    "jvm_args": ["-Dlog4j2.formatMsgNoLookups=true"],
+    "encoding": "utf-8",
+    "Xms": 1073741824,
+    "Xmx": 2147483648,
+    "info": {"core": "Mohist", "mc_version": "1.20.1", "build_version": "build593"},
+    "server_args": ["--nogui"]
+}"#;
+
+/// 测试解析服务器配置
+#[test]
+fn test_parse_server_config() {
+    let config = ServerConfig::from_json(VALID_CONFIG).expect("test_parse_server_config()");
+    assert_eq!(config.name, "test");
+    assert_eq!(config.xmx, 2147483648);
+    assert_eq!(config.info.build_version, "build593");
+    // 没有记录来源的配置是从FastMirror创建的
+    assert_eq!(config.info.provider, ProviderKind::FastMirror);
+
+    let config = ServerConfig::from_json(&VALID_CONFIG.replace(
+        r#""build_version": "build593""#,
+        r#""build_version": "release", "provider": "mojang""#,
+    ))
+    .expect("test_parse_server_config()");
+    assert_eq!(config.info.provider, ProviderKind::Mojang);
+}
+
+/// 测试无效的服务器配置会指出出错的配置项
+#[test]
+fn test_invalid_server_config() {
+    let err = ServerConfig::from_json(&VALID_CONFIG.replace(r#""/usr/bin/java""#, "17"))
+        .expect_err("test_invalid_server_config()");
+    assert_eq!(err.field, "java.path");
+
+    let err = ServerConfig::from_json(&VALID_CONFIG.replace(r#""encoding": "utf-8","#, ""))
+        .expect_err("test_invalid_server_config()");
+    assert_eq!(err.field, "encoding");
+
+    let err = ServerConfig::from_json(&VALID_CONFIG.replace("2147483648", "1024"))
+        .expect_err("test_invalid_server_config()");
+    assert_eq!(err.field, "Xmx");
+}
+
+/// 测试服务器名称不能跳出`MCSCS/servers`或者使用保留名称
+#[test]
+fn test_validate_name() {
+    for name in ["survival", "lobby-1", "生存服", "con-test", "my.server"] {
+        assert!(validate_name(name).is_ok(), "{name}");
+    }
+    for name in [
+        "", " ", "../x", "a/b", "a\\b", "..", ".hidden", "CON", "nul.txt", "com1", "a\nb",
+    ] {
+        assert!(validate_name(name).is_err(), "{name}");
+    }
+
+    let err =
+        ServerConfig::from_json(&VALID_CONFIG.replace(r#""name": "test""#, r#""name": "../x""#))
+            .expect_err("test_validate_name()");
+    assert_eq!(err.field, "name");
+}
@@ -0,0 +1,156 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+#![cfg(unix)]
+
+mod common;
+
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::TcpStream,
+    process, thread,
+    time::Duration,
+};
+
+use common::sh_server;
+use mcscs::{
+    server::ServerConfig,
+    session::{host, Session, SESSION_FILE},
+    workspace::Workspace,
+};
+
+/// 用sh代替Java的"服务器", 把输入原样输出, 收到stop时退出
+fn echo_server(name: &str) -> ServerConfig {
+    sh_server(
+        name,
+        r#"echo started; while read l; do echo "got $l"; [ "$l" = stop ] && exit 0; done"#,
+    )
+}
+
+/// 测试宿主接管服务器的输入输出, 发送命令, 连接控制台和记录日志
+#[test]
+fn test_host_session() {
+    let dir = tempfile::tempdir().expect("test_host_session()");
+    let workspace = Workspace::new(dir.path());
+    Workspace::set_current(workspace.clone());
+    let server_dir = workspace.server_dir("echo");
+    fs::create_dir_all(&server_dir).unwrap();
+    fs::write(server_dir.join("eula.txt"), "eula=true").unwrap();
+
+    let handle = thread::spawn(|| host(&echo_server("echo"), None));
+    let session = loop {
+        if let Some(session) = Session::load("echo").unwrap() {
+            if session.server_pid != 0 {
+                break session;
+            }
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+
+    session
+        .send_command("say hello")
+        .expect("test_host_session()");
+
+    // 只有自己可以读取令牌
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(server_dir.join(SESSION_FILE))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    // 令牌错误时拒绝
+    let mut stream = TcpStream::connect(("127.0.0.1", session.port)).unwrap();
+    writeln!(stream, "COMMAND wrong stop").unwrap();
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply).unwrap();
+    assert!(reply.starts_with("ERR"));
+
+    // 连接后先收到之前的输出, 然后可以输入命令
+    let mut stream = TcpStream::connect(("127.0.0.1", session.port)).unwrap();
+    writeln!(stream, "ATTACH {}", session.token).unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut lines = Vec::new();
+    for _ in 0..2 {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        lines.push(line);
+    }
+    assert_eq!(lines, ["started\n", "got say hello\n"]);
+    writeln!(stream, "stop").unwrap();
+
+    handle.join().unwrap().expect("test_host_session()");
+    // 宿主退出前会把剩下的输出发送给连接的客户端
+    let rest = reader.lines().map(Result::unwrap).collect::<Vec<_>>();
+    assert!(rest
+        .iter()
+        .any(|line| line.starts_with("[mcscs] 服务器已退出")));
+    assert!(!server_dir.join(SESSION_FILE).exists());
+    assert!(Session::load("echo").unwrap().is_none());
+    let log = fs::read_to_string(&session.log).unwrap();
+    assert!(log.contains("> say hello\ngot say hello\n"));
+    assert!(log.contains("got stop\n"));
+
+    // 宿主进程还在但是还没有开始监听时保留session.json, 宿主进程已经退出时删除
+    let port = std::net::TcpListener::bind(("127.0.0.1", 0))
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let starting = Session {
+        host_pid: process::id(),
+        port,
+        ..session
+    };
+    fs::write(
+        server_dir.join(SESSION_FILE),
+        serde_json::to_string(&starting).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(Session::load("echo").unwrap(), Some(starting.clone()));
+    let stale = Session {
+        host_pid: 4194304,
+        ..starting
+    };
+    fs::write(
+        server_dir.join(SESSION_FILE),
+        serde_json::to_string(&stale).unwrap(),
+    )
+    .unwrap();
+    assert!(Session::load("echo").unwrap().is_none());
+    assert!(!server_dir.join(SESSION_FILE).exists());
+
+    // 按服务器配置的编码解码输出和编码命令
+    let server_dir = workspace.server_dir("gbk");
+    fs::create_dir_all(&server_dir).unwrap();
+    fs::write(server_dir.join("eula.txt"), "eula=true").unwrap();
+    // 先输出GBK编码的"你好"
+    let mut server = ServerConfig {
+        encoding: "gbk".to_string(),
+        ..echo_server("gbk")
+    };
+    server.jvm_args[1] = format!(r"printf '\304\343\272\303\n'; {}", server.jvm_args[1]);
+    let handle = thread::spawn(move || host(&server, None));
+    let session = loop {
+        if let Some(session) = Session::load("gbk").unwrap() {
+            if session.server_pid != 0 {
+                break session;
+            }
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    session.send_command("say 你好").unwrap();
+    session.send_command("stop").unwrap();
+    handle.join().unwrap().expect("test_host_session()");
+    let log = fs::read_to_string(&session.log).unwrap();
+    assert!(log.starts_with("你好\nstarted\n"));
+    assert!(log.contains("> say 你好\ngot say 你好\n"));
+}
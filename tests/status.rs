@@ -0,0 +1,152 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::{
+    fs,
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+};
+
+use mcscs::{
+    status::{
+        legacy_ping, ping, query_status, read_varint, server_address, write_varint, DEFAULT_TIMEOUT,
+    },
+    workspace::Workspace,
+};
+use serde_json::json;
+
+/// 读取一个1.7协议的数据包, 返回ID和内容
+fn read_packet(stream: &mut TcpStream) -> (i32, Vec<u8>) {
+    let len = read_varint(stream).unwrap();
+    let mut data = vec![0u8; len as usize];
+    stream.read_exact(&mut data).unwrap();
+    let mut reader = data.as_slice();
+    let id = read_varint(&mut reader).unwrap();
+    (id, reader.to_vec())
+}
+
+fn write_packet(stream: &mut TcpStream, id: i32, data: &[u8]) {
+    let mut body = Vec::new();
+    write_varint(&mut body, id);
+    body.extend_from_slice(data);
+    let mut packet = Vec::new();
+    write_varint(&mut packet, body.len() as i32);
+    packet.extend_from_slice(&body);
+    stream.write_all(&packet).unwrap();
+}
+
+/// 1.7及以上版本的服务器
+fn modern_server(stream: &mut TcpStream) {
+    let (id, handshake) = read_packet(stream);
+    assert_eq!(id, 0x00);
+    // 下一个状态是status
+    assert_eq!(handshake.last(), Some(&1));
+    assert_eq!(read_packet(stream), (0x00, Vec::new()));
+    let status = json!({
+        "version": {"name": "Paper 1.20.1", "protocol": 763},
+        "players": {"max": 20, "online": 2, "sample": [{"name": "Steve", "id": "0"}, {"name": "Alex", "id": "1"}]},
+        "description": {"text": "§aA ", "extra": [{"text": "Minecraft Server"}]}
+    })
+    .to_string();
+    let mut data = Vec::new();
+    write_varint(&mut data, status.len() as i32);
+    data.extend_from_slice(status.as_bytes());
+    write_packet(stream, 0x00, &data);
+    let (id, payload) = read_packet(stream);
+    assert_eq!(id, 0x01);
+    write_packet(stream, 0x01, &payload);
+}
+
+/// 1.6的服务器, 不认识新协议时直接断开连接
+fn legacy_server(stream: &mut TcpStream) {
+    let mut request = [0u8; 3];
+    stream.read_exact(&mut request).unwrap();
+    if request != [0xfe, 0x01, 0xfa] {
+        return;
+    }
+    let response = "§1\x0073\x001.6.1\x00§lLegacy Server\x005\x0010";
+    let units = response.encode_utf16().collect::<Vec<u16>>();
+    let mut data = vec![0xff];
+    data.extend_from_slice(&(units.len() as u16).to_be_bytes());
+    for unit in units {
+        data.extend_from_slice(&unit.to_be_bytes());
+    }
+    stream.write_all(&data).unwrap();
+}
+
+/// 在本地启动一个测试服务器, 返回端口
+fn serve(handler: fn(&mut TcpStream)) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            handler(&mut stream.unwrap());
+        }
+    });
+    port
+}
+
+/// 测试1.7及以上版本的协议
+#[test]
+fn test_ping() {
+    let port = serve(modern_server);
+    let status = ping("127.0.0.1", port, DEFAULT_TIMEOUT).expect("test_ping()");
+    assert_eq!(status.version, "Paper 1.20.1");
+    assert_eq!(status.protocol, 763);
+    assert_eq!((status.online, status.max), (2, 20));
+    assert_eq!(status.players, ["Steve", "Alex"]);
+    assert_eq!(status.motd, "A Minecraft Server");
+}
+
+/// 测试1.6的旧协议, 以及新协议失败时使用旧协议
+#[test]
+fn test_legacy_ping() {
+    let port = serve(legacy_server);
+    let status = legacy_ping("127.0.0.1", port, DEFAULT_TIMEOUT).expect("test_legacy_ping()");
+    assert_eq!(status.version, "1.6.1");
+    assert_eq!(status.motd, "Legacy Server");
+    assert_eq!((status.online, status.max), (5, 10));
+    assert_eq!(
+        query_status("127.0.0.1", port, DEFAULT_TIMEOUT).expect("test_legacy_ping()"),
+        status
+    );
+}
+
+/// 测试服务器没有运行时返回错误
+#[test]
+fn test_offline() {
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    assert!(query_status("127.0.0.1", port, DEFAULT_TIMEOUT).is_err());
+}
+
+/// 测试从server.properties读取地址
+#[test]
+fn test_server_address() {
+    let dir = tempfile::tempdir().expect("test_server_address()");
+    let workspace = Workspace::new(dir.path());
+    Workspace::set_current(workspace.clone());
+    fs::create_dir_all(workspace.server_dir("test")).unwrap();
+    assert_eq!(
+        server_address("test").unwrap(),
+        ("127.0.0.1".to_string(), 25565)
+    );
+    fs::write(
+        workspace.server_dir("test").join("server.properties"),
+        "server-ip=\nserver-port=25570\n",
+    )
+    .unwrap();
+    assert_eq!(
+        server_address("test").unwrap(),
+        ("127.0.0.1".to_string(), 25570)
+    );
+}
@@ -0,0 +1,145 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::{
+    fs::{self, File},
+    time::{Duration, SystemTime},
+};
+
+use mcscs::{
+    backup::{create_backup, list_backups, prune_backups, prune_store, BackupFormat, Retention},
+    server::{save_servers_lists, ServerConfig},
+    store::{Snapshot, Store, CHUNK_SIZE},
+    workspace::Workspace,
+};
+
+const CONFIG: &str = r#"{
+    "schema_version": 1,
+    "name": "survival",
+    "java": {"path": "/usr/bin/java", "version": "17.0.9"},
+    "jvm_args": [],
+    "encoding": "utf-8",
+    "Xms": 1073741824,
+    "Xmx": 4294967296,
+    "info": {"core": "Paper", "mc_version": "1.20.1", "build_version": "build196"},
+    "server_args": ["--nogui"]
+}"#;
+
+/// 测试快照: 没有变化的文件不重复保存, 还原后内容相同, 检查能发现缺少和损坏的块
+#[test]
+fn test_snapshot() {
+    let dir = tempfile::tempdir().expect("test_snapshot()");
+    let store = Store::new(dir.path().join("store"));
+    let base = dir.path().join("server");
+    fs::create_dir_all(base.join("world/region")).unwrap();
+    fs::create_dir_all(base.join("world/empty")).unwrap();
+    let region = (0..CHUNK_SIZE * 2 + 10)
+        .map(|i| (i % 251) as u8)
+        .collect::<Vec<_>>();
+    fs::write(base.join("world/region/r.0.0.mca"), &region).unwrap();
+    fs::write(base.join("world/level.dat"), "v1").unwrap();
+    fs::write(base.join("world/session.lock"), "lock").unwrap();
+    let dirs = ["world".to_string()];
+    let exclude = |path: &str| path.ends_with("/session.lock");
+
+    let first = Snapshot::create(&store, &base, &dirs, None, &exclude).expect("test_snapshot()");
+    assert!(first.added > 0);
+    assert_eq!(first.files.len(), 2);
+    assert_eq!(first.files[1].chunks.len(), 3);
+
+    // 只有level.dat变化了, 区域文件不占用新的空间
+    fs::write(base.join("world/level.dat"), "v2").unwrap();
+    let second =
+        Snapshot::create(&store, &base, &dirs, Some(&first), &exclude).expect("test_snapshot()");
+    assert_eq!(second.files[1].chunks, first.files[1].chunks);
+    assert!(second.added < 100);
+    assert!(second.verify(&store).is_empty());
+
+    let restored = dir.path().join("restored");
+    first.checkout(&store, &restored).expect("test_snapshot()");
+    assert_eq!(
+        fs::read(restored.join("world/region/r.0.0.mca")).unwrap(),
+        region
+    );
+    assert_eq!(
+        fs::read_to_string(restored.join("world/level.dat")).unwrap(),
+        "v1"
+    );
+    assert!(restored.join("world/empty").is_dir());
+    assert!(!restored.join("world/session.lock").exists());
+
+    // 损坏和缺少的块都会被发现
+    let objects = dir.path().join("store/objects");
+    let object = |hash: &str| objects.join(&hash[..2]).join(hash);
+    fs::write(object(&first.files[1].chunks[0]), "broken").unwrap();
+    fs::remove_file(object(&first.files[0].chunks[0])).unwrap();
+    assert_eq!(first.verify(&store).len(), 2);
+    assert!(first.checkout(&store, &restored).is_err());
+}
+
+/// 测试增量备份和清理存储里没有使用的块
+#[test]
+fn test_incremental_backup() {
+    let dir = tempfile::tempdir().expect("test_incremental_backup()");
+    let workspace = Workspace::new(dir.path());
+    Workspace::set_current(workspace.clone());
+    let server_dir = workspace.server_dir("survival");
+    fs::create_dir_all(server_dir.join("world/region")).unwrap();
+    save_servers_lists("survival", &ServerConfig::from_json(CONFIG).unwrap()).unwrap();
+    fs::write(server_dir.join("world/region/r.0.0.mca"), "region").unwrap();
+    fs::write(server_dir.join("world/level.dat"), "v1").unwrap();
+
+    let first =
+        create_backup("survival", BackupFormat::Incremental).expect("test_incremental_backup()");
+    assert_eq!(first.format, BackupFormat::Incremental);
+    assert!(first.verify().unwrap().is_empty());
+    fs::write(server_dir.join("world/level.dat"), "v2").unwrap();
+    let second =
+        create_backup("survival", BackupFormat::Incremental).expect("test_incremental_backup()");
+    assert_ne!(first.id, second.id);
+    let extracted = dir.path().join("extracted");
+    first
+        .extract(&extracted)
+        .expect("test_incremental_backup()");
+    assert_eq!(
+        fs::read_to_string(extracted.join("world/level.dat")).unwrap(),
+        "v1"
+    );
+
+    // 删除旧的备份后, 只有它用到的块会被清理, 而且刚写入的块不会被删除
+    let retention = Retention {
+        hourly: 0,
+        daily: 0,
+        weekly: 0,
+    };
+    assert_eq!(prune_backups("survival", &retention).unwrap().len(), 1);
+    assert_eq!(prune_store().unwrap().0, 0);
+    let objects = workspace.backup_store_dir().join("objects");
+    let old = SystemTime::now() - Duration::from_secs(2 * 60 * 60);
+    for prefix in fs::read_dir(&objects).unwrap() {
+        for entry in fs::read_dir(prefix.unwrap().path()).unwrap() {
+            let file = File::options()
+                .append(true)
+                .open(entry.unwrap().path())
+                .unwrap();
+            file.set_modified(old).unwrap();
+        }
+    }
+    assert_eq!(prune_store().unwrap().0, 1);
+    let backups = list_backups("survival").unwrap();
+    assert_eq!(backups.len(), 1);
+    assert_eq!(backups[0], second);
+    assert!(second.verify().unwrap().is_empty());
+
+    // 损坏的清单仍然会列出, 检查时报告问题
+    fs::write(&second.path, "{").unwrap();
+    let backups = list_backups("survival").unwrap();
+    assert_eq!(backups.len(), 1);
+    assert_eq!(backups[0].id, second.id);
+    assert_eq!(backups[0].verify().unwrap().len(), 1);
+}
@@ -0,0 +1,119 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+mod common;
+
+use std::time::Duration;
+
+use mcscs::supervisor::{
+    backoff, should_restart, ExitKind, RestartHistory, RestartPolicy, RestartRecord,
+};
+
+fn record(exited_at: i64, kind: ExitKind) -> RestartRecord {
+    RestartRecord {
+        started_at: exited_at - 60,
+        exited_at,
+        exit_code: Some(1),
+        kind,
+        restarted: true,
+    }
+}
+
+/// 测试重启等待时间的指数退避
+#[test]
+fn test_backoff() {
+    let policy = RestartPolicy {
+        initial_backoff: Duration::from_secs(2),
+        max_backoff: Duration::from_secs(30),
+        ..Default::default()
+    };
+    let waits = (1..=6)
+        .map(|crashes| backoff(&policy, crashes).as_secs())
+        .collect::<Vec<u64>>();
+    assert_eq!(waits, [2, 4, 8, 16, 30, 30]);
+    // 次数很大时不会溢出
+    assert_eq!(backoff(&policy, u32::MAX), Duration::from_secs(30));
+}
+
+/// 测试只统计时间窗口内的崩溃
+#[test]
+fn test_recent_crashes() {
+    let policy = RestartPolicy {
+        max_restarts: 2,
+        window: Duration::from_secs(100),
+        ..Default::default()
+    };
+    let history = RestartHistory {
+        records: vec![
+            record(0, ExitKind::Crash),
+            record(950, ExitKind::Crash),
+            record(960, ExitKind::Clean),
+            record(990, ExitKind::Crash),
+        ],
+    };
+    let crashes = history.recent_crashes(policy.window, 1000);
+    assert_eq!(crashes, 2);
+    assert!(!should_restart(&policy, crashes + 1));
+    // 很久之后窗口内没有崩溃
+    assert_eq!(history.recent_crashes(policy.window, 5000), 0);
+}
+
+/// 测试保存和读取重启记录
+#[test]
+fn test_restart_history() {
+    let dir = tempfile::tempdir().expect("test_restart_history()");
+    assert_eq!(
+        RestartHistory::load(dir.path()).unwrap(),
+        RestartHistory::default()
+    );
+    let mut history = RestartHistory {
+        records: (0..150).map(|i| record(i, ExitKind::Crash)).collect(),
+    };
+    history.save(dir.path()).unwrap();
+    let loaded = RestartHistory::load(dir.path()).unwrap();
+    assert_eq!(loaded.records.len(), 100);
+    assert_eq!(loaded.records[0].exited_at, 50);
+}
+
+/// 测试用会崩溃的"服务器"运行带自动重启的宿主进程, 用sh代替Java
+#[cfg(unix)]
+#[test]
+fn test_supervise() {
+    use std::fs;
+
+    use common::sh_server;
+    use mcscs::{session::host, workspace::Workspace};
+
+    let dir = tempfile::tempdir().expect("test_supervise()");
+    let workspace = Workspace::new(dir.path());
+    Workspace::set_current(workspace.clone());
+    let policy = RestartPolicy {
+        max_restarts: 2,
+        initial_backoff: Duration::ZERO,
+        ..Default::default()
+    };
+    for name in ["crash", "clean"] {
+        fs::create_dir_all(workspace.server_dir(name)).unwrap();
+        fs::write(workspace.server_dir(name).join("eula.txt"), "eula=true").unwrap();
+    }
+
+    assert!(host(&sh_server("crash", "exit 3"), Some(&policy)).is_err());
+    let history = RestartHistory::load(&workspace.server_dir("crash")).unwrap();
+    let restarted = history
+        .records
+        .iter()
+        .map(|record| record.restarted)
+        .collect::<Vec<bool>>();
+    assert_eq!(restarted, [true, true, false]);
+    assert_eq!(history.records[0].exit_code, Some(3));
+
+    host(&sh_server("clean", "exit 0"), Some(&policy)).expect("test_supervise()");
+    let history = RestartHistory::load(&workspace.server_dir("clean")).unwrap();
+    assert_eq!(history.records.len(), 1);
+    assert_eq!(history.records[0].kind, ExitKind::Clean);
+}
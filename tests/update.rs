@@ -0,0 +1,114 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+mod common;
+
+use std::fs;
+
+use common::FakeServer;
+use mcscs::{
+    pages::update::{check_provider, core_backup, install_core, rollback_core},
+    provider::ProviderKind,
+    server::{load_servers_lists, save_servers_lists, CoreInfo, ServerConfig, CORE_BACKUP_DIR},
+    workspace::Workspace,
+};
+
+const CONFIG: &str = r#"{
+    "schema_version": 1,
+    "name": "survival",
+    "java": {"path": "/usr/bin/java", "version": "17.0.9"},
+    "jvm_args": ["-Dlog4j2.formatMsgNoLookups=true"],
+    "encoding": "utf-8",
+    "Xms": 1073741824,
+    "Xmx": 4294967296,
+    "info": {"core": "Paper", "mc_version": "1.20.1", "build_version": "build196"},
+    "server_args": ["--nogui"]
+}"#;
+
+fn info(build_version: &str) -> CoreInfo {
+    CoreInfo {
+        core: "Paper".to_string(),
+        mc_version: "1.20.1".to_string(),
+        build_version: build_version.to_string(),
+        provider: ProviderKind::FastMirror,
+    }
+}
+
+/// 测试替换核心: 备份原来的核心, 修改配置, 回滚和撤销回滚, 运行时拒绝替换
+#[test]
+fn test_install_core() {
+    let dir = tempfile::tempdir().expect("test_install_core()");
+    let workspace = Workspace::new(dir.path());
+    Workspace::set_current(workspace.clone());
+    let server_dir = workspace.server_dir("survival");
+    fs::create_dir_all(&server_dir).unwrap();
+    save_servers_lists("survival", &ServerConfig::from_json(CONFIG).unwrap()).unwrap();
+    fs::write(server_dir.join("server.jar"), "build196").unwrap();
+    let downloaded = dir.path().join("paper-build197.jar");
+    fs::write(&downloaded, "build197").unwrap();
+    let server_jar = || fs::read_to_string(server_dir.join("server.jar")).unwrap();
+    let current = || load_servers_lists(Some("survival")).unwrap()["survival"].clone();
+    assert_eq!(core_backup("survival").unwrap(), None);
+    assert!(rollback_core("survival").is_err());
+
+    let server =
+        install_core("survival", &downloaded, info("build197")).expect("test_install_core()");
+    assert_eq!(server.info, info("build197"));
+    assert_eq!(current(), server);
+    assert_eq!(server_jar(), "build197");
+    assert!(downloaded.exists());
+    assert_eq!(core_backup("survival").unwrap(), Some(info("build196")));
+    assert_eq!(
+        fs::read_to_string(server_dir.join(CORE_BACKUP_DIR).join("server.jar")).unwrap(),
+        "build196"
+    );
+
+    // 回滚后当前的核心成为新的备份, 再次回滚可以撤销
+    let server = rollback_core("survival").expect("test_install_core()");
+    assert_eq!(server.info, info("build196"));
+    assert_eq!(current().info, info("build196"));
+    assert_eq!(server_jar(), "build196");
+    assert_eq!(core_backup("survival").unwrap(), Some(info("build197")));
+    rollback_core("survival").expect("test_install_core()");
+    assert_eq!(server_jar(), "build197");
+    assert_eq!(current().info, info("build197"));
+
+    // 服务器正在运行时不会替换任何文件
+    let _server = FakeServer::start(&server_dir);
+    assert!(install_core("survival", &downloaded, info("build198")).is_err());
+    assert!(rollback_core("survival").is_err());
+    assert_eq!(server_jar(), "build197");
+    assert_eq!(core_backup("survival").unwrap(), Some(info("build196")));
+    let leftovers = fs::read_dir(&server_dir)
+        .unwrap()
+        .filter(|entry| {
+            let name = entry.as_ref().unwrap().file_name();
+            name.to_string_lossy().contains(".part")
+                || name.to_string_lossy().ends_with(".rollback")
+        })
+        .count();
+    assert_eq!(leftovers, 0);
+}
+
+/// 测试记录的来源不可用时不会使用其他来源的构建版本
+#[test]
+fn test_check_provider() {
+    let fastmirror = info("build196");
+    assert!(check_provider(&fastmirror, ProviderKind::FastMirror).is_ok());
+    assert!(check_provider(&fastmirror, ProviderKind::Mojang).is_err());
+
+    // 从Mojang创建的服务器在Mojang不可用时换成了FastMirror, 它的构建版本都和release不同
+    let mojang = CoreInfo {
+        core: "Vanilla".to_string(),
+        provider: ProviderKind::Mojang,
+        ..info("release")
+    };
+    let err = check_provider(&mojang, ProviderKind::FastMirror).expect_err("test_check_provider()");
+    assert!(err.to_string().contains("release"), "{err}");
+    assert!(check_provider(&mojang, ProviderKind::Mojang).is_ok());
+}
@@ -0,0 +1,56 @@
+/*
+ * Copyright (c) 2024 Arama. Lorem ipsum dolor sit amet, consectetur adipiscing elit.
+ * Morbi non lorem porttitor neque feugiat blandit. Ut vitae ipsum eget quam lacinia accumsan.
+ * Etiam sed turpis ac ipsum condimentum fringilla. Maecenas magna.
+ * Proin dapibus sapien vel ante. Aliquam erat volutpat. Pellentesque sagittis ligula eget metus.
+ * Vestibulum commodo. Ut rhoncus gravida arcu.
+ */
+
+use std::{env, fs};
+
+use mcscs::{
+    server::load_servers_lists,
+    workspace::{Workspace, HOME_ENV},
+};
+
+/// 测试工作目录的优先级, 环境变量是进程共享的所以放在同一个测试里
+#[test]
+fn test_resolve_workspace() {
+    let dir = tempfile::tempdir().expect("test_resolve_workspace()");
+    let config_home = dir.path().join("config");
+    fs::create_dir_all(config_home.join("mcscs")).unwrap();
+    fs::write(
+        config_home.join("mcscs").join("config.json"),
+        r#"{"home": "/srv/mcscs-from-config"}"#,
+    )
+    .unwrap();
+    env::set_var("XDG_CONFIG_HOME", &config_home);
+    env::set_var("APPDATA", &config_home);
+
+    env::remove_var(HOME_ENV);
+    let workspace = Workspace::resolve(None).unwrap();
+    assert_eq!(workspace.root(), "/srv/mcscs-from-config");
+
+    env::set_var(HOME_ENV, "/srv/mcscs-from-env");
+    let workspace = Workspace::resolve(None).unwrap();
+    assert_eq!(workspace.root(), "/srv/mcscs-from-env");
+
+    let workspace = Workspace::resolve(Some(dir.path())).unwrap();
+    assert_eq!(workspace.root(), dir.path());
+    env::remove_var(HOME_ENV);
+
+    // 配置文件无效时返回错误, 不会使用当前目录下的MCSCS
+    fs::write(config_home.join("mcscs").join("config.json"), "{").unwrap();
+    assert!(Workspace::resolve(None).is_err());
+}
+
+/// 测试在临时工作目录里读取服务器配置
+#[test]
+fn test_current_workspace() {
+    let dir = tempfile::tempdir().expect("test_current_workspace()");
+    let workspace = Workspace::new(dir.path());
+    fs::create_dir_all(workspace.server_dir("lobby")).unwrap();
+    Workspace::set_current(workspace.clone());
+    assert_eq!(Workspace::current().unwrap(), workspace);
+    assert!(load_servers_lists(None).unwrap().is_empty());
+}